@@ -4,6 +4,13 @@ mod m20231201_000001_create_users_table;
 mod m20231201_000002_create_user_sessions_table;
 mod m20231201_000003_create_security_events_table;
 mod m20240808_000001_create_watchlist_tables;
+mod m20240815_000001_create_candles_table;
+mod m20240820_000001_widen_candle_symbol;
+mod m20240825_000001_create_funding_rates_table;
+mod m20240901_000001_create_candle_imports_table;
+mod m20240910_000001_create_market_overview_daily_table;
+mod m20240920_000001_create_market_data_coverage_table;
+mod m20241001_000001_widen_candle_decimals;
 
 pub struct Migrator;
 
@@ -15,6 +22,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20231201_000002_create_user_sessions_table::Migration),
             Box::new(m20231201_000003_create_security_events_table::Migration),
             Box::new(m20240808_000001_create_watchlist_tables::Migration),
+            Box::new(m20240815_000001_create_candles_table::Migration),
+            Box::new(m20240820_000001_widen_candle_symbol::Migration),
+            Box::new(m20240825_000001_create_funding_rates_table::Migration),
+            Box::new(m20240901_000001_create_candle_imports_table::Migration),
+            Box::new(m20240910_000001_create_market_overview_daily_table::Migration),
+            Box::new(m20240920_000001_create_market_data_coverage_table::Migration),
+            Box::new(m20241001_000001_widen_candle_decimals::Migration),
         ]
     }
 }
@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建K线表（本地K线存储，按交易所/周期分别保存）
+        manager
+            .create_table(
+                Table::create()
+                    .table(Candles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Candles::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Candles::Symbol).string_len(20).not_null())
+                    .col(ColumnDef::new(Candles::Exchange).string_len(20).not_null())
+                    .col(ColumnDef::new(Candles::Interval).string_len(10).not_null())
+                    .col(ColumnDef::new(Candles::OpenTime).big_integer().not_null())
+                    .col(ColumnDef::new(Candles::Open).decimal_len(20, 8).not_null())
+                    .col(ColumnDef::new(Candles::High).decimal_len(20, 8).not_null())
+                    .col(ColumnDef::new(Candles::Low).decimal_len(20, 8).not_null())
+                    .col(ColumnDef::new(Candles::Close).decimal_len(20, 8).not_null())
+                    .col(
+                        ColumnDef::new(Candles::Volume)
+                            .decimal_len(30, 8)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Candles::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx_candles_series_time")
+                            .col(Candles::Symbol)
+                            .col(Candles::Exchange)
+                            .col(Candles::Interval)
+                            .col(Candles::OpenTime)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Candles::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Candles {
+    Table,
+    Id,
+    Symbol,
+    Exchange,
+    Interval,
+    OpenTime,
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+    CreatedAt,
+}
@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // K线表改用统一格式的交易对（BASE/QUOTE），加宽交易对列
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candles::Table)
+                    .modify_column(ColumnDef::new(Candles::Symbol).string_len(48).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candles::Table)
+                    .modify_column(ColumnDef::new(Candles::Symbol).string_len(20).not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Candles {
+    Table,
+    Symbol,
+}
@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建资金费率历史表（永续合约已结算的资金费率，按交易所分别保存）
+        manager
+            .create_table(
+                Table::create()
+                    .table(FundingRates::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FundingRates::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FundingRates::Symbol)
+                            .string_len(48)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FundingRates::Exchange)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FundingRates::FundingTime)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FundingRates::FundingRate)
+                            .decimal_len(20, 10)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FundingRates::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx_funding_rates_series_time")
+                            .col(FundingRates::Symbol)
+                            .col(FundingRates::Exchange)
+                            .col(FundingRates::FundingTime)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FundingRates::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum FundingRates {
+    Table,
+    Id,
+    Symbol,
+    Exchange,
+    FundingTime,
+    FundingRate,
+    CreatedAt,
+}
@@ -0,0 +1,102 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建K线导入记录表（已导入的数据文件按内容哈希记录，重复导入直接跳过）
+        manager
+            .create_table(
+                Table::create()
+                    .table(CandleImports::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CandleImports::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CandleImports::FileName)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CandleImports::Sha256)
+                            .char_len(64)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CandleImports::Symbol)
+                            .string_len(48)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CandleImports::Exchange)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CandleImports::Interval)
+                            .string_len(10)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CandleImports::Rows).integer().not_null())
+                    .col(
+                        ColumnDef::new(CandleImports::FirstOpenTime)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CandleImports::LastOpenTime)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CandleImports::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx_candle_imports_series_file")
+                            .col(CandleImports::Exchange)
+                            .col(CandleImports::Symbol)
+                            .col(CandleImports::Interval)
+                            .col(CandleImports::Sha256)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CandleImports::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum CandleImports {
+    Table,
+    Id,
+    FileName,
+    Sha256,
+    Symbol,
+    Exchange,
+    Interval,
+    Rows,
+    FirstOpenTime,
+    LastOpenTime,
+    CreatedAt,
+}
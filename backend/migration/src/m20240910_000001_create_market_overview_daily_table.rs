@@ -0,0 +1,90 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建全市场概览日线表（每个UTC日保留当天最后一次快照）
+        manager
+            .create_table(
+                Table::create()
+                    .table(MarketOverviewDaily::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MarketOverviewDaily::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketOverviewDaily::Date)
+                            .date()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketOverviewDaily::TotalMarketCap)
+                            .decimal_len(30, 2)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketOverviewDaily::TotalVolume24h)
+                            .decimal_len(30, 2)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketOverviewDaily::BtcDominance)
+                            .decimal_len(10, 4)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketOverviewDaily::EthDominance)
+                            .decimal_len(10, 4)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MarketOverviewDaily::FearGreedIndex).integer())
+                    .col(ColumnDef::new(MarketOverviewDaily::FearGreedLabel).string_len(32))
+                    .col(
+                        ColumnDef::new(MarketOverviewDaily::Source)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketOverviewDaily::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MarketOverviewDaily::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum MarketOverviewDaily {
+    Table,
+    Id,
+    Date,
+    TotalMarketCap,
+    TotalVolume24h,
+    BtcDominance,
+    EthDominance,
+    FearGreedIndex,
+    FearGreedLabel,
+    Source,
+    UpdatedAt,
+}
@@ -0,0 +1,94 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 创建行情数据同步区间表（已向数据源请求过的时间区间，包括数据源没有数据的区间）
+        manager
+            .create_table(
+                Table::create()
+                    .table(MarketDataCoverage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MarketDataCoverage::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketDataCoverage::Dataset)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketDataCoverage::Symbol)
+                            .string_len(48)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketDataCoverage::Exchange)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketDataCoverage::Interval)
+                            .string_len(10)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketDataCoverage::StartTime)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketDataCoverage::EndTime)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MarketDataCoverage::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .index(
+                        Index::create()
+                            .name("idx_market_data_coverage_series_start")
+                            .col(MarketDataCoverage::Dataset)
+                            .col(MarketDataCoverage::Exchange)
+                            .col(MarketDataCoverage::Symbol)
+                            .col(MarketDataCoverage::Interval)
+                            .col(MarketDataCoverage::StartTime),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MarketDataCoverage::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum MarketDataCoverage {
+    Table,
+    Id,
+    Dataset,
+    Symbol,
+    Exchange,
+    Interval,
+    StartTime,
+    EndTime,
+    UpdatedAt,
+}
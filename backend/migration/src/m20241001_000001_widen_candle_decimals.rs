@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 加宽K线价格和成交量列，低价币和小数成交量保存时不再截断
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candles::Table)
+                    .modify_column(ColumnDef::new(Candles::Open).decimal_len(36, 18).not_null())
+                    .modify_column(ColumnDef::new(Candles::High).decimal_len(36, 18).not_null())
+                    .modify_column(ColumnDef::new(Candles::Low).decimal_len(36, 18).not_null())
+                    .modify_column(
+                        ColumnDef::new(Candles::Close)
+                            .decimal_len(36, 18)
+                            .not_null(),
+                    )
+                    .modify_column(
+                        ColumnDef::new(Candles::Volume)
+                            .decimal_len(36, 18)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candles::Table)
+                    .modify_column(ColumnDef::new(Candles::Open).decimal_len(20, 8).not_null())
+                    .modify_column(ColumnDef::new(Candles::High).decimal_len(20, 8).not_null())
+                    .modify_column(ColumnDef::new(Candles::Low).decimal_len(20, 8).not_null())
+                    .modify_column(ColumnDef::new(Candles::Close).decimal_len(20, 8).not_null())
+                    .modify_column(
+                        ColumnDef::new(Candles::Volume)
+                            .decimal_len(30, 8)
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Candles {
+    Table,
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+}
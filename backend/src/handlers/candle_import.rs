@@ -0,0 +1,109 @@
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::services::candle_import::ImportOptions;
+use crate::services::market_data::interval::Interval;
+use crate::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct CandleImportRequest {
+    /// 导入目录（`CANDLE_IMPORT_DIR`）内的文件或子目录
+    pub path: String,
+    /// 交易对，为空时从文件名识别
+    pub symbol: Option<String>,
+    /// K线周期，为空时从文件名识别
+    pub interval: Option<String>,
+    /// 写入的交易所名称，默认 `binance`
+    pub exchange: Option<String>,
+}
+
+fn error_response(
+    mut response: actix_web::HttpResponseBuilder,
+    message: String,
+) -> Result<HttpResponse> {
+    Ok(response.json(json!({
+        "success": false,
+        "message": message
+    })))
+}
+
+/// 启动K线数据包导入任务，立即返回任务进度
+pub async fn start_candle_import(
+    state: web::Data<AppState>,
+    request: web::Json<CandleImportRequest>,
+) -> Result<HttpResponse> {
+    let symbol = match request
+        .symbol
+        .as_deref()
+        .map(|symbol| state.instruments.resolve_symbol(symbol))
+        .transpose()
+    {
+        Ok(symbol) => symbol,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+    let interval = match request.interval.as_deref() {
+        Some(value) => match Interval::parse(value) {
+            Some(interval) => Some(interval),
+            None => {
+                return error_response(
+                    HttpResponse::BadRequest(),
+                    format!("不支持的时间周期: {}", value),
+                )
+            }
+        },
+        None => None,
+    };
+    let paths = match state.candle_imports.resolve(&request.path) {
+        Ok(paths) if !paths.is_empty() => paths,
+        Ok(_) => {
+            return error_response(
+                HttpResponse::BadRequest(),
+                format!("{} 中没有 .csv 或 .zip 文件", request.path),
+            )
+        }
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+
+    let options = ImportOptions {
+        symbol,
+        interval,
+        exchange: request
+            .exchange
+            .as_deref()
+            .unwrap_or("binance")
+            .to_lowercase(),
+    };
+    println!("📥 开始导入K线: {}（{} 个文件）", request.path, paths.len());
+    let progress = state.candle_imports.start(paths, options);
+    Ok(HttpResponse::Accepted().json(json!({
+        "success": true,
+        "data": progress
+    })))
+}
+
+/// 最近的导入任务
+pub async fn list_candle_imports(state: web::Data<AppState>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(json!({
+        "success": true,
+        "data": state.candle_imports.jobs()
+    })))
+}
+
+/// 查询导入任务进度
+pub async fn get_candle_import(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+    match state.candle_imports.job(&job_id) {
+        Some(progress) => Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "data": progress
+        }))),
+        None => error_response(
+            HttpResponse::NotFound(),
+            format!("导入任务 {} 不存在", job_id),
+        ),
+    }
+}
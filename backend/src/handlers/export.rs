@@ -0,0 +1,285 @@
+use actix_web::http::header;
+use actix_web::web::Bytes;
+use actix_web::{web, HttpResponse, Result};
+use futures_util::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::handlers::market_data::SourceErrors;
+use crate::services::market_data::export::{ExportFormat, KlineEncoder};
+use crate::services::market_data::indicators::IndicatorSpec;
+use crate::services::market_data::interval::Interval;
+use crate::services::market_data::{
+    KlineData, KlineQuery, MarketDataError, MarketDataSource, Symbol,
+};
+use crate::AppState;
+
+/// 每批从K线存储读取的数量，也是 Parquet 的行组大小
+const EXPORT_BATCH_CANDLES: u32 = 5000;
+/// 单次导出最多的K线数量，缺失部分会从数据源补齐，限制区间避免单个请求占满交易所限频
+const MAX_EXPORT_CANDLES: i64 = 100_000;
+
+#[derive(Debug, Deserialize)]
+pub struct KlineExportRequest {
+    pub symbol: String,
+    pub interval: String,
+    /// 区间起点（毫秒时间戳，含）
+    pub start_time: Option<i64>,
+    /// 区间终点（毫秒时间戳，含），默认当前时间
+    pub end_time: Option<i64>,
+    /// 数据源名称，为空时按优先级选择第一个可用的数据源
+    pub source: Option<String>,
+    /// `csv`（默认）、`parquet` 或 `jsonl`
+    pub format: Option<ExportFormat>,
+    /// 附加的指标列，逗号分隔，如 `sma_20,ema_50,rsi_14`
+    pub indicators: Option<String>,
+}
+
+/// 按批读取区间内的K线，每批从上一批最后一根之后开始
+struct ExportCursor {
+    state: web::Data<AppState>,
+    source: Arc<dyn MarketDataSource>,
+    symbol: Symbol,
+    interval: String,
+    /// 区间内没有K线时向后跳过的时长
+    batch_span: i64,
+    next_start: i64,
+    end_time: i64,
+    encoder: Option<KlineEncoder>,
+}
+
+impl ExportCursor {
+    async fn next_batch(&mut self) -> std::result::Result<Vec<KlineData>, MarketDataError> {
+        let query = KlineQuery {
+            symbol: self.symbol.clone(),
+            interval: self.interval.clone(),
+            limit: EXPORT_BATCH_CANDLES,
+            start_time: Some(self.next_start),
+            end_time: Some(self.end_time),
+        };
+        let klines: Vec<KlineData> = self
+            .state
+            .candle_store
+            .get_klines(self.source.as_ref(), &query)
+            .await?
+            .into_iter()
+            .filter(|k| k.timestamp >= self.next_start && k.timestamp <= self.end_time)
+            .collect();
+
+        self.next_start = match klines.last() {
+            Some(last) => last.timestamp + 1,
+            None => self.next_start.saturating_add(self.batch_span),
+        };
+        Ok(klines)
+    }
+
+    /// 下一段要发送的字节，导出结束时返回 `None`
+    async fn next_chunk(&mut self) -> std::result::Result<Option<Vec<u8>>, MarketDataError> {
+        if self.next_start > self.end_time {
+            return match self.encoder.take() {
+                Some(encoder) => encoder.finish().map(Some),
+                None => Ok(None),
+            };
+        }
+        let klines = self.next_batch().await?;
+        match self.encoder.as_mut() {
+            Some(encoder) => encoder.encode(&klines).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// 导出区间K线为 CSV、Parquet 或 JSON Lines
+///
+/// 按批读取K线存储（缺失部分从数据源补齐）并以分块传输发送，
+/// 大区间导出不会把全部数据缓存在内存中。指标从区间起点开始计算，
+/// 前 `周期-1` 行为空。区间最多包含 `MAX_EXPORT_CANDLES` 根K线。
+pub async fn export_klines(
+    state: web::Data<AppState>,
+    query: web::Query<KlineExportRequest>,
+) -> Result<HttpResponse> {
+    let error_response = |mut response: actix_web::HttpResponseBuilder, message: String| {
+        Ok(response.json(serde_json::json!({
+            "success": false,
+            "message": message
+        })))
+    };
+
+    let symbol = match state.instruments.resolve_symbol(&query.symbol) {
+        Ok(symbol) => symbol,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+    let Some(interval) = Interval::parse(&query.interval) else {
+        return error_response(
+            HttpResponse::BadRequest(),
+            format!("不支持的时间周期: {}", query.interval),
+        );
+    };
+    let Some(start_time) = query.start_time else {
+        return error_response(
+            HttpResponse::BadRequest(),
+            "导出必须指定 start_time".to_string(),
+        );
+    };
+    let end_time = query
+        .end_time
+        .unwrap_or(i64::MAX)
+        .min(state.clock.now_millis());
+    if start_time > end_time {
+        return error_response(
+            HttpResponse::BadRequest(),
+            "start_time 不能晚于 end_time".to_string(),
+        );
+    }
+    let candles = (end_time - start_time) / interval.approx_millis() + 1;
+    if candles > MAX_EXPORT_CANDLES {
+        return error_response(
+            HttpResponse::BadRequest(),
+            format!(
+                "导出区间包含约 {} 根K线，超过上限 {}，请缩小区间或使用更大的周期",
+                candles, MAX_EXPORT_CANDLES
+            ),
+        );
+    }
+    let indicators = match IndicatorSpec::parse_list(query.indicators.as_deref().unwrap_or("")) {
+        Ok(indicators) => indicators,
+        Err(invalid) => {
+            return error_response(
+                HttpResponse::BadRequest(),
+                format!("不支持的指标: {}（可用 sma_N、ema_N、rsi_N）", invalid),
+            )
+        }
+    };
+    let format = query.format.unwrap_or_default();
+
+    let sources: Vec<Arc<dyn MarketDataSource>> = match query.source.as_deref() {
+        Some(name) => match state.market_sources.get(name) {
+            Ok(source) => vec![source],
+            Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+        },
+        None => state
+            .market_sources
+            .sources()
+            .iter()
+            .map(|s| s.clone() as Arc<dyn MarketDataSource>)
+            .collect(),
+    };
+
+    // 先取第一批确定数据源，开始发送后就无法再返回错误状态码
+    let mut errors = SourceErrors::default();
+    for source in sources {
+        let name = source.name();
+        let encoder = match KlineEncoder::new(format, &indicators) {
+            Ok(encoder) => encoder,
+            Err(e) => return error_response(HttpResponse::InternalServerError(), e.to_string()),
+        };
+        let mut cursor = ExportCursor {
+            state: state.clone(),
+            source,
+            symbol: symbol.clone(),
+            interval: interval.to_string(),
+            batch_span: interval
+                .approx_millis()
+                .saturating_mul(i64::from(EXPORT_BATCH_CANDLES)),
+            next_start: start_time,
+            end_time,
+            encoder: Some(encoder),
+        };
+        let first_batch = match cursor.next_batch().await {
+            Ok(klines) => klines,
+            Err(e) => {
+                println!("导出: 源 {} 失败: {}", name, e);
+                errors.record(&e);
+                continue;
+            }
+        };
+
+        let first_chunk = cursor.encoder.as_mut().map(|encoder| {
+            let mut chunk = encoder.header();
+            encoder.encode(&first_batch).map(|bytes| {
+                chunk.extend(bytes);
+                chunk
+            })
+        });
+        let first_chunk = match first_chunk {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(e)) => {
+                return error_response(HttpResponse::InternalServerError(), e.to_string())
+            }
+            None => Vec::new(),
+        };
+
+        println!(
+            "📤 导出K线: {} {} {} {:?}, 区间 {} - {}",
+            symbol, interval, name, format, start_time, end_time
+        );
+        let rest = stream::unfold(Some(cursor), |cursor| async move {
+            let mut cursor = cursor?;
+            match cursor.next_chunk().await {
+                Ok(Some(chunk)) => Some((Ok(Bytes::from(chunk)), Some(cursor))),
+                Ok(None) => None,
+                Err(e) => {
+                    println!("❌ 导出中断: {}", e);
+                    Some((Err(actix_web::error::ErrorInternalServerError(e)), None))
+                }
+            }
+        });
+        let body = stream::once(async move { Ok::<_, actix_web::Error>(Bytes::from(first_chunk)) })
+            .chain(rest);
+
+        let filename = format!(
+            "{}_{}_{}.{}",
+            symbol.to_string().replace('/', "-"),
+            interval,
+            name,
+            format.extension()
+        );
+        return Ok(HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ))
+            .streaming(body));
+    }
+
+    let message = errors.message(format!("没有数据源支持 {} 的 {} K线", symbol, interval));
+    error_response(errors.response(), message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_support::get_text;
+    use actix_web::http::StatusCode;
+
+    #[actix_rt::test]
+    async fn export_endpoint_streams_recorded_range_as_csv() {
+        let (status, body) = get_text(
+            "/api/market/kline/export?symbol=BTC/USDT&interval=1h&source=okx\
+             &start_time=1717020000000&end_time=1717030800000&indicators=sma_2",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let lines: Vec<_> = body.lines().collect();
+        assert_eq!(lines.len(), 5, "{}", body);
+        assert!(lines[0].ends_with(",sma_2"), "{}", lines[0]);
+        assert!(lines[1].starts_with("1717020000000,"), "{}", lines[1]);
+        // 指标预热期为空
+        assert!(lines[1].ends_with(','), "{}", lines[1]);
+        assert!(lines[4].ends_with(",68059.05"), "{}", lines[4]);
+    }
+
+    #[actix_rt::test]
+    async fn export_endpoint_rejects_ranges_over_the_candle_cap() {
+        // 一年的1分钟K线约 52.7 万根
+        let (status, body) = get_text(
+            "/api/market/kline/export?symbol=BTC/USDT&interval=1m&source=okx\
+             &start_time=1685491200000&end_time=1717027200000",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+        assert!(body.contains("超过上限 100000"), "{}", body);
+    }
+}
@@ -1,495 +1,1181 @@
-use actix_web::{web, HttpResponse, Result};
-use chrono;
-use reqwest;
+use actix_web::http::{header, StatusCode};
+use actix_web::{web, HttpResponse, HttpResponseBuilder, Result};
+use futures_util::future::join_all;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::services::candle_store::{apply_limit, resolve_window};
+use crate::services::market_data::chart::{
+    anchor_time, build_chart, ChartBar, ChartOptions, ChartType,
+};
+use crate::services::market_data::compare::{compare_venues, PriceComparison};
+use crate::services::market_data::consensus::{
+    build_consensus, ConsensusKline, ConsensusMethod, SourceSeries, AGGREGATE_SOURCE,
+    DEFAULT_DEVIATION_THRESHOLD_BPS,
+};
+use crate::services::market_data::health::CircuitState;
+use crate::services::market_data::interval::Interval;
+use crate::services::market_data::orderbook::{check_step, merge_order_books, MergedOrderBook};
+use crate::services::market_data::quality::{CheckedKlines, QualityReport};
+use crate::services::market_data::{
+    KlineQuery, MarketDataError, MarketDataSource, OrderBook, PublicTrade, SourceCapabilities,
+    Ticker, TradeQuery,
+};
+use crate::AppState;
+
+pub use crate::services::market_data::KlineData;
+
+/// 区间查询单次最多返回的K线数量
+const MAX_RANGE_CANDLES: u32 = 500_000;
+/// 行情批量查询单次最多的交易对数量
+const MAX_TICKER_SYMBOLS: usize = 50;
+/// 订单簿每侧最多返回的档位数
+const MAX_ORDER_BOOK_DEPTH: u32 = 400;
+/// 成交记录单次最多返回的数量
+const MAX_TRADES: u32 = 1000;
+/// 交易对搜索单次最多返回的数量
+const MAX_SYMBOL_RESULTS: usize = 500;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct KlineRequest {
     pub symbol: String,
     pub interval: String,
     pub limit: Option<u32>,
+    /// 区间起点（毫秒时间戳，含）
     pub start_time: Option<i64>,
+    /// 区间终点（毫秒时间戳，含）
     pub end_time: Option<i64>,
+    /// 数据源名称，`aggregate` 表示聚合全部数据源
     pub source: Option<String>,
+    /// 聚合模式的共识算法：`median`（默认）或 `vwap`
+    pub method: Option<ConsensusMethod>,
+    /// 聚合模式或交叉校验时标记异常的偏离阈值（基点）
+    pub deviation_threshold_bps: Option<f64>,
+    /// 是否用其余数据源的同周期K线校验结果，标记偏离过大的K线（会额外请求全部数据源）
+    pub cross_check: Option<bool>,
+    /// 图表类型：`candles`（默认）、`heikin_ashi`、`renko`、`range` 或 `volume`
+    pub chart_type: Option<ChartType>,
+    /// Renko 砖块大小、区间K线的价格范围或成交量K线每根的成交量
+    #[serde(default, with = "crate::services::market_data::decimal::option")]
+    pub box_size: Option<Decimal>,
+    /// Renko 未指定 `box_size` 时按此周期的 ATR 计算砖块大小（默认14）
+    pub atr_period: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct KlineData {
-    pub timestamp: i64,
-    pub open: f64,
-    pub high: f64,
-    pub low: f64,
-    pub close: f64,
-    pub volume: f64,
+pub struct KlineResponse {
+    pub success: bool,
+    pub data: Vec<KlineData>,
     pub source: String,
+    /// 数据质量报告，请求失败时为空
+    pub quality: Option<QualityReport>,
+    pub message: Option<String>,
 }
 
+/// 衍生图表类型的K线响应
 #[derive(Debug, Serialize, Deserialize)]
-pub struct KlineResponse {
+pub struct ChartResponse {
     pub success: bool,
-    pub data: Vec<KlineData>,
+    pub data: Vec<ChartBar>,
+    pub source: String,
+    pub chart_type: ChartType,
+    /// 实际使用的砖块大小（Renko 按 ATR 计算时为计算结果）
+    #[serde(default, with = "crate::services::market_data::decimal::option")]
+    pub box_size: Option<Decimal>,
+    /// 基础K线的数据质量报告
+    pub quality: Option<QualityReport>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SourceFailure {
+    pub source: String,
+    pub message: String,
+}
+
+/// 汇总各数据源的失败原因，决定全部失败时的响应状态
+///
+/// 全部是参数问题时返回 400；其余失败都是本地或上游限频时返回 429 并带
+/// `Retry-After`，否则返回 503。
+#[derive(Debug, Default)]
+pub struct SourceErrors {
+    request_supported: bool,
+    unavailable: bool,
+    retry_after_secs: Option<u64>,
+}
+
+impl SourceErrors {
+    pub fn record(&mut self, error: &MarketDataError) {
+        if error.is_client_error() {
+            return;
+        }
+        self.request_supported = true;
+        match error.retry_after_secs() {
+            Some(secs) => {
+                self.retry_after_secs = Some(self.retry_after_secs.map_or(secs, |s| s.min(secs)))
+            }
+            None => self.unavailable = true,
+        }
+    }
+
+    /// 全部失败都是限频时建议的重试等待时间（秒）
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        if self.unavailable {
+            None
+        } else {
+            self.retry_after_secs
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        if !self.request_supported {
+            StatusCode::BAD_REQUEST
+        } else if self.retry_after_secs().is_some() {
+            StatusCode::TOO_MANY_REQUESTS
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
+
+    pub fn response(&self) -> HttpResponseBuilder {
+        let mut response = HttpResponse::build(self.status());
+        if let Some(secs) = self.retry_after_secs() {
+            response.insert_header((header::RETRY_AFTER, secs.to_string()));
+        }
+        response
+    }
+
+    /// 数据源可用性问题的提示；全部是参数问题时使用 `unsupported`
+    pub fn message(&self, unsupported: String) -> String {
+        if !self.request_supported {
+            unsupported
+        } else if let Some(secs) = self.retry_after_secs() {
+            format!("数据源请求过于频繁，请 {} 秒后重试", secs)
+        } else {
+            "所有数据源都不可用".to_string()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AggregateKlineResponse {
+    pub success: bool,
+    pub data: Vec<ConsensusKline>,
     pub source: String,
+    pub method: ConsensusMethod,
+    /// 参与聚合的数据源
+    pub sources: Vec<String>,
+    /// 请求失败或不支持该周期的数据源
+    pub failed_sources: Vec<SourceFailure>,
+    /// 各参与数据源的数据质量报告（已与其余数据源交叉校验）
+    pub quality: BTreeMap<String, QualityReport>,
     pub message: Option<String>,
 }
 
 /// 获取K线数据的主要端点
-pub async fn get_kline_data(query: web::Query<KlineRequest>) -> Result<HttpResponse> {
-    let symbol = &query.symbol;
+pub async fn get_kline_data(
+    state: web::Data<AppState>,
+    query: web::Query<KlineRequest>,
+) -> Result<HttpResponse> {
     let interval = &query.interval;
-    let limit = query.limit.unwrap_or(100);
+    // 指定起点的区间查询默认返回区间内全部K线
+    let default_limit = if query.start_time.is_some() {
+        MAX_RANGE_CANDLES
+    } else {
+        100
+    };
+    let limit = query.limit.unwrap_or(default_limit).min(MAX_RANGE_CANDLES);
     let requested_source = query.source.as_deref();
 
     println!(
-        "🔍 API请求: symbol={}, interval={}, limit={}, source={:?}",
-        symbol, interval, limit, requested_source
+        "🔍 API请求: symbol={}, interval={}, limit={}, start_time={:?}, end_time={:?}, source={:?}",
+        query.symbol, interval, limit, query.start_time, query.end_time, requested_source
     );
 
+    let symbol = match state.instruments.resolve_symbol(&query.symbol) {
+        Ok(symbol) => symbol,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(KlineResponse {
+                success: false,
+                data: vec![],
+                source: "none".to_string(),
+                quality: None,
+                message: Some(e.to_string()),
+            }));
+        }
+    };
+
+    let Some(parsed_interval) = Interval::parse(interval) else {
+        return Ok(HttpResponse::BadRequest().json(KlineResponse {
+            success: false,
+            data: vec![],
+            source: "none".to_string(),
+            quality: None,
+            message: Some(format!("不支持的时间周期: {}", interval)),
+        }));
+    };
+
+    if let (Some(start_time), Some(end_time)) = (query.start_time, query.end_time) {
+        if start_time > end_time {
+            return Ok(HttpResponse::BadRequest().json(KlineResponse {
+                success: false,
+                data: vec![],
+                source: "none".to_string(),
+                quality: None,
+                message: Some("start_time 不能晚于 end_time".to_string()),
+            }));
+        }
+    }
+
+    let kline_query = KlineQuery {
+        symbol,
+        interval: interval.clone(),
+        limit,
+        start_time: query.start_time,
+        end_time: query.end_time,
+    };
+
+    let threshold_bps = query
+        .deviation_threshold_bps
+        .unwrap_or(DEFAULT_DEVIATION_THRESHOLD_BPS);
+    let cross_check = query.cross_check.unwrap_or(false);
+
+    let chart = ChartOptions {
+        chart_type: query.chart_type.unwrap_or_default(),
+        box_size: query.box_size,
+        atr_period: query.atr_period,
+    };
+    if chart.chart_type.is_derived() {
+        return get_chart_klines(
+            &state,
+            &kline_query,
+            &parsed_interval,
+            requested_source,
+            &chart,
+        )
+        .await;
+    }
+
+    if requested_source == Some(AGGREGATE_SOURCE) {
+        let method = query.method.unwrap_or_default();
+        return get_aggregate_klines(
+            &state,
+            &kline_query,
+            &parsed_interval,
+            method,
+            threshold_bps,
+        )
+        .await;
+    }
+
+    let (mut checked, source) =
+        match fetch_checked_klines(&state, &kline_query, requested_source).await {
+            Ok(result) => result,
+            Err(response) => return Ok(response),
+        };
+    if cross_check {
+        cross_check_sources(
+            &state,
+            &kline_query,
+            &parsed_interval,
+            &source,
+            &mut checked,
+            threshold_bps,
+        )
+        .await;
+    }
+
+    Ok(HttpResponse::Ok().json(KlineResponse {
+        success: true,
+        data: checked.klines,
+        source,
+        quality: Some(checked.quality),
+        message: None,
+    }))
+}
+
+/// 从指定数据源获取K线，未指定时按注册表优先级依次尝试（优先读取本地K线存储）
+///
+/// 全部数据源都因参数问题（周期或交易对不支持）失败时返回 400 响应。
+async fn fetch_checked_klines(
+    state: &AppState,
+    query: &KlineQuery,
+    requested_source: Option<&str>,
+) -> std::result::Result<(CheckedKlines, String), HttpResponse> {
     // 如果指定了特定数据源，只使用该源
     if let Some(source) = requested_source {
         println!("🎯 使用指定数据源: {}", source);
-        match fetch_kline_from_source(symbol, interval, limit, source).await {
-            Ok(data) => {
-                return Ok(HttpResponse::Ok().json(KlineResponse {
-                    success: true,
-                    data,
-                    source: source.to_string(),
-                    message: None,
-                }));
+        let result = match state.market_sources.get(source) {
+            Ok(market_source) => {
+                state
+                    .candle_store
+                    .get_checked_klines(market_source.as_ref(), query)
+                    .await
             }
-            Err(e) => {
+            Err(e) => Err(e),
+        };
+        return result
+            .map(|checked| (checked, source.to_string()))
+            .map_err(|e| {
                 println!("❌ 指定源 {} 失败: {}", source, e);
-                return Ok(HttpResponse::ServiceUnavailable().json(KlineResponse {
+                let mut errors = SourceErrors::default();
+                errors.record(&e);
+                errors.response().json(KlineResponse {
                     success: false,
                     data: vec![],
                     source: source.to_string(),
+                    quality: None,
                     message: Some(format!("数据源 {} 不可用: {}", source, e)),
-                }));
-            }
-        }
+                })
+            });
     }
 
-    // 如果没有指定数据源，尝试真实的数据源，优先使用OKX
-    let sources = vec!["okx", "binance", "coingecko", "yahoo"];
-
-    for source in sources {
-        match fetch_kline_from_source(symbol, interval, limit, source).await {
-            Ok(data) => {
-                return Ok(HttpResponse::Ok().json(KlineResponse {
-                    success: true,
-                    data,
-                    source: source.to_string(),
-                    message: None,
-                }));
-            }
+    let mut errors = SourceErrors::default();
+    for market_source in state.market_sources.sources() {
+        match state
+            .candle_store
+            .get_checked_klines(market_source.as_ref(), query)
+            .await
+        {
+            Ok(checked) => return Ok((checked, market_source.name().to_string())),
             Err(e) => {
-                println!("源 {} 失败: {}", source, e);
+                println!("源 {} 失败: {}", market_source.name(), e);
+                errors.record(&e);
                 continue;
             }
         }
     }
 
-    Ok(HttpResponse::ServiceUnavailable().json(KlineResponse {
+    Err(errors.response().json(KlineResponse {
         success: false,
         data: vec![],
         source: "none".to_string(),
-        message: Some("所有数据源都不可用".to_string()),
+        quality: None,
+        message: Some(errors.message(format!(
+            "没有数据源支持 {} 的 {} K线",
+            query.symbol, query.interval
+        ))),
     }))
 }
 
-async fn fetch_kline_from_source(
-    symbol: &str,
-    interval: &str,
-    limit: u32,
-    source: &str,
-) -> Result<Vec<KlineData>, Box<dyn std::error::Error + Send + Sync>> {
-    // 创建支持代理的HTTP客户端
-    let client = create_proxy_client().await?;
-
-    match source {
-        "coingecko" => fetch_from_coingecko(&client, symbol, interval, limit).await,
-        "yahoo" => fetch_from_yahoo(&client, symbol, interval, limit).await,
-        "okx" => fetch_from_okx(&client, symbol, interval, limit).await,
-        "binance" => fetch_from_binance(&client, symbol, interval, limit).await,
-        _ => Err("Unknown source".into()),
+/// 由基础K线构建 Heikin-Ashi、Renko、区间或成交量K线
+///
+/// 基础K线从窗口起点所在锚点周期（UTC 自然日、周、月或年，见 [`anchor_time`]）的开始获取，
+/// 起点在同一锚点周期内的查询得到相同的砖块和K线边界；只返回在窗口内结束的K线，
+/// 数量受 `limit` 限制。Renko 的 ATR 砖块大小取锚点周期开头的基础K线计算，同样不随窗口变化。
+/// 质量报告针对包含锚点预热部分的基础K线。
+async fn get_chart_klines(
+    state: &AppState,
+    query: &KlineQuery,
+    interval: &Interval,
+    requested_source: Option<&str>,
+    chart: &ChartOptions,
+) -> Result<HttpResponse> {
+    let error_response = |mut response: HttpResponseBuilder, message: String| {
+        Ok(response.json(ChartResponse {
+            success: false,
+            data: vec![],
+            source: "none".to_string(),
+            chart_type: chart.chart_type,
+            box_size: None,
+            quality: None,
+            message: Some(message),
+        }))
+    };
+
+    if requested_source == Some(AGGREGATE_SOURCE) {
+        return error_response(
+            HttpResponse::BadRequest(),
+            "聚合模式不支持衍生图表类型".to_string(),
+        );
     }
+    let Some(step) = interval.millis() else {
+        return error_response(
+            HttpResponse::BadRequest(),
+            format!("{} 周期不支持衍生图表类型", interval),
+        );
+    };
+
+    let (window_start, window_end) = resolve_window(query, step, state.clock.now_millis());
+    let anchor = anchor_time(window_start, step);
+    let base_query = KlineQuery {
+        limit: u32::try_from((window_end - anchor) / step + 1).unwrap_or(u32::MAX),
+        start_time: Some(anchor),
+        end_time: Some(window_end),
+        ..query.clone()
+    };
+
+    let (checked, source) = match fetch_checked_klines(state, &base_query, requested_source).await {
+        Ok(result) => result,
+        Err(response) => return Ok(response),
+    };
+    let series = match build_chart(&checked.klines, chart, MAX_RANGE_CANDLES as usize) {
+        Ok(series) => series,
+        Err(message) => return error_response(HttpResponse::BadRequest(), message),
+    };
+    let bars: Vec<ChartBar> = series
+        .bars
+        .into_iter()
+        .filter(|bar| bar.end_time >= window_start)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ChartResponse {
+        success: true,
+        data: apply_limit(bars, query),
+        source,
+        chart_type: chart.chart_type,
+        box_size: series.box_size,
+        quality: Some(checked.quality),
+        message: None,
+    }))
 }
 
-/// 创建支持代理的HTTP客户端
-async fn create_proxy_client() -> Result<reqwest::Client, Box<dyn std::error::Error + Send + Sync>>
-{
-    let mut client_builder = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .user_agent("QuantConsole/1.0")
-        .danger_accept_invalid_certs(true); // 如果SSR代理使用自签名证书
-
-    let mut proxy_configured = false;
-
-    // 检查环境变量中的代理配置
-    if let Ok(proxy_url) = std::env::var("HTTP_PROXY") {
-        println!("🔧 使用环境变量HTTP代理: {}", proxy_url);
-        match reqwest::Proxy::http(&proxy_url) {
-            Ok(proxy) => {
-                client_builder = client_builder.proxy(proxy);
-                proxy_configured = true;
-            }
-            Err(e) => println!("❌ HTTP代理配置失败: {}", e),
-        }
-    }
+/// 并行请求其余数据源，以同周期K线的中位数校验 `checked`，结果写入质量报告的准确率和异常标记
+///
+/// 其余数据源请求失败时忽略，没有可比较的数据时准确率为空。
+async fn cross_check_sources(
+    state: &AppState,
+    query: &KlineQuery,
+    interval: &Interval,
+    source: &str,
+    checked: &mut CheckedKlines,
+    threshold_bps: f64,
+) {
+    let others: Vec<_> = state
+        .market_sources
+        .sources()
+        .iter()
+        .filter(|s| s.name() != source)
+        .collect();
+    let results = join_all(
+        others
+            .iter()
+            .map(|s| state.candle_store.get_klines(s.as_ref(), query)),
+    )
+    .await;
+
+    let references: Vec<Vec<KlineData>> = results.into_iter().filter_map(|r| r.ok()).collect();
+    let references: Vec<&[KlineData]> = references.iter().map(Vec::as_slice).collect();
+    checked
+        .quality
+        .cross_check(&checked.klines, interval, &references, threshold_bps);
+}
+
+/// 并行请求全部数据源，按时间对齐后返回共识K线及各数据源的偏离
+async fn get_aggregate_klines(
+    state: &AppState,
+    query: &KlineQuery,
+    interval: &Interval,
+    method: ConsensusMethod,
+    threshold_bps: f64,
+) -> Result<HttpResponse> {
+    let sources = state.market_sources.sources();
+    let results = join_all(sources.iter().map(|source| {
+        state
+            .candle_store
+            .get_checked_klines(source.as_ref(), query)
+    }))
+    .await;
 
-    if let Ok(proxy_url) = std::env::var("HTTPS_PROXY") {
-        println!("🔧 使用环境变量HTTPS代理: {}", proxy_url);
-        match reqwest::Proxy::https(&proxy_url) {
-            Ok(proxy) => {
-                client_builder = client_builder.proxy(proxy);
-                proxy_configured = true;
+    let mut series = Vec::new();
+    let mut quality = BTreeMap::new();
+    let mut failed_sources = Vec::new();
+    let mut errors = SourceErrors::default();
+    for (source, result) in sources.iter().zip(results) {
+        match result {
+            Ok(checked) => {
+                quality.insert(source.name().to_string(), checked.quality);
+                series.push(SourceSeries {
+                    source: source.name().to_string(),
+                    klines: checked.klines,
+                    base_volume: source.capabilities().base_volume,
+                });
+            }
+            Err(e) => {
+                println!("聚合: 源 {} 失败: {}", source.name(), e);
+                errors.record(&e);
+                failed_sources.push(SourceFailure {
+                    source: source.name().to_string(),
+                    message: e.to_string(),
+                });
             }
-            Err(e) => println!("❌ HTTPS代理配置失败: {}", e),
         }
     }
 
-    // 如果没有环境变量，尝试常见的SSR代理端口
-    if !proxy_configured {
-        println!("⚠️ 未检测到代理环境变量，尝试常见SSR代理端口...");
-        let potential_proxies = [
-            "http://127.0.0.1:1080",  // 常见SOCKS5转HTTP
-            "http://127.0.0.1:7890",  // Clash HTTP代理
-            "http://127.0.0.1:10809", // V2RayN HTTP
-            "http://127.0.0.1:1087",  // Shadowsocks HTTP
-            "http://127.0.0.1:8080",  // 通用HTTP代理
-        ];
-
-        for proxy_url in &potential_proxies {
-            match reqwest::Proxy::http(*proxy_url) {
-                Ok(proxy) => {
-                    println!("尝试使用代理: {}", proxy_url);
-                    client_builder = client_builder.proxy(proxy);
-                    proxy_configured = true;
-                    break;
-                }
-                Err(e) => {
-                    println!("代理 {} 配置失败: {}", proxy_url, e);
-                }
-            }
+    for (index, current) in series.iter().enumerate() {
+        let references: Vec<&[KlineData]> = series
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .map(|(_, s)| s.klines.as_slice())
+            .collect();
+        if let Some(report) = quality.get_mut(&current.source) {
+            report.cross_check(&current.klines, interval, &references, threshold_bps);
         }
     }
 
-    if !proxy_configured {
-        println!("⚠️ 未配置代理，直接连接（可能因网络限制失败）");
-        println!("💡 要使用SSR代理，请设置环境变量：");
-        println!("   HTTP_PROXY=http://127.0.0.1:你的代理端口");
-        println!("   HTTPS_PROXY=http://127.0.0.1:你的代理端口");
+    let names: Vec<String> = series.iter().map(|s| s.source.clone()).collect();
+    let data = apply_limit(
+        build_consensus(&series, interval, method, threshold_bps),
+        query,
+    );
+    let outliers = data
+        .iter()
+        .flat_map(|k| &k.deviations)
+        .filter(|d| d.outlier)
+        .count();
+    println!(
+        "🧮 聚合完成: {} 根K线, 数据源 {:?}, 异常点 {}",
+        data.len(),
+        names,
+        outliers
+    );
+
+    let (mut response, message) = if !series.is_empty() {
+        (HttpResponse::Ok(), None)
     } else {
-        println!("✅ 代理配置成功");
-    }
+        (
+            errors.response(),
+            Some(errors.message(format!(
+                "没有数据源支持 {} 的 {} K线",
+                query.symbol, query.interval
+            ))),
+        )
+    };
 
-    Ok(client_builder.build()?)
+    Ok(response.json(AggregateKlineResponse {
+        success: !series.is_empty(),
+        data,
+        source: AGGREGATE_SOURCE.to_string(),
+        method,
+        sources: names,
+        failed_sources,
+        quality,
+        message,
+    }))
 }
 
-async fn fetch_from_coingecko(
-    client: &reqwest::Client,
-    symbol: &str,
-    _interval: &str,
-    _limit: u32,
-) -> Result<Vec<KlineData>, Box<dyn std::error::Error + Send + Sync>> {
-    // 扩展的交易对映射
-    let coin_id = match symbol.to_uppercase().as_str() {
-        "BTCUSDT" | "BTC-USD" | "BTC" => "bitcoin",
-        "ETHUSDT" | "ETH-USD" | "ETH" => "ethereum",
-        "BNBUSDT" | "BNB-USD" | "BNB" => "binancecoin",
-        "ADAUSDT" | "ADA-USD" | "ADA" => "cardano",
-        "SOLUSDT" | "SOL-USD" | "SOL" => "solana",
-        "DOGEUSDT" | "DOGE-USD" | "DOGE" => "dogecoin",
-        "XRPUSDT" | "XRP-USD" | "XRP" => "ripple",
-        "DOTUSDT" | "DOT-USD" | "DOT" => "polkadot",
-        "AVAXUSDT" | "AVAX-USD" | "AVAX" => "avalanche-2",
-        "LINKUSDT" | "LINK-USD" | "LINK" => "chainlink",
-        _ => {
-            println!("⚠️ CoinGecko: 未知交易对 {}, 使用默认值 bitcoin", symbol);
-            "bitcoin"
-        }
-    };
+/// 健康检查端点，返回各数据源的熔断器状态与滚动统计
+///
+/// 全部数据源的现货接口熔断时返回 503，部分熔断（含永续合约接口）时状态为 `degraded`。
+pub async fn market_health_check(state: web::Data<AppState>) -> Result<HttpResponse> {
+    let sources = state.market_sources.health();
+    let open_count = sources
+        .iter()
+        .filter(|s| s.state != CircuitState::Closed)
+        .count();
+    let perpetual_open = sources
+        .iter()
+        .filter_map(|s| s.perpetual.as_ref())
+        .any(|p| p.state != CircuitState::Closed);
 
-    let url = format!(
-        "https://api.coingecko.com/api/v3/coins/{}/market_chart?vs_currency=usd&days=1&interval=hourly",
-        coin_id
-    );
+    let (mut response, status, message) = if open_count == 0 && !perpetual_open {
+        (HttpResponse::Ok(), "ok", "Market data API is running")
+    } else if open_count < sources.len() {
+        (HttpResponse::Ok(), "degraded", "部分数据源已熔断")
+    } else {
+        (
+            HttpResponse::ServiceUnavailable(),
+            "down",
+            "所有数据源都已熔断",
+        )
+    };
 
-    println!("🟢 CoinGecko请求: {}", url);
+    Ok(response.json(serde_json::json!({
+        "status": status,
+        "timestamp": chrono::Utc::now().timestamp(),
+        "message": message,
+        "sources": sources,
+        "streams": state.market_stream.status()
+    })))
+}
 
-    let response = client.get(&url).send().await?;
+#[derive(Debug, Deserialize)]
+pub struct TickerRequest {
+    /// 单个交易对
+    pub symbol: Option<String>,
+    /// 多个交易对，逗号分隔，如 `BTCUSDT,ETHUSDT`
+    pub symbols: Option<String>,
+    pub source: Option<String>,
+}
 
-    if !response.status().is_success() {
-        return Err(format!("CoinGecko HTTP错误: {}", response.status()).into());
-    }
+#[derive(Debug, Serialize)]
+pub struct TickerFailure {
+    pub symbol: String,
+    pub message: String,
+}
 
-    let data: serde_json::Value = response.json().await?;
+#[derive(Debug, Serialize)]
+pub struct TickerResponse {
+    pub success: bool,
+    pub data: Vec<Ticker>,
+    pub failed: Vec<TickerFailure>,
+    pub message: Option<String>,
+}
 
-    let prices = data["prices"].as_array().ok_or("CoinGecko: 无价格数据")?;
-    let volumes = data["total_volumes"]
-        .as_array()
-        .ok_or("CoinGecko: 无成交量数据")?;
+/// 获取24小时行情，支持批量查询
+pub async fn get_ticker(
+    state: web::Data<AppState>,
+    query: web::Query<TickerRequest>,
+) -> Result<HttpResponse> {
+    let inputs: Vec<&str> = query
+        .symbol
+        .iter()
+        .chain(query.symbols.iter())
+        .flat_map(|s| s.split(','))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
 
-    let mut klines = Vec::new();
+    if inputs.is_empty() || inputs.len() > MAX_TICKER_SYMBOLS {
+        return Ok(HttpResponse::BadRequest().json(TickerResponse {
+            success: false,
+            data: vec![],
+            failed: vec![],
+            message: Some(format!(
+                "请通过 symbol 或 symbols 指定 1-{} 个交易对",
+                MAX_TICKER_SYMBOLS
+            )),
+        }));
+    }
 
-    // CoinGecko只提供价格点，我们需要构造OHLC数据
-    for (i, price_point) in prices.iter().enumerate() {
-        let price_array = price_point.as_array().ok_or("无效价格格式")?;
-        let timestamp = price_array[0].as_i64().ok_or("无效时间戳")?;
-        let price = price_array[1].as_f64().ok_or("无效价格")?;
+    let mut failed = Vec::new();
+    let mut requests = Vec::new();
+    for input in inputs {
+        match state.instruments.resolve_symbol(input) {
+            Ok(symbol) => requests.push((symbol, query.source.clone())),
+            Err(e) => failed.push(TickerFailure {
+                symbol: input.to_string(),
+                message: e.to_string(),
+            }),
+        }
+    }
 
-        let volume = if i < volumes.len() {
-            if let Some(vol_array) = volumes[i].as_array() {
-                vol_array[1].as_f64().unwrap_or(0.0)
-            } else {
-                0.0
+    let mut data = Vec::new();
+    let mut errors = SourceErrors::default();
+    for ((symbol, _), result) in requests.iter().zip(state.tickers.get_many(&requests).await) {
+        match result {
+            Ok(ticker) => data.push(ticker),
+            Err(e) => {
+                errors.record(&e);
+                failed.push(TickerFailure {
+                    symbol: symbol.to_string(),
+                    message: e.to_string(),
+                });
             }
-        } else {
-            0.0
-        };
+        }
+    }
 
-        // 由于CoinGecko只提供单个价格点，我们使用相同价格作为OHLC
-        // 这不是完美的解决方案，但能提供基本的价格趋势
-        klines.push(KlineData {
-            timestamp,
-            open: price,
-            high: price * 1.001, // 稍微调整以避免平线
-            low: price * 0.999,
-            close: price,
-            volume,
-            source: "coingecko".to_string(),
-        });
+    let mut response = if !data.is_empty() {
+        HttpResponse::Ok()
+    } else {
+        errors.response()
+    };
+    Ok(response.json(TickerResponse {
+        success: !data.is_empty(),
+        data,
+        failed,
+        message: None,
+    }))
+}
+
+/// 逗号分隔的交易所列表对应的数据源；为空时使用全部具备指定能力的数据源
+fn select_sources(
+    state: &AppState,
+    exchanges: Option<&str>,
+    capable: impl Fn(&SourceCapabilities) -> bool,
+) -> std::result::Result<Vec<Arc<dyn MarketDataSource>>, MarketDataError> {
+    match exchanges {
+        Some(exchanges) => exchanges
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| state.market_sources.get(name))
+            .collect(),
+        None => Ok(state
+            .market_sources
+            .sources()
+            .iter()
+            .filter(|s| capable(&s.capabilities()))
+            .map(|s| s.clone() as Arc<dyn MarketDataSource>)
+            .collect()),
     }
+}
 
-    println!("✅ CoinGecko成功获取 {} 个数据点", klines.len());
-    Ok(klines)
+#[derive(Debug, Deserialize)]
+pub struct OrderBookRequest {
+    pub symbol: String,
+    /// 每侧档位数，默认20
+    pub depth: Option<u32>,
+    /// 交易所列表，逗号分隔；为空时使用全部支持订单簿的数据源
+    pub exchanges: Option<String>,
+    /// 是否返回跨交易所合并的订单簿
+    pub merge: Option<bool>,
+    /// 合并时的价格分组步长
+    #[serde(default, with = "crate::services::market_data::decimal::option")]
+    pub step: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderBookResponse {
+    pub success: bool,
+    pub symbol: String,
+    pub books: Vec<OrderBook>,
+    pub merged: Option<MergedOrderBook>,
+    pub failed_sources: Vec<SourceFailure>,
+    pub message: Option<String>,
 }
 
-async fn fetch_from_yahoo(
-    client: &reqwest::Client,
-    symbol: &str,
-    _interval: &str,
-    _limit: u32,
-) -> Result<Vec<KlineData>, Box<dyn std::error::Error + Send + Sync>> {
-    // 转换符号格式
-    let yahoo_symbol = match symbol.to_uppercase().as_str() {
-        "BTCUSDT" | "BTC" => "BTC-USD",
-        "ETHUSDT" | "ETH" => "ETH-USD",
-        "BNBUSDT" | "BNB" => "BNB-USD",
-        _ => "BTC-USD",
+/// 获取各交易所的订单簿快照，可选合并为一个订单簿
+pub async fn get_order_book(
+    state: web::Data<AppState>,
+    query: web::Query<OrderBookRequest>,
+) -> Result<HttpResponse> {
+    let error_response = |mut response: actix_web::HttpResponseBuilder, message: String| {
+        Ok(response.json(OrderBookResponse {
+            success: false,
+            symbol: query.symbol.clone(),
+            books: vec![],
+            merged: None,
+            failed_sources: vec![],
+            message: Some(message),
+        }))
     };
 
-    let end_time = chrono::Utc::now().timestamp();
-    let start_time = end_time - 86400; // 1天前
+    let symbol = match state.instruments.resolve_symbol(&query.symbol) {
+        Ok(symbol) => symbol,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+    let depth = query.depth.unwrap_or(20).clamp(1, MAX_ORDER_BOOK_DEPTH);
 
-    let url = format!(
-        "https://query1.finance.yahoo.com/v8/finance/chart/{}?period1={}&period2={}&interval=1h",
-        yahoo_symbol, start_time, end_time
-    );
+    let sources = match select_sources(&state, query.exchanges.as_deref(), |c| c.order_book) {
+        Ok(sources) => sources,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
 
-    let response = client.get(&url).send().await?;
-    let data: serde_json::Value = response.json().await?;
-
-    let result = data["chart"]["result"][0]
-        .as_object()
-        .ok_or("No result data")?;
-    let timestamps = result["timestamp"].as_array().ok_or("No timestamp data")?;
-    let indicators = &result["indicators"]["quote"][0];
-
-    let opens = indicators["open"].as_array().ok_or("No open data")?;
-    let highs = indicators["high"].as_array().ok_or("No high data")?;
-    let lows = indicators["low"].as_array().ok_or("No low data")?;
-    let closes = indicators["close"].as_array().ok_or("No close data")?;
-    let volumes = indicators["volume"].as_array().ok_or("No volume data")?;
-
-    let mut klines = Vec::new();
-    for i in 0..timestamps.len() {
-        if let (Some(ts), Some(o), Some(h), Some(l), Some(c), Some(v)) = (
-            timestamps[i].as_i64(),
-            opens[i].as_f64(),
-            highs[i].as_f64(),
-            lows[i].as_f64(),
-            closes[i].as_f64(),
-            volumes[i].as_f64(),
-        ) {
-            klines.push(KlineData {
-                timestamp: ts * 1000, // 转换为毫秒
-                open: o,
-                high: h,
-                low: l,
-                close: c,
-                volume: v,
-                source: "yahoo".to_string(),
-            });
+    let results = join_all(
+        sources
+            .iter()
+            .map(|source| source.fetch_order_book(&symbol, depth)),
+    )
+    .await;
+
+    let mut books = Vec::new();
+    let mut failed_sources = Vec::new();
+    let mut errors = SourceErrors::default();
+    for (source, result) in sources.iter().zip(results) {
+        match result {
+            Ok(mut book) => {
+                // 交易所可能返回超过请求档数的数据
+                book.bids.truncate(depth as usize);
+                book.asks.truncate(depth as usize);
+                books.push(book);
+            }
+            Err(e) => {
+                println!("订单簿: 源 {} 失败: {}", source.name(), e);
+                errors.record(&e);
+                failed_sources.push(SourceFailure {
+                    source: source.name().to_string(),
+                    message: e.to_string(),
+                });
+            }
         }
     }
 
-    Ok(klines)
-}
-
-async fn fetch_from_okx(
-    client: &reqwest::Client,
-    symbol: &str,
-    interval: &str,
-    limit: u32,
-) -> Result<Vec<KlineData>, Box<dyn std::error::Error + Send + Sync>> {
-    // 转换符号格式 (OKX使用 BTC-USDT 格式)
-    let okx_symbol = match symbol.to_uppercase().as_str() {
-        "BTCUSDT" | "BTC" => "BTC-USDT".to_string(),
-        "ETHUSDT" | "ETH" => "ETH-USDT".to_string(),
-        "BNBUSDT" | "BNB" => "BNB-USDT".to_string(),
-        "ADAUSDT" | "ADA" => "ADA-USDT".to_string(),
-        "SOLUSDT" | "SOL" => "SOL-USDT".to_string(),
-        s if s.contains("USDT") && !s.contains("-") => {
-            // 自动转换 XXXUSDT 格式到 XXX-USDT
-            let base = s.replace("USDT", "");
-            format!("{}-USDT", base)
+    let merge = query.merge.unwrap_or(false);
+    if let (true, Some(step)) = (merge, query.step) {
+        if let Err(message) = check_step(&books, step) {
+            return error_response(HttpResponse::BadRequest(), message);
         }
-        _ => "BTC-USDT".to_string(),
-    };
+    }
+    let merged = merge.then(|| merge_order_books(&books, depth as usize, query.step));
 
-    // 转换时间间隔格式
-    let okx_interval = match interval {
-        "1h" | "hourly" => "1H",
-        "1d" | "daily" => "1D",
-        "1w" | "weekly" => "1W",
-        "1m" | "1min" => "1m",
-        "5m" | "5min" => "5m",
-        "15m" | "15min" => "15m",
-        "30m" | "30min" => "30m",
-        _ => "1H",
+    let (mut response, message) = if !books.is_empty() {
+        (HttpResponse::Ok(), None)
+    } else {
+        (
+            errors.response(),
+            Some(errors.message(format!("没有数据源提供 {} 的订单簿", symbol))),
+        )
     };
 
-    let url = format!(
-        "https://www.okx.com/api/v5/market/candles?instId={}&bar={}&limit={}",
-        okx_symbol, okx_interval, limit
-    );
+    Ok(response.json(OrderBookResponse {
+        success: !books.is_empty(),
+        symbol: symbol.to_string(),
+        books,
+        merged,
+        failed_sources,
+        message,
+    }))
+}
 
-    println!("🟡 OKX请求: {}", url);
+#[derive(Debug, Deserialize)]
+pub struct TradesRequest {
+    pub symbol: String,
+    pub limit: Option<u32>,
+    pub source: Option<String>,
+    /// 翻页游标（上一页返回的 `next_cursor`），需同时指定 `source`
+    pub before: Option<String>,
+}
 
-    let response = client.get(&url).send().await?;
+#[derive(Debug, Serialize)]
+pub struct TradesResponse {
+    pub success: bool,
+    pub data: Vec<PublicTrade>,
+    pub source: String,
+    /// 获取更早成交的游标（本页最早的成交ID），本页为空时为空
+    pub next_cursor: Option<String>,
+    pub message: Option<String>,
+}
 
-    if !response.status().is_success() {
-        return Err(format!("OKX API HTTP错误: {}", response.status()).into());
+/// 获取最近的公开成交记录，支持按游标向前翻页
+pub async fn get_trades(
+    state: web::Data<AppState>,
+    query: web::Query<TradesRequest>,
+) -> Result<HttpResponse> {
+    let error_response = |mut response: actix_web::HttpResponseBuilder, message: String| {
+        Ok(response.json(TradesResponse {
+            success: false,
+            data: vec![],
+            source: "none".to_string(),
+            next_cursor: None,
+            message: Some(message),
+        }))
+    };
+
+    let symbol = match state.instruments.resolve_symbol(&query.symbol) {
+        Ok(symbol) => symbol,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+    // 游标是交易所各自的成交ID，不能跨数据源使用
+    if query.before.is_some() && query.source.is_none() {
+        return error_response(
+            HttpResponse::BadRequest(),
+            "使用 before 翻页时必须指定 source".to_string(),
+        );
     }
 
-    let data: serde_json::Value = response.json().await?;
+    let trade_query = TradeQuery {
+        symbol,
+        limit: query.limit.unwrap_or(100).clamp(1, MAX_TRADES),
+        before: query.before.clone(),
+    };
 
-    // 检查OKX响应格式
-    if data["code"].as_str() != Some("0") {
-        return Err(format!(
-            "OKX API错误: {}",
-            data["msg"].as_str().unwrap_or("Unknown error")
-        )
-        .into());
-    }
+    let sources: Vec<std::sync::Arc<dyn MarketDataSource>> = match query.source.as_deref() {
+        Some(name) => match state.market_sources.get(name) {
+            Ok(source) => vec![source],
+            Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+        },
+        None => state
+            .market_sources
+            .sources()
+            .iter()
+            .filter(|s| s.capabilities().trades)
+            .map(|s| s.clone() as std::sync::Arc<dyn MarketDataSource>)
+            .collect(),
+    };
 
-    let candles = data["data"].as_array().ok_or("OKX: 无效的数据格式")?;
-
-    let mut klines = Vec::new();
-    for candle in candles {
-        let candle_array = candle.as_array().ok_or("OKX: 无效的K线格式")?;
-
-        // OKX K线格式: [timestamp, open, high, low, close, volume, volumeCcy]
-        if candle_array.len() >= 6 {
-            klines.push(KlineData {
-                timestamp: candle_array[0].as_str().ok_or("无效时间戳")?.parse()?,
-                open: candle_array[1].as_str().ok_or("无效开盘价")?.parse()?,
-                high: candle_array[2].as_str().ok_or("无效最高价")?.parse()?,
-                low: candle_array[3].as_str().ok_or("无效最低价")?.parse()?,
-                close: candle_array[4].as_str().ok_or("无效收盘价")?.parse()?,
-                volume: candle_array[5].as_str().ok_or("无效成交量")?.parse()?,
-                source: "okx".to_string(),
-            });
+    // 按优先级依次尝试，与K线接口的回退逻辑一致
+    let mut last_error = None;
+    let mut errors = SourceErrors::default();
+    for source in sources {
+        match source.fetch_trades(&trade_query).await {
+            Ok(data) => {
+                // 交易所单页上限可能小于 limit，只要本页非空就返回游标
+                let next_cursor = data.last().map(|t| t.id.clone());
+                return Ok(HttpResponse::Ok().json(TradesResponse {
+                    success: true,
+                    data,
+                    source: source.name().to_string(),
+                    next_cursor,
+                    message: None,
+                }));
+            }
+            Err(e) => {
+                println!("成交记录: 源 {} 失败: {}", source.name(), e);
+                errors.record(&e);
+                last_error = Some(e);
+            }
         }
     }
 
-    println!("✅ OKX成功获取 {} 个K线数据点", klines.len());
-    Ok(klines)
+    let message = last_error
+        .map(|e| e.to_string())
+        .unwrap_or_else(|| "没有数据源提供成交记录".to_string());
+    error_response(errors.response(), message)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    pub symbol: String,
+    /// 交易所列表，逗号分隔；为空时使用全部提供行情的数据源
+    pub exchanges: Option<String>,
 }
 
-async fn fetch_from_binance(
-    client: &reqwest::Client,
-    symbol: &str,
-    interval: &str,
-    limit: u32,
-) -> Result<Vec<KlineData>, Box<dyn std::error::Error + Send + Sync>> {
-    // 转换间隔格式
-    let binance_interval = match interval {
-        "1h" | "hourly" => "1h",
-        "1d" | "daily" => "1d",
-        "1w" | "weekly" => "1w",
-        _ => "1h",
+#[derive(Debug, Serialize)]
+pub struct CompareResponse {
+    pub success: bool,
+    pub data: Option<PriceComparison>,
+    pub failed_sources: Vec<SourceFailure>,
+    pub message: Option<String>,
+}
+
+/// 跨数据源价格比较：各源价格相对中位数的价差、数据时间偏差和最优买卖价所在交易所
+///
+/// 行情走行情服务的缓存，最优买卖价取自各交易所的订单簿一档。
+pub async fn get_compare(
+    state: web::Data<AppState>,
+    query: web::Query<CompareRequest>,
+) -> Result<HttpResponse> {
+    let error_response = |mut response: actix_web::HttpResponseBuilder, message: String| {
+        Ok(response.json(CompareResponse {
+            success: false,
+            data: None,
+            failed_sources: vec![],
+            message: Some(message),
+        }))
     };
 
-    let binance_symbol = symbol.to_uppercase().replace("-", "");
+    let symbol = match state.instruments.resolve_symbol(&query.symbol) {
+        Ok(symbol) => symbol,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+    let sources = match select_sources(&state, query.exchanges.as_deref(), |c| c.ticker) {
+        Ok(sources) => sources,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
 
-    let url = format!(
-        "https://api.binance.com/api/v3/klines?symbol={}&interval={}&limit={}",
-        binance_symbol, binance_interval, limit
+    let (ticker_results, book_results) = futures_util::join!(
+        join_all(
+            sources
+                .iter()
+                .map(|source| state.tickers.get(&symbol, Some(source.name())))
+        ),
+        join_all(
+            sources
+                .iter()
+                .filter(|source| source.capabilities().order_book)
+                .map(|source| source.fetch_order_book(&symbol, 1))
+        ),
     );
 
-    let response = client.get(&url).send().await?;
-    let data: Vec<serde_json::Value> = response.json().await?;
-
-    let mut klines = Vec::new();
-    for kline in data {
-        let kline_array = kline.as_array().ok_or("Invalid kline format")?;
-
-        klines.push(KlineData {
-            timestamp: kline_array[0].as_i64().ok_or("Invalid timestamp")?,
-            open: kline_array[1].as_str().ok_or("Invalid open")?.parse()?,
-            high: kline_array[2].as_str().ok_or("Invalid high")?.parse()?,
-            low: kline_array[3].as_str().ok_or("Invalid low")?.parse()?,
-            close: kline_array[4].as_str().ok_or("Invalid close")?.parse()?,
-            volume: kline_array[5].as_str().ok_or("Invalid volume")?.parse()?,
-            source: "binance".to_string(),
-        });
+    let mut tickers = Vec::new();
+    let mut failed_sources = Vec::new();
+    let mut errors = SourceErrors::default();
+    for (source, result) in sources.iter().zip(ticker_results) {
+        match result {
+            Ok(ticker) => tickers.push(ticker),
+            Err(e) => {
+                println!("价格比较: 源 {} 失败: {}", source.name(), e);
+                errors.record(&e);
+                failed_sources.push(SourceFailure {
+                    source: source.name().to_string(),
+                    message: e.to_string(),
+                });
+            }
+        }
     }
+    // 订单簿只用于最优买卖价，失败时不影响价格比较
+    let books: Vec<OrderBook> = book_results.into_iter().filter_map(|r| r.ok()).collect();
 
-    Ok(klines)
+    let comparison = compare_venues(&tickers, &books);
+    let (mut response, message) = if comparison.is_some() {
+        (HttpResponse::Ok(), None)
+    } else {
+        (
+            errors.response(),
+            Some(errors.message(format!("没有数据源提供 {} 的行情", symbol))),
+        )
+    };
+
+    Ok(response.json(CompareResponse {
+        success: comparison.is_some(),
+        data: comparison,
+        failed_sources,
+        message,
+    }))
 }
 
-/// 健康检查端点
-pub async fn market_health_check() -> Result<HttpResponse> {
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "ok",
-        "timestamp": chrono::Utc::now().timestamp(),
-        "message": "Market data API is running"
-    })))
+#[derive(Debug, Deserialize)]
+pub struct SymbolSearchRequest {
+    /// 搜索关键字，如 `BTC`、`ethusdt`
+    pub q: Option<String>,
+    /// 只返回指定交易所的交易对
+    pub exchange: Option<String>,
+    pub limit: Option<usize>,
 }
 
-/// 获取支持的交易对列表
-pub async fn get_supported_symbols() -> Result<HttpResponse> {
-    let symbols = vec![
-        serde_json::json!({
-            "symbol": "BTCUSDT",
-            "name": "Bitcoin",
-            "sources": ["coingecko", "yahoo", "okx", "binance"]
-        }),
-        serde_json::json!({
-            "symbol": "ETHUSDT",
-            "name": "Ethereum",
-            "sources": ["coingecko", "yahoo", "okx", "binance"]
-        }),
-        serde_json::json!({
-            "symbol": "BNBUSDT",
-            "name": "Binance Coin",
-            "sources": ["coingecko", "yahoo", "okx", "binance"]
-        }),
-        serde_json::json!({
-            "symbol": "ADAUSDT",
-            "name": "Cardano",
-            "sources": ["coingecko", "yahoo", "okx", "binance"]
-        }),
-        serde_json::json!({
-            "symbol": "SOLUSDT",
-            "name": "Solana",
-            "sources": ["coingecko", "yahoo", "okx", "binance"]
-        }),
-    ];
+/// 搜索支持的交易对，数据来自交易对注册表
+pub async fn get_supported_symbols(
+    state: web::Data<AppState>,
+    query: web::Query<SymbolSearchRequest>,
+) -> Result<HttpResponse> {
+    let limit = query.limit.unwrap_or(50).min(MAX_SYMBOL_RESULTS);
+    let symbols = state
+        .instruments
+        .search(query.q.as_deref(), query.exchange.as_deref(), limit);
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "symbols": symbols
+        "symbols": symbols,
+        "sources": state.market_sources.names(),
+        "updated_at": state.instruments.updated_at()
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_support::get;
+    use actix_web::http::StatusCode;
+
+    #[actix_rt::test]
+    async fn ticker_endpoint_serves_recorded_responses() {
+        let (status, body) = get("/api/market/ticker?symbol=BTC/USDT").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["success"], true);
+        let ticker = &body["data"][0];
+        assert_eq!(ticker["symbol"], "BTC/USDT");
+        assert_eq!(ticker["price"].to_string(), "67850.1");
+        assert_eq!(ticker["source"], "okx");
+        assert_eq!(ticker["market_cap"].as_f64(), Some(1_336_700_000_000.0));
+    }
+
+    #[actix_rt::test]
+    async fn kline_endpoint_serves_recorded_range() {
+        let (status, body) = get("/api/market/kline?symbol=BTC/USDT&interval=1h&source=okx\
+             &start_time=1717020000000&end_time=1717030800000")
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["success"], true);
+        assert_eq!(body["source"], "okx");
+        let data = body["data"].as_array().unwrap();
+        let timestamps: Vec<_> = data
+            .iter()
+            .map(|k| k["timestamp"].as_i64().unwrap())
+            .collect();
+        assert_eq!(
+            timestamps,
+            vec![
+                1_717_020_000_000,
+                1_717_023_600_000,
+                1_717_027_200_000,
+                1_717_030_800_000
+            ]
+        );
+        assert_eq!(data[1]["close"].to_string(), "67850.1");
+        assert_eq!(body["quality"]["missing_bars"], 0);
+        assert_eq!(body["quality"]["completeness"].as_f64(), Some(1.0));
+    }
+
+    #[actix_rt::test]
+    async fn kline_endpoint_defaults_to_latest_bars_before_now() {
+        let (status, body) =
+            get("/api/market/kline?symbol=BTC/USDT&interval=1h&source=okx&limit=3").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let timestamps: Vec<_> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|k| k["timestamp"].as_i64().unwrap())
+            .collect();
+        // 回放时间为 02:00，最后一根为正在形成的 02:00 K线
+        assert_eq!(
+            timestamps,
+            vec![1_717_027_200_000, 1_717_030_800_000, 1_717_034_400_000]
+        );
+        assert_eq!(body["data"][2]["close"].to_string(), "68230.5");
+    }
+
+    #[actix_rt::test]
+    async fn aggregate_kline_combines_recorded_sources() {
+        let (status, body) = get(
+            "/api/market/kline?symbol=BTC/USDT&interval=1h&source=aggregate\
+             &start_time=1717020000000&end_time=1717030800000",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["source"], "aggregate");
+        assert_eq!(body["method"], "median");
+        let sources: Vec<_> = body["sources"].as_array().unwrap().iter().collect();
+        assert_eq!(sources, vec!["okx", "binance"]);
+        let data = body["data"].as_array().unwrap();
+        assert_eq!(data.len(), 4);
+        // 两个数据源取中位数即均值
+        assert_eq!(data[1]["timestamp"], 1_717_023_600_000_i64);
+        assert_eq!(data[1]["close"].to_string(), "67855.1");
+        assert_eq!(data[1]["source_count"], 2);
+    }
+
+    #[actix_rt::test]
+    async fn orderbook_endpoint_merges_recorded_books() {
+        let (status, body) =
+            get("/api/market/orderbook?symbol=BTC/USDT&exchanges=okx,binance&depth=3&merge=true")
+                .await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["success"], true);
+        let books = body["books"].as_array().unwrap();
+        assert_eq!(books.len(), 2);
+        assert!(books
+            .iter()
+            .all(|b| b["bids"].as_array().unwrap().len() == 3));
+
+        let merged = &body["merged"];
+        assert_eq!(merged["bids"][0]["price"].to_string(), "67850.2");
+        assert_eq!(merged["bids"][0]["venues"][0]["exchange"], "binance");
+        assert_eq!(merged["asks"][0]["price"].to_string(), "67850.3");
+        assert_eq!(merged["asks"][0]["venues"][0]["exchange"], "okx");
+    }
+
+    #[actix_rt::test]
+    async fn trades_endpoint_returns_recorded_trades_with_cursor() {
+        let (status, body) = get("/api/market/trades?symbol=BTC/USDT&source=okx&limit=3").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["source"], "okx");
+        let ids: Vec<_> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["512345603", "512345602", "512345601"]);
+        assert_eq!(body["next_cursor"], "512345601");
+    }
+
+    #[actix_rt::test]
+    async fn compare_endpoint_reports_spread_between_venues() {
+        let (status, body) = get("/api/market/compare?symbol=BTC/USDT&exchanges=okx,binance").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["success"], true);
+        let data = &body["data"];
+        assert_eq!(data["venues"].as_array().unwrap().len(), 2);
+        assert_eq!(data["best_bid"]["source"], "binance");
+        assert_eq!(data["best_ask"]["source"], "okx");
+        assert_eq!(data["crossed"], false);
+    }
+
+    #[actix_rt::test]
+    async fn symbols_endpoint_searches_instrument_snapshots() {
+        let (status, body) = get("/api/market/symbols?q=eth").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let symbols: Vec<_> = body["symbols"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["symbol"].as_str().unwrap())
+            .collect();
+        // 基础币完全匹配且上架交易所多的排在前面
+        assert_eq!(symbols, vec!["ETH/USDT", "ETH/BTC"]);
+        assert_eq!(body["symbols"][0]["sources"].as_array().unwrap().len(), 2);
+        assert_eq!(body["updated_at"]["okx"], 1_717_027_200_000_i64);
+    }
+}
@@ -0,0 +1,115 @@
+use actix_web::{web, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::handlers::market_data::SourceErrors;
+use crate::services::market_data::MarketDataError;
+use crate::services::market_overview::{MarketOverview, MarketOverviewDay};
+use crate::AppState;
+
+/// 历史查询默认和最大天数
+const DEFAULT_HISTORY_DAYS: u32 = 90;
+const MAX_HISTORY_DAYS: u32 = 3650;
+
+#[derive(Debug, Serialize)]
+pub struct MarketOverviewResponse {
+    pub success: bool,
+    pub data: Option<MarketOverview>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverviewHistoryRequest {
+    /// 最近的天数，默认90
+    pub days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OverviewHistoryResponse {
+    pub success: bool,
+    pub data: Vec<MarketOverviewDay>,
+    pub message: Option<String>,
+}
+
+/// 全市场概览：总市值、24小时成交额、BTC/ETH 市值占比和恐惧贪婪指数
+pub async fn get_market_overview(state: web::Data<AppState>) -> Result<HttpResponse> {
+    match state.market_overview.get().await {
+        Ok(overview) => Ok(HttpResponse::Ok().json(MarketOverviewResponse {
+            success: true,
+            data: Some(overview),
+            message: None,
+        })),
+        Err(e) => {
+            println!("❌ 全市场概览获取失败: {}", e);
+            let mut errors = SourceErrors::default();
+            errors.record(&e);
+            let (mut response, message) = match e {
+                // 没有启用提供全市场统计的数据源（如 CoinGecko）
+                MarketDataError::Unsupported { .. } => {
+                    (HttpResponse::ServiceUnavailable(), e.to_string())
+                }
+                _ => (
+                    errors.response(),
+                    errors.message(format!("全市场概览获取失败: {}", e)),
+                ),
+            };
+            Ok(response.json(MarketOverviewResponse {
+                success: false,
+                data: None,
+                message: Some(message),
+            }))
+        }
+    }
+}
+
+/// 每日概览历史，用于绘制市值占比等走势
+pub async fn get_market_overview_history(
+    state: web::Data<AppState>,
+    query: web::Query<OverviewHistoryRequest>,
+) -> Result<HttpResponse> {
+    let days = query.days.unwrap_or(DEFAULT_HISTORY_DAYS);
+    if !(1..=MAX_HISTORY_DAYS).contains(&days) {
+        return Ok(HttpResponse::BadRequest().json(OverviewHistoryResponse {
+            success: false,
+            data: vec![],
+            message: Some(format!("days 必须在 1-{} 之间", MAX_HISTORY_DAYS)),
+        }));
+    }
+
+    match state.market_overview.history(days).await {
+        Ok(data) => Ok(HttpResponse::Ok().json(OverviewHistoryResponse {
+            success: true,
+            data,
+            message: None,
+        })),
+        Err(e) => {
+            log::error!("读取全市场概览历史失败: {}", e);
+            Ok(
+                HttpResponse::InternalServerError().json(OverviewHistoryResponse {
+                    success: false,
+                    data: vec![],
+                    message: Some("读取全市场概览历史失败".to_string()),
+                }),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_support::get;
+    use actix_web::http::StatusCode;
+
+    #[actix_rt::test]
+    async fn overview_endpoint_combines_global_metrics_and_fear_greed() {
+        let (status, body) = get("/api/market/overview").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["success"], true);
+        let overview = &body["data"];
+        assert_eq!(overview["source"], "coingecko");
+        assert_eq!(overview["btcDominance"].as_f64(), Some(50.1234));
+        assert_eq!(overview["totalVolume24h"].as_f64(), Some(81_512_345_678.9));
+        assert_eq!(overview["fearGreedIndex"], 74);
+        assert_eq!(overview["fearGreedLabel"], "Greed");
+    }
+}
@@ -0,0 +1,795 @@
+use actix::{
+    Actor, ActorContext, ActorFutureExt, AsyncContext, SpawnHandle, StreamHandler, WrapFuture,
+};
+use actix_web::{http::header::AUTHORIZATION, web, HttpRequest, HttpResponse, Result};
+use actix_web_actors::ws;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::services::market_data::interval::Interval;
+use crate::services::market_data::stream::{MarketEvent, StreamChannel, StreamTopic};
+use crate::services::market_data::{
+    KlineQuery, MarketDataError, MarketDataSource, PublicTrade, Symbol, TradeQuery,
+};
+use crate::AppState;
+
+/// 服务端发送 ping 的间隔
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// 超过该时间未收到客户端任何消息即断开
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+/// 匿名连接最多的订阅数
+const ANONYMOUS_MAX_SUBSCRIPTIONS: usize = 10;
+/// 已登录连接最多的订阅数
+const AUTHENTICATED_MAX_SUBSCRIPTIONS: usize = 50;
+/// 订单簿默认及最大档位数
+const DEFAULT_ORDER_BOOK_DEPTH: u32 = 20;
+const MAX_ORDER_BOOK_DEPTH: u32 = 100;
+/// 每次推送的最大成交条数
+const TRADES_PER_POLL: u32 = 50;
+/// 单个 IP 默认最多的并发连接数
+const DEFAULT_MAX_CONNECTIONS_PER_IP: usize = 20;
+/// 轮询缓存条目超过该数量时清理过期条目
+const MAX_POLL_ENTRIES: usize = 1000;
+
+/// 订阅频道
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Ticker,
+    Kline,
+    Orderbook,
+    Trade,
+}
+
+impl Channel {
+    /// 对应前端 `WebSocketMessage.type`
+    fn message_type(self) -> &'static str {
+        match self {
+            Channel::Ticker => "price",
+            Channel::Kline => "candle",
+            Channel::Orderbook => "orderbook",
+            Channel::Trade => "trade",
+        }
+    }
+
+    fn poll_interval(self) -> Duration {
+        match self {
+            Channel::Ticker | Channel::Orderbook | Channel::Trade => Duration::from_secs(2),
+            Channel::Kline => Duration::from_secs(5),
+        }
+    }
+}
+
+/// 客户端订阅参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscriptionRequest {
+    pub channel: Channel,
+    pub symbol: String,
+    /// K线周期，仅 `kline` 频道需要
+    pub interval: Option<String>,
+    /// 指定数据源，为空时按优先级回退
+    pub source: Option<String>,
+    /// 订单簿档位数，仅 `orderbook` 频道使用
+    pub depth: Option<u32>,
+}
+
+/// 客户端消息，如 `{"action":"subscribe","channel":"ticker","symbol":"BTCUSDT"}`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum ClientMessage {
+    Subscribe(SubscriptionRequest),
+    Unsubscribe(SubscriptionRequest),
+    Ping,
+}
+
+/// 规范化后的订阅，同一连接内唯一
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Subscription {
+    pub channel: Channel,
+    pub symbol: Symbol,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+}
+
+impl Subscription {
+    fn from_request(
+        request: SubscriptionRequest,
+        state: &AppState,
+    ) -> std::result::Result<Self, String> {
+        let symbol = state
+            .instruments
+            .resolve_symbol(&request.symbol)
+            .map_err(|e| e.to_string())?;
+        if let Some(source) = &request.source {
+            state
+                .market_sources
+                .get(source)
+                .map_err(|e| e.to_string())?;
+        }
+
+        let interval = match request.channel {
+            Channel::Kline => {
+                let interval = request
+                    .interval
+                    .ok_or_else(|| "kline 频道需要指定 interval".to_string())?;
+                let parsed = Interval::parse(&interval)
+                    .ok_or_else(|| format!("不支持的时间周期: {}", interval))?;
+                Some(parsed.to_string())
+            }
+            _ => None,
+        };
+        let depth = match request.channel {
+            Channel::Orderbook => Some(
+                request
+                    .depth
+                    .unwrap_or(DEFAULT_ORDER_BOOK_DEPTH)
+                    .clamp(1, MAX_ORDER_BOOK_DEPTH),
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            channel: request.channel,
+            symbol,
+            interval,
+            source: request.source,
+            depth,
+        })
+    }
+}
+
+/// 推送给客户端的消息，数据消息与前端 `WebSocketMessage` 一致
+#[derive(Debug, Serialize)]
+struct ServerMessage<'a> {
+    #[serde(rename = "type")]
+    message_type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subscription: Option<&'a Subscription>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    timestamp: i64,
+}
+
+/// 订阅的数据来源
+enum Feed {
+    /// 定时轮询 REST 接口
+    Poll(SpawnHandle),
+    /// 从实时行情总线接收指定数据源的推送
+    Stream {
+        source: &'static str,
+        topic: StreamTopic,
+    },
+}
+
+/// 单个 WebSocket 连接
+///
+/// 交易所有推送的频道（行情、K线、成交）从实时行情总线接收，其余按频道周期轮询数据源，
+/// 只推送有变化的数据；成交频道只推送新成交。
+pub struct MarketSession {
+    state: web::Data<AppState>,
+    feeds: web::Data<MarketFeeds>,
+    user_id: Option<String>,
+    /// 连接关闭（会话释放）时归还 IP 连接名额
+    _permit: Option<ConnectionPermit>,
+    last_heartbeat: Instant,
+    subscriptions: HashMap<Subscription, Feed>,
+    /// 是否已接入实时行情总线
+    bus_attached: bool,
+    in_flight: HashSet<Subscription>,
+    /// 每个订阅上次推送的内容（或错误），用于去重
+    last_payload: HashMap<Subscription, String>,
+    /// 成交频道上次推送的最新成交ID
+    last_trade_id: HashMap<Subscription, String>,
+}
+
+impl MarketSession {
+    pub fn new(
+        state: web::Data<AppState>,
+        feeds: web::Data<MarketFeeds>,
+        user_id: Option<String>,
+        permit: Option<ConnectionPermit>,
+    ) -> Self {
+        Self {
+            state,
+            feeds,
+            user_id,
+            _permit: permit,
+            last_heartbeat: Instant::now(),
+            subscriptions: HashMap::new(),
+            bus_attached: false,
+            in_flight: HashSet::new(),
+            last_payload: HashMap::new(),
+            last_trade_id: HashMap::new(),
+        }
+    }
+
+    fn max_subscriptions(&self) -> usize {
+        if self.user_id.is_some() {
+            AUTHENTICATED_MAX_SUBSCRIPTIONS
+        } else {
+            ANONYMOUS_MAX_SUBSCRIPTIONS
+        }
+    }
+
+    fn send(
+        ctx: &mut ws::WebsocketContext<Self>,
+        message_type: &str,
+        subscription: Option<&Subscription>,
+        data: Option<serde_json::Value>,
+        message: Option<String>,
+    ) {
+        let message = ServerMessage {
+            message_type,
+            subscription,
+            data,
+            message,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        };
+        if let Ok(text) = serde_json::to_string(&message) {
+            ctx.text(text);
+        }
+    }
+
+    fn heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            if act.last_heartbeat.elapsed() > CLIENT_TIMEOUT {
+                println!("💔 WebSocket 心跳超时，断开连接");
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"");
+        });
+    }
+
+    fn handle_text(&mut self, text: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => {
+                Self::send(ctx, "error", None, None, Some(format!("无效的消息: {}", e)));
+                return;
+            }
+        };
+
+        match message {
+            ClientMessage::Ping => Self::send(ctx, "pong", None, None, None),
+            ClientMessage::Subscribe(request) => self.subscribe(request, ctx),
+            ClientMessage::Unsubscribe(request) => {
+                match Subscription::from_request(request, &self.state) {
+                    Ok(subscription) => {
+                        match self.subscriptions.remove(&subscription) {
+                            Some(Feed::Poll(handle)) => {
+                                ctx.cancel_future(handle);
+                            }
+                            Some(Feed::Stream { source, topic }) => {
+                                self.state.market_stream.unwatch(source, &topic);
+                            }
+                            None => {}
+                        }
+                        self.last_payload.remove(&subscription);
+                        self.last_trade_id.remove(&subscription);
+                        Self::send(ctx, "unsubscribed", Some(&subscription), None, None);
+                    }
+                    Err(e) => Self::send(ctx, "error", None, None, Some(e)),
+                }
+            }
+        }
+    }
+
+    fn subscribe(&mut self, request: SubscriptionRequest, ctx: &mut ws::WebsocketContext<Self>) {
+        let subscription = match Subscription::from_request(request, &self.state) {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                Self::send(ctx, "error", None, None, Some(e));
+                return;
+            }
+        };
+
+        if self.subscriptions.contains_key(&subscription) {
+            Self::send(ctx, "subscribed", Some(&subscription), None, None);
+            return;
+        }
+        if self.subscriptions.len() >= self.max_subscriptions() {
+            Self::send(
+                ctx,
+                "error",
+                Some(&subscription),
+                None,
+                Some(format!("订阅数已达上限 {}", self.max_subscriptions())),
+            );
+            return;
+        }
+
+        let feed = match self.stream_feed(&subscription) {
+            Some((source, topic)) => {
+                self.attach_bus(ctx);
+                Feed::Stream { source, topic }
+            }
+            None => {
+                let polled = subscription.clone();
+                Feed::Poll(ctx.run_interval(
+                    subscription.channel.poll_interval(),
+                    move |act, ctx| {
+                        act.poll(polled.clone(), ctx);
+                    },
+                ))
+            }
+        };
+        self.subscriptions.insert(subscription.clone(), feed);
+        Self::send(ctx, "subscribed", Some(&subscription), None, None);
+        // 立即拉取一次作为初始快照，推送订阅之后只有增量
+        self.poll(subscription, ctx);
+    }
+
+    /// 选择推送该订阅的数据源并订阅上游主题，不支持推送时返回 `None`
+    ///
+    /// 未指定数据源时按优先级选择上架了该交易对的推送源。
+    fn stream_feed(&self, subscription: &Subscription) -> Option<(&'static str, StreamTopic)> {
+        let channel = match subscription.channel {
+            Channel::Ticker => StreamChannel::Ticker,
+            Channel::Kline => StreamChannel::Kline(subscription.interval.clone()?),
+            Channel::Trade => StreamChannel::Trade,
+            Channel::Orderbook => return None,
+        };
+        let stream = &self.state.market_stream;
+        let streamed = stream.sources(&channel);
+        let source = match &subscription.source {
+            Some(source) => streamed.into_iter().find(|s| s == source)?,
+            None => self.state.market_sources.names().into_iter().find(|name| {
+                streamed.contains(name)
+                    && (self.state.instruments.is_empty()
+                        || self
+                            .state
+                            .instruments
+                            .find(name, &subscription.symbol)
+                            .is_some())
+            })?,
+        };
+
+        let topic = StreamTopic::new(channel, subscription.symbol.clone());
+        stream
+            .watch(source, topic.clone())
+            .then_some((source, topic))
+    }
+
+    fn attach_bus(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        if self.bus_attached {
+            return;
+        }
+        self.bus_attached = true;
+
+        let receiver = self.state.market_stream.subscribe();
+        ctx.add_stream(futures_util::stream::unfold(
+            receiver,
+            |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) => return Some((event, receiver)),
+                        // 处理不及时被覆盖的事件直接跳过
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            },
+        ));
+    }
+
+    /// 拉取一次订阅数据，上一次请求未返回时跳过
+    fn poll(&mut self, subscription: Subscription, ctx: &mut ws::WebsocketContext<Self>) {
+        if !self.in_flight.insert(subscription.clone()) {
+            return;
+        }
+
+        let state = self.state.clone();
+        let feeds = self.feeds.clone();
+        let request = subscription.clone();
+        let fut = async move { feeds.poll(&state, &request).await };
+
+        ctx.spawn(fut.into_actor(self).map(move |result, act, ctx| {
+            act.in_flight.remove(&subscription);
+            // 已取消的订阅丢弃迟到的结果
+            if !act.subscriptions.contains_key(&subscription) {
+                return;
+            }
+
+            let (message_type, data, message) = match result {
+                Ok(Polled::Value(data)) => (subscription.channel.message_type(), Some(data), None),
+                Ok(Polled::Trades(trades)) => {
+                    let Some(newest) = trades.first() else {
+                        return;
+                    };
+                    let since = act
+                        .last_trade_id
+                        .insert(subscription.clone(), newest.id.clone());
+                    // 成交按从新到旧排列，截取到上次推送的成交为止
+                    let fresh: Vec<_> = trades
+                        .iter()
+                        .take_while(|t| Some(&t.id) != since.as_ref())
+                        .collect();
+                    if fresh.is_empty() {
+                        return;
+                    }
+                    (
+                        subscription.channel.message_type(),
+                        Some(serde_json::json!(fresh)),
+                        None,
+                    )
+                }
+                Err(e) => ("error", None, Some(e.to_string())),
+            };
+
+            let payload = format!("{}:{:?}:{:?}", message_type, data, message);
+            if act.last_payload.get(&subscription) == Some(&payload) {
+                return;
+            }
+            act.last_payload.insert(subscription.clone(), payload);
+            Self::send(ctx, message_type, Some(&subscription), data, message);
+        }));
+    }
+}
+
+impl Actor for MarketSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.heartbeat(ctx);
+    }
+
+    /// 连接关闭时释放仍在使用的上游推送主题
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        for (_, feed) in self.subscriptions.drain() {
+            if let Feed::Stream { source, topic } = feed {
+                self.state.market_stream.unwatch(source, &topic);
+            }
+        }
+    }
+}
+
+impl StreamHandler<std::result::Result<ws::Message, ws::ProtocolError>> for MarketSession {
+    fn handle(
+        &mut self,
+        msg: std::result::Result<ws::Message, ws::ProtocolError>,
+        ctx: &mut Self::Context,
+    ) {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(e) => {
+                println!("❌ WebSocket 协议错误: {}", e);
+                ctx.stop();
+                return;
+            }
+        };
+
+        self.last_heartbeat = Instant::now();
+        match msg {
+            ws::Message::Ping(bytes) => ctx.pong(&bytes),
+            ws::Message::Pong(_) => {}
+            ws::Message::Text(text) => self.handle_text(&text, ctx),
+            ws::Message::Binary(_) => Self::send(
+                ctx,
+                "error",
+                None,
+                None,
+                Some("不支持二进制消息".to_string()),
+            ),
+            ws::Message::Close(reason) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            ws::Message::Continuation(_) | ws::Message::Nop => {}
+        }
+    }
+}
+
+impl StreamHandler<MarketEvent> for MarketSession {
+    fn handle(&mut self, event: MarketEvent, ctx: &mut Self::Context) {
+        for (subscription, feed) in &self.subscriptions {
+            let Feed::Stream { source, topic } = feed else {
+                continue;
+            };
+            if event.source() != *source || !event.matches(topic) {
+                continue;
+            }
+            // K线和成交与轮询结果一样以数组推送
+            let data = match &event {
+                MarketEvent::Ticker(ticker) => serde_json::json!(ticker),
+                MarketEvent::Kline(kline) => serde_json::json!([kline]),
+                MarketEvent::Trade(trade) => serde_json::json!([trade]),
+            };
+            Self::send(
+                ctx,
+                subscription.channel.message_type(),
+                Some(subscription),
+                Some(data),
+                None,
+            );
+        }
+    }
+
+    /// 总线关闭不影响轮询的订阅，保持连接
+    fn finished(&mut self, _ctx: &mut Self::Context) {}
+}
+
+/// 一次轮询得到的数据，在订阅相同的连接间共享
+#[derive(Clone)]
+enum Polled {
+    Value(serde_json::Value),
+    /// 最近的成交（从新到旧），各连接自行截取未推送的部分
+    Trades(Arc<Vec<PublicTrade>>),
+}
+
+struct CachedPoll {
+    data: Polled,
+    fetched_at: Instant,
+}
+
+type PollSlot = Arc<tokio::sync::Mutex<Option<CachedPoll>>>;
+
+/// 各连接共享的轮询结果与连接计数
+///
+/// 订阅相同（频道、交易对、数据源等参数一致）的连接共用一份轮询结果，
+/// 频道轮询周期内同一订阅只请求一次上游；并发的请求等待进行中的请求返回。
+pub struct MarketFeeds {
+    max_connections_per_ip: usize,
+    connections: Mutex<HashMap<IpAddr, usize>>,
+    polls: Mutex<HashMap<Subscription, PollSlot>>,
+}
+
+impl MarketFeeds {
+    pub fn new(max_connections_per_ip: usize) -> Self {
+        Self {
+            max_connections_per_ip,
+            connections: Mutex::new(HashMap::new()),
+            polls: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 单个 IP 的连接上限由 `MARKET_WS_MAX_CONNECTIONS_PER_IP` 指定
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("MARKET_WS_MAX_CONNECTIONS_PER_IP")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_MAX_CONNECTIONS_PER_IP),
+        )
+    }
+
+    /// 占用一个连接名额，该 IP 的连接数已达上限时返回 `None`
+    fn connect(feeds: &web::Data<Self>, ip: IpAddr) -> Option<ConnectionPermit> {
+        let mut connections = feeds.connections.lock().unwrap();
+        let count = connections.entry(ip).or_insert(0);
+        if *count >= feeds.max_connections_per_ip {
+            return None;
+        }
+        *count += 1;
+        Some(ConnectionPermit {
+            feeds: feeds.clone(),
+            ip,
+        })
+    }
+
+    /// 获取订阅数据，轮询周期内的结果直接复用
+    async fn poll(
+        &self,
+        state: &AppState,
+        subscription: &Subscription,
+    ) -> Result<Polled, MarketDataError> {
+        let slot = {
+            let mut polls = self.polls.lock().unwrap();
+            if polls.len() >= MAX_POLL_ENTRIES {
+                // 正在请求的条目无法加锁，保留
+                polls.retain(|key, slot| {
+                    slot.try_lock().map_or(true, |cached| {
+                        cached
+                            .as_ref()
+                            .is_some_and(|c| c.fetched_at.elapsed() < key.channel.poll_interval())
+                    })
+                });
+            }
+            polls.entry(subscription.clone()).or_default().clone()
+        };
+
+        let mut cached = slot.lock().await;
+        if let Some(c) = cached
+            .as_ref()
+            .filter(|c| c.fetched_at.elapsed() < subscription.channel.poll_interval())
+        {
+            return Ok(c.data.clone());
+        }
+        let data = fetch_channel(state, subscription).await?;
+        *cached = Some(CachedPoll {
+            data: data.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(data)
+    }
+}
+
+/// 连接名额，释放时归还
+pub struct ConnectionPermit {
+    feeds: web::Data<MarketFeeds>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        let mut connections = self.feeds.connections.lock().unwrap();
+        if let Some(count) = connections.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                connections.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// 订阅指定了数据源时只用该源，否则按优先级选出支持该频道的数据源
+fn channel_sources(
+    state: &AppState,
+    subscription: &Subscription,
+) -> Result<Vec<Arc<dyn MarketDataSource>>, MarketDataError> {
+    if let Some(source) = &subscription.source {
+        return Ok(vec![state.market_sources.get(source)?]);
+    }
+    Ok(state
+        .market_sources
+        .sources()
+        .iter()
+        .filter(|s| {
+            let capabilities = s.capabilities();
+            match subscription.channel {
+                Channel::Ticker => capabilities.ticker,
+                Channel::Kline => capabilities.klines,
+                Channel::Orderbook => capabilities.order_book,
+                Channel::Trade => capabilities.trades,
+            }
+        })
+        .map(|s| s.clone() as Arc<dyn MarketDataSource>)
+        .collect())
+}
+
+async fn fetch_channel(
+    state: &AppState,
+    subscription: &Subscription,
+) -> Result<Polled, MarketDataError> {
+    if subscription.channel == Channel::Ticker {
+        let ticker = state
+            .tickers
+            .get(&subscription.symbol, subscription.source.as_deref())
+            .await?;
+        return Ok(Polled::Value(serde_json::json!(ticker)));
+    }
+
+    let mut last_error = None;
+    for source in channel_sources(state, subscription)? {
+        let result = match subscription.channel {
+            Channel::Ticker => unreachable!(),
+            Channel::Kline => {
+                // 推送最近两根K线：刚收盘的一根和正在形成的一根
+                let query = KlineQuery {
+                    symbol: subscription.symbol.clone(),
+                    interval: subscription.interval.clone().unwrap_or_default(),
+                    limit: 2,
+                    start_time: None,
+                    end_time: None,
+                };
+                state
+                    .candle_store
+                    .get_klines(source.as_ref(), &query)
+                    .await
+                    .map(|klines| Polled::Value(serde_json::json!(klines)))
+            }
+            Channel::Orderbook => source
+                .fetch_order_book(
+                    &subscription.symbol,
+                    subscription.depth.unwrap_or(DEFAULT_ORDER_BOOK_DEPTH),
+                )
+                .await
+                .map(|book| Polled::Value(serde_json::json!(book))),
+            Channel::Trade => source
+                .fetch_trades(&TradeQuery {
+                    symbol: subscription.symbol.clone(),
+                    limit: TRADES_PER_POLL,
+                    before: None,
+                })
+                .await
+                .map(|trades| Polled::Trades(Arc::new(trades))),
+        };
+
+        match result {
+            Ok(data) => return Ok(data),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or(MarketDataError::Unsupported {
+        exchange: "market",
+        operation: "实时推送",
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarketWsQuery {
+    /// 可选的访问令牌，浏览器 WebSocket 无法设置请求头时使用
+    pub token: Option<String>,
+}
+
+/// 实时行情 WebSocket 端点 `/ws/market`
+///
+/// 令牌可选：通过 `Authorization: Bearer` 或 `?token=` 传入，已登录连接的订阅上限更高；
+/// 传入无效令牌或该 IP 连接数已达上限时拒绝连接。
+pub async fn market_ws(
+    req: HttpRequest,
+    stream: web::Payload,
+    state: web::Data<AppState>,
+    feeds: web::Data<MarketFeeds>,
+    query: web::Query<MarketWsQuery>,
+) -> Result<HttpResponse> {
+    let header_token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "));
+    let token = header_token.or(query.token.as_deref());
+
+    let user_id = match token {
+        Some(token) => match state.auth_service.verify_token(token) {
+            Ok(claims) => Some(claims.sub),
+            Err(_) => {
+                return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                    "success": false,
+                    "message": "无效的访问令牌"
+                })));
+            }
+        },
+        None => None,
+    };
+
+    let permit = match req.peer_addr() {
+        Some(addr) => match MarketFeeds::connect(&feeds, addr.ip()) {
+            Some(permit) => Some(permit),
+            None => {
+                return Ok(HttpResponse::TooManyRequests().json(serde_json::json!({
+                    "success": false,
+                    "message": format!("该 IP 的连接数已达上限 {}", feeds.max_connections_per_ip)
+                })));
+            }
+        },
+        None => None,
+    };
+
+    ws::start(
+        MarketSession::new(state, feeds, user_id, permit),
+        &req,
+        stream,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_connections_per_ip_and_releases_on_drop() {
+        let feeds = web::Data::new(MarketFeeds::new(2));
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let first = MarketFeeds::connect(&feeds, ip).unwrap();
+        let _second = MarketFeeds::connect(&feeds, ip).unwrap();
+        assert!(MarketFeeds::connect(&feeds, ip).is_none());
+        // 其他 IP 不受影响
+        assert!(MarketFeeds::connect(&feeds, "10.0.0.2".parse().unwrap()).is_some());
+
+        drop(first);
+        assert!(MarketFeeds::connect(&feeds, ip).is_some());
+    }
+}
@@ -1,6 +1,13 @@
 pub mod auth;
+pub mod candle_import;
 pub mod device;
+pub mod export;
 pub mod market_data;
+pub mod market_overview;
+pub mod market_ws;
+pub mod perpetual;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod watchlist;
 
 pub use auth::*;
@@ -0,0 +1,494 @@
+use actix_web::{web, HttpResponse, Result};
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::handlers::market_data::{SourceErrors, SourceFailure};
+use crate::services::market_data::basis::{basis_series, BasisPoint};
+use crate::services::market_data::interval::Interval;
+use crate::services::market_data::quality;
+use crate::services::market_data::{
+    FundingQuery, KlineQuery, MarketDataError, MarketDataSource, PriceKind, Symbol,
+};
+use crate::AppState;
+
+/// 历史查询单次最多返回的记录数
+const MAX_HISTORY_LIMIT: u32 = 1000;
+/// 历史查询默认返回的记录数
+const DEFAULT_HISTORY_LIMIT: u32 = 100;
+/// 持仓量历史和基差的默认统计周期
+const DEFAULT_INTERVAL: &str = "1h";
+
+#[derive(Debug, Deserialize)]
+pub struct PerpSnapshotRequest {
+    pub symbol: String,
+    /// 指定数据源，为空时查询所有提供永续合约数据的数据源
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PerpSnapshotResponse<T> {
+    pub success: bool,
+    pub symbol: String,
+    pub data: Vec<T>,
+    pub failed_sources: Vec<SourceFailure>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PerpHistoryRequest {
+    pub symbol: String,
+    /// 统计周期（持仓量历史、标记/指数价格K线、基差使用）
+    pub interval: Option<String>,
+    pub limit: Option<u32>,
+    /// 区间起点（毫秒时间戳，含）
+    pub start_time: Option<i64>,
+    /// 区间终点（毫秒时间戳，含）
+    pub end_time: Option<i64>,
+    /// 指定数据源，为空时按优先级回退
+    pub source: Option<String>,
+    /// 价格K线类型：`mark`（默认）或 `index`
+    pub kind: Option<PriceKind>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PerpHistoryResponse<T> {
+    pub success: bool,
+    pub data: Vec<T>,
+    pub source: String,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BasisResponse {
+    pub success: bool,
+    pub symbol: String,
+    pub source: String,
+    /// 当前基差（现货最新价 vs 标记价格）
+    pub current: Option<BasisPoint>,
+    /// 按周期收盘价计算的基差走势
+    pub history: Vec<BasisPoint>,
+    pub message: Option<String>,
+}
+
+/// 指定的数据源，或按优先级排列的所有提供永续合约数据的数据源
+fn perp_sources(
+    state: &AppState,
+    source: Option<&str>,
+) -> std::result::Result<Vec<Arc<dyn MarketDataSource>>, MarketDataError> {
+    match source {
+        Some(name) => Ok(vec![state.market_sources.get(name)?]),
+        None => Ok(state
+            .market_sources
+            .sources()
+            .iter()
+            .filter(|s| s.capabilities().perpetuals)
+            .map(|s| s.clone() as Arc<dyn MarketDataSource>)
+            .collect()),
+    }
+}
+
+/// 依次尝试各数据源，返回第一个成功的结果；全部失败时返回失败汇总和最后一个错误
+async fn first_success<T, F, Fut>(
+    sources: Vec<Arc<dyn MarketDataSource>>,
+    operation: &str,
+    fetch: F,
+) -> std::result::Result<(T, &'static str), (SourceErrors, String)>
+where
+    F: Fn(Arc<dyn MarketDataSource>) -> Fut,
+    Fut: Future<Output = std::result::Result<T, MarketDataError>>,
+{
+    let mut last_error = None;
+    let mut errors = SourceErrors::default();
+    for source in sources {
+        let name = source.name();
+        match fetch(source).await {
+            Ok(data) => return Ok((data, name)),
+            Err(e) => {
+                println!("{}: 源 {} 失败: {}", operation, name, e);
+                errors.record(&e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    let message = last_error
+        .map(|e| e.to_string())
+        .unwrap_or_else(|| format!("没有数据源提供{}", operation));
+    Err((errors, message))
+}
+
+/// 并行查询各数据源的当前快照
+async fn snapshot<T, F, Fut>(
+    state: &AppState,
+    query: &PerpSnapshotRequest,
+    operation: &str,
+    fetch: F,
+) -> Result<HttpResponse>
+where
+    T: Serialize,
+    F: Fn(Arc<dyn MarketDataSource>, Symbol) -> Fut,
+    Fut: Future<Output = std::result::Result<T, MarketDataError>>,
+{
+    let error_response = |mut response: actix_web::HttpResponseBuilder, message: String| {
+        Ok(response.json(PerpSnapshotResponse::<T> {
+            success: false,
+            symbol: query.symbol.clone(),
+            data: vec![],
+            failed_sources: vec![],
+            message: Some(message),
+        }))
+    };
+
+    let symbol = match state.instruments.resolve_symbol(&query.symbol) {
+        Ok(symbol) => symbol,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+    let sources = match perp_sources(state, query.source.as_deref()) {
+        Ok(sources) => sources,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+
+    let results = join_all(
+        sources
+            .iter()
+            .map(|source| fetch(source.clone(), symbol.clone())),
+    )
+    .await;
+
+    let mut data = Vec::new();
+    let mut failed_sources = Vec::new();
+    let mut errors = SourceErrors::default();
+    for (source, result) in sources.iter().zip(results) {
+        match result {
+            Ok(item) => data.push(item),
+            Err(e) => {
+                println!("{}: 源 {} 失败: {}", operation, source.name(), e);
+                errors.record(&e);
+                failed_sources.push(SourceFailure {
+                    source: source.name().to_string(),
+                    message: e.to_string(),
+                });
+            }
+        }
+    }
+
+    let (mut response, message) = if !data.is_empty() {
+        (HttpResponse::Ok(), None)
+    } else {
+        (
+            errors.response(),
+            Some(errors.message(format!("没有数据源提供 {} 的{}", symbol, operation))),
+        )
+    };
+
+    Ok(response.json(PerpSnapshotResponse {
+        success: !data.is_empty(),
+        symbol: symbol.to_string(),
+        data,
+        failed_sources,
+        message,
+    }))
+}
+
+/// 历史数据查询：解析参数后按数据源优先级回退
+async fn history<T, F, Fut>(
+    state: &AppState,
+    query: &PerpHistoryRequest,
+    operation: &str,
+    fetch: F,
+) -> Result<HttpResponse>
+where
+    T: Serialize,
+    F: Fn(Arc<dyn MarketDataSource>, KlineQuery) -> Fut,
+    Fut: Future<Output = std::result::Result<Vec<T>, MarketDataError>>,
+{
+    let error_response = |mut response: actix_web::HttpResponseBuilder, message: String| {
+        Ok(response.json(PerpHistoryResponse::<T> {
+            success: false,
+            data: vec![],
+            source: "none".to_string(),
+            message: Some(message),
+        }))
+    };
+
+    let kline_query = match history_query(state, query) {
+        Ok(kline_query) => kline_query,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+    let sources = match perp_sources(state, query.source.as_deref()) {
+        Ok(sources) => sources,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+
+    match first_success(sources, operation, |source| {
+        fetch(source, kline_query.clone())
+    })
+    .await
+    {
+        Ok((data, source)) => Ok(HttpResponse::Ok().json(PerpHistoryResponse {
+            success: true,
+            data,
+            source: source.to_string(),
+            message: None,
+        })),
+        Err((errors, message)) => error_response(errors.response(), message),
+    }
+}
+
+fn history_query(
+    state: &AppState,
+    query: &PerpHistoryRequest,
+) -> std::result::Result<KlineQuery, MarketDataError> {
+    Ok(KlineQuery {
+        symbol: state.instruments.resolve_symbol(&query.symbol)?,
+        interval: query
+            .interval
+            .clone()
+            .unwrap_or_else(|| DEFAULT_INTERVAL.to_string()),
+        limit: query
+            .limit
+            .unwrap_or(DEFAULT_HISTORY_LIMIT)
+            .clamp(1, MAX_HISTORY_LIMIT),
+        start_time: query.start_time,
+        end_time: query.end_time,
+    })
+}
+
+/// 当前资金费率
+pub async fn get_funding_rate(
+    state: web::Data<AppState>,
+    query: web::Query<PerpSnapshotRequest>,
+) -> Result<HttpResponse> {
+    snapshot(&state, &query, "资金费率", |source, symbol| async move {
+        source.fetch_funding_rate(&symbol).await
+    })
+    .await
+}
+
+/// 资金费率历史，先同步到本地存储再读取，数据源不可用时返回已存储的记录
+pub async fn get_funding_history(
+    state: web::Data<AppState>,
+    query: web::Query<PerpHistoryRequest>,
+) -> Result<HttpResponse> {
+    let store = state.funding_store.clone();
+    history(
+        &state,
+        &query,
+        "资金费率历史",
+        |source, kline_query| {
+            let store = store.clone();
+            async move {
+                let funding_query = FundingQuery {
+                    symbol: kline_query.symbol,
+                    limit: kline_query.limit,
+                    start_time: kline_query.start_time,
+                    end_time: kline_query.end_time,
+                };
+                store.get_history(source.as_ref(), &funding_query).await
+            }
+        },
+    )
+    .await
+}
+
+/// 当前持仓量
+pub async fn get_open_interest(
+    state: web::Data<AppState>,
+    query: web::Query<PerpSnapshotRequest>,
+) -> Result<HttpResponse> {
+    snapshot(&state, &query, "持仓量", |source, symbol| async move {
+        source.fetch_open_interest(&symbol).await
+    })
+    .await
+}
+
+/// 持仓量历史，`interval` 为统计周期（默认 1h）
+pub async fn get_open_interest_history(
+    state: web::Data<AppState>,
+    query: web::Query<PerpHistoryRequest>,
+) -> Result<HttpResponse> {
+    history(
+        &state,
+        &query,
+        "持仓量历史",
+        |source, kline_query| async move { source.fetch_open_interest_history(&kline_query).await },
+    )
+    .await
+}
+
+/// 标记价格（`kind=mark`）或指数价格（`kind=index`）K线，经过与现货K线相同的修正
+pub async fn get_price_klines(
+    state: web::Data<AppState>,
+    query: web::Query<PerpHistoryRequest>,
+) -> Result<HttpResponse> {
+    let kind = query.kind.unwrap_or(PriceKind::Mark);
+    history(
+        &state,
+        &query,
+        "标记/指数价格K线",
+        |source, kline_query| async move {
+            let interval = Interval::parse(&kline_query.interval)
+                .ok_or_else(|| MarketDataError::InvalidInterval(kline_query.interval.clone()))?;
+            let klines = source.fetch_price_klines(kind, &kline_query).await?;
+            Ok(quality::sanitize(klines, &interval).0)
+        },
+    )
+    .await
+}
+
+/// 永续合约相对同一交易所现货的基差：当前值和按周期的走势
+pub async fn get_basis(
+    state: web::Data<AppState>,
+    query: web::Query<PerpHistoryRequest>,
+) -> Result<HttpResponse> {
+    let error_response = |mut response: actix_web::HttpResponseBuilder, message: String| {
+        Ok(response.json(BasisResponse {
+            success: false,
+            symbol: query.symbol.clone(),
+            source: "none".to_string(),
+            current: None,
+            history: vec![],
+            message: Some(message),
+        }))
+    };
+
+    let kline_query = match history_query(&state, &query) {
+        Ok(kline_query) => kline_query,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+    let sources = match perp_sources(&state, query.source.as_deref()) {
+        Ok(sources) => sources,
+        Err(e) => return error_response(HttpResponse::BadRequest(), e.to_string()),
+    };
+
+    let result = first_success(sources, "基差", |source| {
+        let kline_query = &kline_query;
+        async move {
+            let (spot, perp, ticker, mark) = futures_util::join!(
+                source.fetch_klines(kline_query),
+                source.fetch_price_klines(PriceKind::Mark, kline_query),
+                source.fetch_ticker(&kline_query.symbol),
+                source.fetch_mark_price(&kline_query.symbol),
+            );
+            let history = basis_series(&spot?, &perp?);
+            // 当前基差取两者中较晚的时间
+            let current = match (ticker, mark) {
+                (Ok(ticker), Ok(mark)) => Some(BasisPoint::new(
+                    ticker.timestamp.max(mark.timestamp),
+                    ticker.price,
+                    mark.mark_price,
+                )),
+                _ => None,
+            };
+            Ok((current, history))
+        }
+    })
+    .await;
+
+    match result {
+        Ok(((current, history), source)) => Ok(HttpResponse::Ok().json(BasisResponse {
+            success: true,
+            symbol: kline_query.symbol.to_string(),
+            source: source.to_string(),
+            current,
+            history,
+            message: None,
+        })),
+        Err((errors, message)) => error_response(errors.response(), message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handlers::test_support::get;
+    use actix_web::http::StatusCode;
+
+    #[actix_rt::test]
+    async fn funding_endpoint_serves_recorded_rate() {
+        let (status, body) = get("/api/market/perp/funding?symbol=BTC/USDT&source=okx").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["success"], true);
+        let rate = &body["data"][0];
+        assert_eq!(rate["source"], "okx");
+        assert_eq!(rate["funding_rate"].to_string(), "0.000085");
+        assert_eq!(rate["next_funding_time"], 1_717_056_000_000_i64);
+    }
+
+    #[actix_rt::test]
+    async fn funding_history_falls_back_to_source_without_storage() {
+        let (status, body) =
+            get("/api/market/perp/funding/history?symbol=BTC/USDT&source=okx&limit=2").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["source"], "okx");
+        let times: Vec<_> = body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["funding_time"].as_i64().unwrap())
+            .collect();
+        // 未指定起点时保留最新的记录，按时间升序
+        assert_eq!(times, vec![1_717_005_600_000, 1_717_027_200_000]);
+    }
+
+    #[actix_rt::test]
+    async fn open_interest_endpoint_serves_recorded_snapshot() {
+        let (status, body) = get("/api/market/perp/open-interest?symbol=BTC/USDT&source=okx").await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let oi = &body["data"][0];
+        assert_eq!(oi["open_interest"].to_string(), "24512.37");
+        assert_eq!(oi["open_interest_value"].to_string(), "1663512345.6");
+    }
+
+    #[actix_rt::test]
+    async fn open_interest_history_is_returned_oldest_first() {
+        let (status, body) = get(
+            "/api/market/perp/open-interest/history?symbol=BTC/USDT&source=okx&interval=1h&limit=3",
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let data = body["data"].as_array().unwrap();
+        let times: Vec<_> = data
+            .iter()
+            .map(|r| r["timestamp"].as_i64().unwrap())
+            .collect();
+        assert_eq!(
+            times,
+            vec![1_717_027_200_000, 1_717_030_800_000, 1_717_034_400_000]
+        );
+        assert_eq!(data[2]["open_interest"].to_string(), "24512.37");
+    }
+
+    #[actix_rt::test]
+    async fn price_kline_endpoint_serves_recorded_mark_candles() {
+        let (status, body) = get("/api/market/perp/price-kline?symbol=BTC/USDT&source=okx\
+             &interval=1h&limit=4&start_time=1717020000000&end_time=1717030800000")
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let data = body["data"].as_array().unwrap();
+        assert_eq!(data.len(), 4);
+        assert_eq!(data[0]["timestamp"], 1_717_020_000_000_i64);
+        assert_eq!(data[3]["close"].to_string(), "68131.2");
+    }
+
+    #[actix_rt::test]
+    async fn basis_endpoint_compares_mark_and_spot_candles() {
+        let (status, body) = get("/api/market/perp/basis?symbol=BTC/USDT&source=okx\
+             &interval=1h&limit=4&start_time=1717020000000&end_time=1717030800000")
+        .await;
+
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["source"], "okx");
+        let history = body["history"].as_array().unwrap();
+        assert_eq!(history.len(), 4);
+        // 03:00 收盘: 标记价格 68131.2，现货 68105.7
+        assert_eq!(history[3]["basis"].to_string(), "25.5");
+        assert_eq!(body["current"]["perp_price"].to_string(), "67872.4");
+    }
+}
@@ -0,0 +1,121 @@
+//! 处理器测试共用的回放环境：上游响应来自录制文件，数据库不可用
+
+use actix_web::{http::StatusCode, test, web, App};
+use sea_orm::SqlxMySqlConnector;
+use serde_json::Value;
+use sqlx::mysql::MySqlPoolOptions;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::handlers::{export, market_data, market_overview, perpetual};
+use crate::middleware::DecimalOutput;
+use crate::services::{
+    AuthService, CandleImporter, CandleStore, Clock, FundingStore, HttpMode, InstrumentRegistry,
+    MarketDataRegistry, MarketOverviewService, MarketStream, TickerService,
+};
+use crate::AppState;
+
+/// 回放使用的固定时间（2024-05-30 02:00 UTC），依赖当前时间的请求地址与录制时一致
+pub(crate) const REPLAY_CLOCK: Clock = Clock::Fixed(1_717_034_400_000);
+
+fn fixture_dir(name: &str) -> PathBuf {
+    PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures")).join(name)
+}
+
+/// 回放录制响应、数据库不可用的应用状态，数据库读写失败时各服务按缓存未命中处理
+///
+/// 交易对注册表读取 `tests/fixtures/instruments` 中的快照。
+pub(crate) async fn replay_state() -> web::Data<AppState> {
+    // 延迟连接到无人监听的端口，查询立即失败而不会访问真实数据库
+    let pool = MySqlPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("mysql://test@127.0.0.1:1/test")
+        .unwrap();
+    let db = SqlxMySqlConnector::from_sqlx_mysql_pool(pool);
+    let mode = HttpMode::Replay(fixture_dir("recorded"));
+    let sources = Arc::new(MarketDataRegistry::with_default_sources(mode));
+    let candle_store = Arc::new(CandleStore::new(db.clone(), REPLAY_CLOCK));
+    let instruments = Arc::new(InstrumentRegistry::new(fixture_dir("instruments")));
+    instruments.load_snapshots().await;
+    web::Data::new(AppState {
+        auth_service: Arc::new(AuthService::new(db.clone(), "test".to_string())),
+        candle_imports: Arc::new(CandleImporter::new(
+            db.clone(),
+            candle_store.clone(),
+            std::env::temp_dir(),
+        )),
+        candle_store,
+        funding_store: Arc::new(FundingStore::new(db.clone())),
+        instruments,
+        tickers: Arc::new(TickerService::new(
+            sources.clone(),
+            Duration::from_secs(10),
+            Duration::from_secs(300),
+        )),
+        market_stream: Arc::new(MarketStream::new()),
+        market_overview: Arc::new(MarketOverviewService::new(
+            db.clone(),
+            sources.clone(),
+            Duration::from_secs(60),
+        )),
+        market_sources: sources,
+        clock: REPLAY_CLOCK,
+        db,
+    })
+}
+
+/// 按 `main.rs` 注册的行情路由（不含需要登录的导入接口）请求，返回状态码和响应原文
+pub(crate) async fn get_text(uri: &str) -> (StatusCode, String) {
+    let app = test::init_service(
+        App::new().app_data(replay_state().await).service(
+            web::scope("/api").wrap(DecimalOutput).service(
+                web::scope("/market")
+                    .route("/kline", web::get().to(market_data::get_kline_data))
+                    .route("/kline/export", web::get().to(export::export_klines))
+                    .route("/ticker", web::get().to(market_data::get_ticker))
+                    .route("/orderbook", web::get().to(market_data::get_order_book))
+                    .route("/trades", web::get().to(market_data::get_trades))
+                    .route("/compare", web::get().to(market_data::get_compare))
+                    .route(
+                        "/overview",
+                        web::get().to(market_overview::get_market_overview),
+                    )
+                    .service(
+                        web::scope("/perp")
+                            .route("/funding", web::get().to(perpetual::get_funding_rate))
+                            .route(
+                                "/funding/history",
+                                web::get().to(perpetual::get_funding_history),
+                            )
+                            .route(
+                                "/open-interest",
+                                web::get().to(perpetual::get_open_interest),
+                            )
+                            .route(
+                                "/open-interest/history",
+                                web::get().to(perpetual::get_open_interest_history),
+                            )
+                            .route("/price-kline", web::get().to(perpetual::get_price_klines))
+                            .route("/basis", web::get().to(perpetual::get_basis)),
+                    )
+                    .route(
+                        "/symbols",
+                        web::get().to(market_data::get_supported_symbols),
+                    ),
+            ),
+        ),
+    )
+    .await;
+    let response = test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+    let status = response.status();
+    let body = test::read_body(response).await;
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+/// 请求 JSON 接口
+pub(crate) async fn get(uri: &str) -> (StatusCode, Value) {
+    let (status, body) = get_text(uri).await;
+    let body = serde_json::from_str(&body).unwrap_or_else(|e| panic!("{}: {}", e, body));
+    (status, body)
+}
@@ -1,13 +1,10 @@
 use actix_web::{web, HttpRequest, HttpResponse, Result};
 use chrono::Utc;
-use rust_decimal::Decimal;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait,
     QueryFilter, QueryOrder, Set,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::str::FromStr;
 
 use crate::middleware::auth::extract_user_from_token;
 use crate::models::price_alert::{
@@ -17,6 +14,8 @@ use crate::models::watchlist_token::{
     CreateWatchlistTokenRequest, UpdateWatchlistTokenRequest, WatchlistTokenResponse,
 };
 use crate::models::{PriceAlert, WatchlistToken};
+use crate::services::market_data::{Symbol, Ticker};
+use crate::AppState;
 
 // ============ 关注列表管理 ============
 
@@ -24,6 +23,7 @@ use crate::models::{PriceAlert, WatchlistToken};
 pub async fn get_watchlist_tokens(
     req: HttpRequest,
     db: web::Data<DatabaseConnection>,
+    state: web::Data<AppState>,
     query: web::Query<GetWatchlistQuery>,
 ) -> Result<HttpResponse> {
     // 身份验证
@@ -60,10 +60,10 @@ pub async fn get_watchlist_tokens(
         actix_web::error::ErrorInternalServerError("查询失败")
     })?;
 
-    // 获取实时价格数据（模拟数据，实际应从市场数据服务获取）
+    // 批量获取实时行情，获取失败的代币价格字段为空
+    let tickers = get_token_tickers(&state, &tokens).await;
     let mut token_responses = Vec::new();
-    for token in tokens {
-        let price_data = get_mock_price_data(&token.symbol, &token.exchange).await;
+    for (token, ticker) in tokens.into_iter().zip(tickers) {
         token_responses.push(WatchlistTokenResponse {
             id: token.id,
             symbol: token.symbol,
@@ -71,11 +71,11 @@ pub async fn get_watchlist_tokens(
             display_name: token.display_name,
             is_active: token.is_active,
             sort_order: token.sort_order,
-            current_price: price_data.get("price").copied(),
-            price_change_24h: price_data.get("change_24h").copied(),
-            price_change_percentage_24h: price_data.get("change_percent_24h").copied(),
-            volume_24h: price_data.get("volume_24h").copied(),
-            market_cap: price_data.get("market_cap").copied(),
+            current_price: ticker.as_ref().map(|t| t.price),
+            price_change_24h: ticker.as_ref().and_then(|t| t.price_change_24h),
+            price_change_percentage_24h: ticker.as_ref().and_then(|t| t.price_change_percent_24h),
+            volume_24h: ticker.as_ref().and_then(|t| t.volume_24h),
+            market_cap: ticker.as_ref().and_then(|t| t.market_cap),
             created_at: token.created_at,
             updated_at: token.updated_at,
         });
@@ -334,10 +334,8 @@ pub async fn get_price_alerts(
             symbol: alert.symbol,
             exchange: alert.exchange,
             alert_type: alert.alert_type,
-            target_value: alert.target_value.to_string().parse().unwrap_or(0.0),
-            comparison_value: alert
-                .comparison_value
-                .map(|v| v.to_string().parse().unwrap_or(0.0)),
+            target_value: alert.target_value,
+            comparison_value: alert.comparison_value,
             condition: alert
                 .condition
                 .and_then(|v| serde_json::from_value(v.into()).ok()),
@@ -383,11 +381,8 @@ pub async fn create_price_alert(
         symbol: Set(req_data.symbol.to_uppercase()),
         exchange: Set(req_data.exchange.to_lowercase()),
         alert_type: Set(req_data.alert_type),
-        target_value: Set(Decimal::from_str(&req_data.target_value.to_string())
-            .unwrap_or_default()),
-        comparison_value: Set(req_data.comparison_value.map(|v| {
-            Decimal::from_str(&v.to_string()).unwrap_or_default()
-        })),
+        target_value: Set(req_data.target_value),
+        comparison_value: Set(req_data.comparison_value),
         condition: Set(req_data.condition.map(serde_json::to_value).transpose().unwrap_or_default().map(|v| sea_orm::JsonValue::from(v))),
         is_active: Set(true),
         is_triggered: Set(false),
@@ -413,10 +408,8 @@ pub async fn create_price_alert(
             symbol: alert.symbol,
             exchange: alert.exchange,
             alert_type: alert.alert_type,
-            target_value: alert.target_value.to_string().parse().unwrap_or(0.0),
-            comparison_value: alert
-                .comparison_value
-                .map(|v| v.to_string().parse().unwrap_or(0.0)),
+            target_value: alert.target_value,
+            comparison_value: alert.comparison_value,
             condition: alert
                 .condition
                 .and_then(|v| serde_json::from_value(v.into()).ok()),
@@ -466,13 +459,10 @@ pub async fn update_price_alert(
     let mut alert_active: crate::models::price_alert::ActiveModel = alert.into();
 
     if let Some(target_value) = req_data.target_value {
-        alert_active.target_value = Set(Decimal::from_str(&target_value.to_string())
-            .unwrap_or_default());
+        alert_active.target_value = Set(target_value);
     }
     if let Some(comparison_value) = req_data.comparison_value {
-        alert_active.comparison_value = Set(Some(
-            Decimal::from_str(&comparison_value.to_string()).unwrap_or_default(),
-        ));
+        alert_active.comparison_value = Set(Some(comparison_value));
     }
     if let Some(condition) = req_data.condition {
         alert_active.condition = Set(Some(serde_json::to_value(condition)
@@ -501,10 +491,8 @@ pub async fn update_price_alert(
             symbol: updated_alert.symbol,
             exchange: updated_alert.exchange,
             alert_type: updated_alert.alert_type,
-            target_value: updated_alert.target_value.to_string().parse().unwrap_or(0.0),
-            comparison_value: updated_alert
-                .comparison_value
-                .map(|v| v.to_string().parse().unwrap_or(0.0)),
+            target_value: updated_alert.target_value,
+            comparison_value: updated_alert.comparison_value,
             condition: updated_alert
                 .condition
                 .and_then(|v| serde_json::from_value(v.into()).ok()),
@@ -555,19 +543,42 @@ pub async fn delete_price_alert(
 
 // ============ 辅助函数 ============
 
-/// 获取模拟价格数据（实际应从市场数据服务获取）
-async fn get_mock_price_data(
-    _symbol: &str,
-    _exchange: &str,
-) -> HashMap<&'static str, f64> {
-    // TODO: 从实际的市场数据服务获取价格
-    let mut data = HashMap::new();
-    data.insert("price", 50000.0);
-    data.insert("change_24h", 1250.0);
-    data.insert("change_percent_24h", 2.56);
-    data.insert("volume_24h", 28500000.0);
-    data.insert("market_cap", 950000000000.0);
-    data
+/// 批量获取关注代币的24小时行情
+///
+/// 代币的交易所是已注册的数据源时优先使用该交易所的行情，否则按数据源优先级回退。
+async fn get_token_tickers(
+    state: &AppState,
+    tokens: &[crate::models::watchlist_token::Model],
+) -> Vec<Option<Ticker>> {
+    let requests: Vec<Option<(Symbol, Option<String>)>> = tokens
+        .iter()
+        .map(|token| {
+            let symbol = state.instruments.resolve_symbol(&token.symbol).ok()?;
+            let source = state
+                .market_sources
+                .get(&token.exchange)
+                .ok()
+                .map(|_| token.exchange.clone());
+            Some((symbol, source))
+        })
+        .collect();
+
+    let valid: Vec<(Symbol, Option<String>)> = requests.iter().flatten().cloned().collect();
+    let mut results = state.tickers.get_many(&valid).await.into_iter();
+
+    requests
+        .into_iter()
+        .map(|request| {
+            let (symbol, _) = request?;
+            match results.next()? {
+                Ok(ticker) => Some(ticker),
+                Err(e) => {
+                    eprintln!("获取 {} 行情失败: {}", symbol, e);
+                    None
+                }
+            }
+        })
+        .collect()
 }
 
 // ============ 请求/响应结构 ============
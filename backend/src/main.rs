@@ -12,12 +12,24 @@ use sea_orm_migration::prelude::*;
 use std::{env, sync::Arc};
 
 use handlers::*;
-use middleware::JwtAuth;
-use services::AuthService;
+use middleware::{DecimalOutput, JwtAuth};
+use services::{
+    AuthService, CandleImporter, CandleStore, Clock, FundingStore, HttpMode, InstrumentRegistry,
+    MarketDataRegistry, MarketOverviewService, MarketStream, TickerService,
+};
 
 pub struct AppState {
     pub db: DatabaseConnection,
     pub auth_service: Arc<AuthService>,
+    pub market_sources: Arc<MarketDataRegistry>,
+    pub candle_store: Arc<CandleStore>,
+    pub candle_imports: Arc<CandleImporter>,
+    pub funding_store: Arc<FundingStore>,
+    pub instruments: Arc<InstrumentRegistry>,
+    pub tickers: Arc<TickerService>,
+    pub market_stream: Arc<MarketStream>,
+    pub market_overview: Arc<MarketOverviewService>,
+    pub clock: Clock,
 }
 
 async fn create_database_connection() -> Result<DatabaseConnection, Box<dyn std::error::Error>> {
@@ -41,6 +53,15 @@ async fn main() -> std::io::Result<()> {
     // 初始化日志
     env_logger::init();
 
+    // 命令行导入K线数据包: `backend import-klines <文件或目录>...`
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("import-klines") {
+        let db = create_database_connection()
+            .await
+            .expect("无法连接到数据库");
+        return services::candle_import::run_cli(db, &args[2..]).await;
+    }
+
     log::info!("正在启动 QuantConsole 后端服务...");
 
     // 创建数据库连接
@@ -56,6 +77,44 @@ async fn main() -> std::io::Result<()> {
 
     let auth_service = Arc::new(AuthService::new(db.clone(), jwt_secret));
 
+    let http_mode = HttpMode::from_env();
+    let clock = Clock::from_env();
+    let market_sources = Arc::new(MarketDataRegistry::with_default_sources(
+        http_mode.clone(),
+    ));
+    let instruments = Arc::new(InstrumentRegistry::from_env());
+    instruments.clone().spawn_refresh(market_sources.clone());
+
+    let tickers = Arc::new(TickerService::from_env(market_sources.clone()));
+
+    let market_stream = Arc::new(MarketStream::from_env(&http_mode));
+    market_stream.spawn();
+
+    let market_overview = Arc::new(MarketOverviewService::from_env(
+        db.clone(),
+        market_sources.clone(),
+    ));
+    market_overview.clone().spawn_refresh();
+
+    let candle_store = Arc::new(CandleStore::new(db.clone(), clock));
+    let candle_imports = Arc::new(CandleImporter::from_env(db.clone(), candle_store.clone()));
+
+    let app_state = web::Data::new(AppState {
+        db: db.clone(),
+        auth_service: auth_service.clone(),
+        market_sources,
+        candle_store,
+        candle_imports,
+        funding_store: Arc::new(FundingStore::new(db.clone())),
+        instruments,
+        tickers,
+        market_stream,
+        market_overview,
+        clock,
+    });
+
+    let market_feeds = web::Data::new(market_ws::MarketFeeds::from_env());
+
     // 获取服务器配置
     let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
     let port = env::var("PORT")
@@ -79,10 +138,18 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(db.clone()))
             .app_data(web::Data::new(auth_service.clone()))
+            .app_data(app_state.clone())
+            .app_data(market_feeds.clone())
             .wrap(cors)
-            .wrap(Logger::default())
+            // 与默认格式相同，但只记录路径不记录查询串（行情 WebSocket 通过 `?token=` 传递令牌）
+            .wrap(Logger::new(
+                r#"%a "%m %U" %s %b "%{Referer}i" "%{User-Agent}i" %T"#,
+            ))
+            // 实时行情推送 (令牌可选)
+            .route("/ws/market", web::get().to(market_ws::market_ws))
             .service(
                 web::scope("/api")
+                    .wrap(DecimalOutput)
                     // 健康检查
                     .route("/health", web::get().to(health_check))
                     // 市场数据API (无需身份验证，允许前端直接访问)
@@ -90,9 +157,51 @@ async fn main() -> std::io::Result<()> {
                         web::scope("/market")
                             .route("/health", web::get().to(market_data::market_health_check))
                             .route("/kline", web::get().to(market_data::get_kline_data))
+                            .route("/kline/export", web::get().to(export::export_klines))
+                            .route("/ticker", web::get().to(market_data::get_ticker))
+                            .route("/orderbook", web::get().to(market_data::get_order_book))
+                            .route("/trades", web::get().to(market_data::get_trades))
+                            .route("/compare", web::get().to(market_data::get_compare))
+                            .route(
+                                "/overview",
+                                web::get().to(market_overview::get_market_overview),
+                            )
+                            .route(
+                                "/overview/history",
+                                web::get().to(market_overview::get_market_overview_history),
+                            )
+                            .service(
+                                web::scope("/perp")
+                                    .route("/funding", web::get().to(perpetual::get_funding_rate))
+                                    .route(
+                                        "/funding/history",
+                                        web::get().to(perpetual::get_funding_history),
+                                    )
+                                    .route(
+                                        "/open-interest",
+                                        web::get().to(perpetual::get_open_interest),
+                                    )
+                                    .route(
+                                        "/open-interest/history",
+                                        web::get().to(perpetual::get_open_interest_history),
+                                    )
+                                    .route("/price-kline", web::get().to(perpetual::get_price_klines))
+                                    .route("/basis", web::get().to(perpetual::get_basis)),
+                            )
                             .route(
                                 "/symbols",
                                 web::get().to(market_data::get_supported_symbols),
+                            )
+                            // 历史数据导入 (需要身份验证)
+                            .service(
+                                web::scope("/import")
+                                    .wrap(JwtAuth::new(auth_service.clone()))
+                                    .route("", web::post().to(candle_import::start_candle_import))
+                                    .route("", web::get().to(candle_import::list_candle_imports))
+                                    .route(
+                                        "/{job_id}",
+                                        web::get().to(candle_import::get_candle_import),
+                                    ),
                             ),
                     )
                     // 认证路由 (无需身份验证)
@@ -0,0 +1,72 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::ErrorBadRequest,
+    web, Error,
+};
+use futures_util::future::LocalBoxFuture;
+use serde::Deserialize;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use crate::services::market_data::decimal::{self, DecimalFormat};
+
+/// 按查询参数 `decimals=number|string` 设置本次请求中价格、数量字段的输出格式
+///
+/// `decimals=string` 时以字符串返回交易所给出的全部精度，默认仍为 JSON 数字。
+pub struct DecimalOutput;
+
+#[derive(Deserialize)]
+struct DecimalQuery {
+    #[serde(default)]
+    decimals: DecimalFormat,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for DecimalOutput
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = DecimalOutputMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(DecimalOutputMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct DecimalOutputMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for DecimalOutputMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let format = match web::Query::<DecimalQuery>::from_query(req.query_string()) {
+                Ok(query) => query.decimals,
+                Err(_) => return Err(ErrorBadRequest("decimals 参数只能为 number 或 string")),
+            };
+            decimal::scope(format, service.call(req)).await
+        })
+    }
+}
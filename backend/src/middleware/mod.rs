@@ -1,3 +1,5 @@
 pub mod auth;
+pub mod decimal_format;
 
 pub use auth::JwtAuth;
+pub use decimal_format::DecimalOutput;
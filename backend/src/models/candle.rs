@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "candles")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub symbol: String,
+    pub exchange: String,
+    pub interval: String,
+    /// K线开盘时间（毫秒时间戳）
+    pub open_time: i64,
+    #[sea_orm(column_type = "Decimal(Some((36, 18)))")]
+    pub open: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((36, 18)))")]
+    pub high: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((36, 18)))")]
+    pub low: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((36, 18)))")]
+    pub close: Decimal,
+    #[sea_orm(column_type = "Decimal(Some((36, 18)))")]
+    pub volume: Decimal,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,25 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "candle_imports")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub file_name: String,
+    /// 数据文件内容的 SHA-256（十六进制）
+    pub sha256: String,
+    pub symbol: String,
+    pub exchange: String,
+    pub interval: String,
+    pub rows: i32,
+    /// 文件中第一根和最后一根K线的开盘时间（毫秒时间戳）
+    pub first_open_time: i64,
+    pub last_open_time: i64,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "funding_rates")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub symbol: String,
+    pub exchange: String,
+    /// 结算时间（毫秒时间戳）
+    pub funding_time: i64,
+    #[sea_orm(column_type = "Decimal(Some((20, 10)))")]
+    pub funding_rate: Decimal,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
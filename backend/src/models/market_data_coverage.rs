@@ -0,0 +1,24 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "market_data_coverage")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// 数据类型，如 `candles`、`funding_rates`
+    pub dataset: String,
+    pub symbol: String,
+    pub exchange: String,
+    /// K线周期，不分周期的数据为空字符串
+    pub interval: String,
+    /// 已同步区间的起止时间（毫秒时间戳，闭区间）
+    pub start_time: i64,
+    pub end_time: i64,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "market_overview_daily")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    /// UTC 日期
+    #[sea_orm(unique)]
+    pub date: Date,
+    /// 总市值（美元）
+    #[sea_orm(column_type = "Decimal(Some((30, 2)))")]
+    pub total_market_cap: Decimal,
+    /// 24小时总成交额（美元）
+    #[sea_orm(column_type = "Decimal(Some((30, 2)))")]
+    pub total_volume24h: Decimal,
+    /// BTC 市值占比（百分比）
+    #[sea_orm(column_type = "Decimal(Some((10, 4)))")]
+    pub btc_dominance: Decimal,
+    /// ETH 市值占比（百分比）
+    #[sea_orm(column_type = "Decimal(Some((10, 4)))")]
+    pub eth_dominance: Decimal,
+    /// 恐惧贪婪指数（0-100）
+    pub fear_greed_index: Option<i32>,
+    pub fear_greed_label: Option<String>,
+    pub source: String,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -4,6 +4,11 @@ pub mod security_event;
 pub mod watchlist_token;
 pub mod price_alert;
 pub mod price_history;
+pub mod candle;
+pub mod funding_rate;
+pub mod candle_import;
+pub mod market_overview_daily;
+pub mod market_data_coverage;
 
 pub use user::Entity as User;
 pub use user_session::Entity as UserSession;
@@ -11,3 +16,8 @@ pub use security_event::Entity as SecurityEvent;
 pub use watchlist_token::Entity as WatchlistToken;
 pub use price_alert::Entity as PriceAlert;
 pub use price_history::Entity as PriceHistory;
+pub use candle::Entity as Candle;
+pub use funding_rate::Entity as FundingRate;
+pub use candle_import::Entity as CandleImport;
+pub use market_overview_daily::Entity as MarketOverviewDaily;
+pub use market_data_coverage::Entity as MarketDataCoverage;
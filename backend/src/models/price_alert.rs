@@ -62,16 +62,20 @@ pub struct CreatePriceAlertRequest {
     pub symbol: String,
     pub exchange: String,
     pub alert_type: PriceAlertType,
-    pub target_value: f64,
-    pub comparison_value: Option<f64>,
+    #[serde(with = "crate::services::market_data::decimal")]
+    pub target_value: Decimal,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub comparison_value: Option<Decimal>,
     pub condition: Option<serde_json::Value>,
     pub notification_channels: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdatePriceAlertRequest {
-    pub target_value: Option<f64>,
-    pub comparison_value: Option<f64>,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub target_value: Option<Decimal>,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub comparison_value: Option<Decimal>,
     pub condition: Option<serde_json::Value>,
     pub is_active: Option<bool>,
     pub notification_channels: Option<Vec<String>>,
@@ -83,8 +87,10 @@ pub struct PriceAlertResponse {
     pub symbol: String,
     pub exchange: String,
     pub alert_type: PriceAlertType,
-    pub target_value: f64,
-    pub comparison_value: Option<f64>,
+    #[serde(with = "crate::services::market_data::decimal")]
+    pub target_value: Decimal,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub comparison_value: Option<Decimal>,
     pub condition: Option<serde_json::Value>,
     pub is_active: bool,
     pub is_triggered: bool,
@@ -57,11 +57,16 @@ pub struct WatchlistTokenResponse {
     pub display_name: Option<String>,
     pub is_active: bool,
     pub sort_order: i32,
-    pub current_price: Option<f64>,
-    pub price_change_24h: Option<f64>,
-    pub price_change_percentage_24h: Option<f64>,
-    pub volume_24h: Option<f64>,
-    pub market_cap: Option<f64>,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub current_price: Option<Decimal>,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub price_change_24h: Option<Decimal>,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub price_change_percentage_24h: Option<Decimal>,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub volume_24h: Option<Decimal>,
+    #[serde(with = "crate::services::market_data::decimal::option", default)]
+    pub market_cap: Option<Decimal>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -0,0 +1,720 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::models::{candle_import, CandleImport};
+use crate::services::market_data::decimal;
+use crate::services::market_data::interval::Interval;
+use crate::services::market_data::{Clock, KlineData, Symbol};
+use crate::services::CandleStore;
+
+/// 每批写入的K线数量，也是进度更新的粒度
+const WRITE_BATCH_SIZE: usize = 5000;
+/// 校验失败时最多报告的行数
+const MAX_REPORTED_ERRORS: usize = 10;
+/// 内存中保留的导入任务数量
+const MAX_JOBS: usize = 50;
+/// 大于该值的时间戳按微秒处理（Binance 2025 年起的现货数据包使用微秒）
+const MICROS_THRESHOLD: i64 = 100_000_000_000_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("读取文件失败: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("解压失败: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("数据库错误: {0}")]
+    Db(#[from] DbErr),
+
+    #[error("无法从文件名 {0} 识别交易对和周期，请指定 symbol 和 interval")]
+    UnknownLayout(String),
+
+    #[error("路径 {0} 不在导入目录内")]
+    OutsideImportDir(String),
+
+    #[error("数据校验失败: {0}")]
+    Invalid(String),
+}
+
+/// 导入参数，未指定的交易对和周期从文件名识别（如 `BTCUSDT-1m-2024-01.zip`）
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub symbol: Option<Symbol>,
+    pub interval: Option<Interval>,
+    /// 写入K线存储时使用的交易所名称
+    pub exchange: String,
+}
+
+/// 单个文件的导入结果
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReport {
+    pub file: String,
+    pub symbol: String,
+    pub interval: String,
+    /// 去重后的K线数量
+    pub rows: usize,
+    /// 文件内重复的行
+    pub duplicates: usize,
+    pub inserted: usize,
+    /// 与已存储数据不一致而被覆盖的K线
+    pub updated: usize,
+    pub unchanged: usize,
+    /// 相同内容的文件已导入过，未做任何写入
+    pub skipped: bool,
+    pub first_open_time: Option<i64>,
+    pub last_open_time: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Running,
+    Completed,
+    /// 部分文件导入失败，见 `errors`
+    Failed,
+}
+
+/// 导入任务进度
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    pub job_id: String,
+    pub status: ImportStatus,
+    pub files_total: usize,
+    pub files_done: usize,
+    pub current_file: Option<String>,
+    /// 当前文件已写入的K线数量
+    pub current_rows_written: usize,
+    pub current_rows_total: usize,
+    pub files: Vec<FileReport>,
+    pub errors: Vec<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+/// 去重并校验后的数据文件内容
+#[derive(Debug)]
+pub struct ParsedDump {
+    pub klines: Vec<KlineData>,
+    pub duplicates: usize,
+}
+
+/// 从交易所数据包（如 Binance 月度K线 CSV/ZIP）批量导入K线存储
+///
+/// 文件按内容哈希记录在 `candle_imports` 表中，相同文件重复导入直接跳过；
+/// 与已存储数据完全一致的K线也不会重写，因此导入是幂等的。
+pub struct CandleImporter {
+    db: DatabaseConnection,
+    store: Arc<CandleStore>,
+    import_dir: PathBuf,
+    jobs: Mutex<VecDeque<Arc<Mutex<ImportProgress>>>>,
+}
+
+impl CandleImporter {
+    pub fn new(db: DatabaseConnection, store: Arc<CandleStore>, import_dir: PathBuf) -> Self {
+        Self {
+            db,
+            store,
+            import_dir,
+            jobs: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// API 只能导入 `CANDLE_IMPORT_DIR`（默认 `./data/imports`）内的文件
+    pub fn from_env(db: DatabaseConnection, store: Arc<CandleStore>) -> Self {
+        let import_dir = std::env::var("CANDLE_IMPORT_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from("./data/imports"));
+        Self::new(db, store, import_dir)
+    }
+
+    /// 解析导入目录内的相对路径，目录会展开为其中的数据文件
+    pub fn resolve(&self, relative: &str) -> Result<Vec<PathBuf>, ImportError> {
+        let root = self.import_dir.canonicalize()?;
+        let path = root.join(relative).canonicalize()?;
+        if !path.starts_with(&root) {
+            return Err(ImportError::OutsideImportDir(relative.to_string()));
+        }
+        expand_paths(&[path])
+    }
+
+    /// 在后台导入文件，返回任务ID，进度通过 [`Self::job`] 查询
+    pub fn start(self: &Arc<Self>, paths: Vec<PathBuf>, options: ImportOptions) -> ImportProgress {
+        let progress = Arc::new(Mutex::new(ImportProgress {
+            job_id: uuid::Uuid::new_v4().to_string(),
+            status: ImportStatus::Running,
+            files_total: paths.len(),
+            files_done: 0,
+            current_file: None,
+            current_rows_written: 0,
+            current_rows_total: 0,
+            files: Vec::new(),
+            errors: Vec::new(),
+            started_at: Utc::now().timestamp_millis(),
+            finished_at: None,
+        }));
+        {
+            let mut jobs = self.jobs.lock().unwrap();
+            if jobs.len() >= MAX_JOBS {
+                jobs.pop_front();
+            }
+            jobs.push_back(progress.clone());
+        }
+
+        let snapshot = progress.lock().unwrap().clone();
+        let importer = self.clone();
+        tokio::spawn(async move { importer.run(paths, &options, &progress).await });
+        snapshot
+    }
+
+    pub fn job(&self, job_id: &str) -> Option<ImportProgress> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|job| job.lock().unwrap())
+            .find(|job| job.job_id == job_id)
+            .map(|job| job.clone())
+    }
+
+    /// 最近的导入任务，最新的在前
+    pub fn jobs(&self) -> Vec<ImportProgress> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .map(|job| job.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// 依次导入文件，单个文件失败不影响其余文件
+    pub async fn run(
+        &self,
+        paths: Vec<PathBuf>,
+        options: &ImportOptions,
+        progress: &Mutex<ImportProgress>,
+    ) -> ImportProgress {
+        for path in paths {
+            let file = path.display().to_string();
+            {
+                let mut progress = progress.lock().unwrap();
+                progress.current_file = Some(file.clone());
+                progress.current_rows_written = 0;
+                progress.current_rows_total = 0;
+            }
+
+            let result = self.import_file(&path, options, progress).await;
+            let mut progress = progress.lock().unwrap();
+            progress.files_done += 1;
+            match result {
+                Ok(report) => {
+                    println!(
+                        "📥 [{}/{}] {}: {} 根K线，新增 {}，更新 {}，未变 {}{}",
+                        progress.files_done,
+                        progress.files_total,
+                        file,
+                        report.rows,
+                        report.inserted,
+                        report.updated,
+                        report.unchanged,
+                        if report.skipped {
+                            "（已导入过，跳过）"
+                        } else {
+                            ""
+                        }
+                    );
+                    progress.files.push(report);
+                }
+                Err(e) => {
+                    println!(
+                        "❌ [{}/{}] {} 导入失败: {}",
+                        progress.files_done, progress.files_total, file, e
+                    );
+                    progress.errors.push(format!("{}: {}", file, e));
+                }
+            }
+        }
+
+        let mut progress = progress.lock().unwrap();
+        progress.current_file = None;
+        progress.status = if progress.errors.is_empty() {
+            ImportStatus::Completed
+        } else {
+            ImportStatus::Failed
+        };
+        progress.finished_at = Some(Utc::now().timestamp_millis());
+        progress.clone()
+    }
+
+    async fn import_file(
+        &self,
+        path: &Path,
+        options: &ImportOptions,
+        progress: &Mutex<ImportProgress>,
+    ) -> Result<FileReport, ImportError> {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (symbol, interval) = match (&options.symbol, options.interval) {
+            (Some(symbol), Some(interval)) => (symbol.clone(), interval),
+            (symbol, interval) => {
+                let (inferred_symbol, inferred_interval) = infer_layout(&file_name)
+                    .ok_or_else(|| ImportError::UnknownLayout(file_name.clone()))?;
+                (
+                    symbol.clone().unwrap_or(inferred_symbol),
+                    interval.unwrap_or(inferred_interval),
+                )
+            }
+        };
+        let symbol_key = symbol.to_string();
+        let interval_key = interval.to_string();
+        let exchange = options.exchange.clone();
+
+        let owned_path = path.to_path_buf();
+        let (sha256, text) = tokio::task::spawn_blocking(move || read_dump(&owned_path))
+            .await
+            .map_err(|e| ImportError::Io(std::io::Error::other(e.to_string())))??;
+
+        let mut report = FileReport {
+            file: file_name.clone(),
+            symbol: symbol_key.clone(),
+            interval: interval_key.clone(),
+            rows: 0,
+            duplicates: 0,
+            inserted: 0,
+            updated: 0,
+            unchanged: 0,
+            skipped: false,
+            first_open_time: None,
+            last_open_time: None,
+        };
+
+        let imported = CandleImport::find()
+            .filter(candle_import::Column::Exchange.eq(&exchange))
+            .filter(candle_import::Column::Symbol.eq(&symbol_key))
+            .filter(candle_import::Column::Interval.eq(&interval_key))
+            .filter(candle_import::Column::Sha256.eq(&sha256))
+            .one(&self.db)
+            .await?;
+        if let Some(imported) = imported {
+            report.rows = imported.rows.max(0) as usize;
+            report.unchanged = report.rows;
+            report.skipped = true;
+            report.first_open_time = Some(imported.first_open_time);
+            report.last_open_time = Some(imported.last_open_time);
+            return Ok(report);
+        }
+
+        let source = exchange.clone();
+        let parsed = tokio::task::spawn_blocking(move || parse_kline_csv(&text, &source, interval))
+            .await
+            .map_err(|e| ImportError::Io(std::io::Error::other(e.to_string())))??;
+        let (Some(first), Some(last)) = (parsed.klines.first(), parsed.klines.last()) else {
+            return Err(ImportError::Invalid("文件中没有K线".to_string()));
+        };
+        let (first_open_time, last_open_time) = (first.timestamp, last.timestamp);
+        report.rows = parsed.klines.len();
+        report.duplicates = parsed.duplicates;
+        report.first_open_time = Some(first_open_time);
+        report.last_open_time = Some(last_open_time);
+
+        // 只写入新增或与已存储数据不一致的K线
+        let stored: HashMap<i64, KlineData> = self
+            .store
+            .load(
+                &symbol_key,
+                &exchange,
+                &interval_key,
+                first_open_time,
+                last_open_time,
+            )
+            .await?
+            .into_iter()
+            .map(|k| (k.timestamp, k))
+            .collect();
+        let mut pending = Vec::new();
+        for kline in parsed.klines {
+            match stored.get(&kline.timestamp) {
+                None => {
+                    report.inserted += 1;
+                    pending.push(kline);
+                }
+                Some(existing) if same_candle(existing, &kline) => report.unchanged += 1,
+                Some(_) => {
+                    report.updated += 1;
+                    pending.push(kline);
+                }
+            }
+        }
+
+        progress.lock().unwrap().current_rows_total = pending.len();
+        for batch in pending.chunks(WRITE_BATCH_SIZE) {
+            self.store
+                .save(&symbol_key, &exchange, &interval_key, batch)
+                .await?;
+            progress.lock().unwrap().current_rows_written += batch.len();
+        }
+
+        CandleImport::insert(candle_import::ActiveModel {
+            file_name: Set(file_name.chars().take(255).collect()),
+            sha256: Set(sha256),
+            symbol: Set(symbol_key),
+            exchange: Set(exchange),
+            interval: Set(interval_key),
+            rows: Set(i32::try_from(report.rows).unwrap_or(i32::MAX)),
+            first_open_time: Set(first_open_time),
+            last_open_time: Set(last_open_time),
+            created_at: Set(Utc::now().into()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([
+                candle_import::Column::Exchange,
+                candle_import::Column::Symbol,
+                candle_import::Column::Interval,
+                candle_import::Column::Sha256,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(&self.db)
+        .await?;
+
+        Ok(report)
+    }
+}
+
+/// 命令行导入：`import-klines <文件或目录>... [--symbol BTC/USDT] [--interval 1m] [--exchange binance]`
+pub async fn run_cli(db: DatabaseConnection, args: &[String]) -> std::io::Result<()> {
+    let usage = || {
+        std::io::Error::other(
+            "用法: import-klines <文件或目录>... [--symbol BTC/USDT] [--interval 1m] [--exchange binance]",
+        )
+    };
+
+    let mut paths = Vec::new();
+    let mut options = ImportOptions {
+        symbol: None,
+        interval: None,
+        exchange: "binance".to_string(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--symbol" => {
+                let value = args.next().ok_or_else(usage)?;
+                options.symbol = Some(Symbol::parse(value).map_err(std::io::Error::other)?);
+            }
+            "--interval" => {
+                let value = args.next().ok_or_else(usage)?;
+                options.interval =
+                    Some(Interval::parse(value).ok_or_else(|| {
+                        std::io::Error::other(format!("不支持的周期: {}", value))
+                    })?);
+            }
+            "--exchange" => options.exchange = args.next().ok_or_else(usage)?.to_lowercase(),
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+    if paths.is_empty() {
+        return Err(usage());
+    }
+    let paths = expand_paths(&paths).map_err(std::io::Error::other)?;
+
+    let store = Arc::new(CandleStore::new(db.clone(), Clock::from_env()));
+    let importer = CandleImporter::new(db, store, PathBuf::new());
+    let progress = Mutex::new(ImportProgress {
+        job_id: "cli".to_string(),
+        status: ImportStatus::Running,
+        files_total: paths.len(),
+        files_done: 0,
+        current_file: None,
+        current_rows_written: 0,
+        current_rows_total: 0,
+        files: Vec::new(),
+        errors: Vec::new(),
+        started_at: Utc::now().timestamp_millis(),
+        finished_at: None,
+    });
+    let result = importer.run(paths, &options, &progress).await;
+
+    let total = |f: fn(&FileReport) -> usize| result.files.iter().map(f).sum::<usize>();
+    println!(
+        "✅ 导入完成: {} 个文件，新增 {}，更新 {}，未变 {}，失败 {}",
+        result.files.len(),
+        total(|r| r.inserted),
+        total(|r| r.updated),
+        total(|r| r.unchanged),
+        result.errors.len()
+    );
+    match result.status {
+        ImportStatus::Failed => Err(std::io::Error::other(result.errors.join("\n"))),
+        _ => Ok(()),
+    }
+}
+
+/// 目录展开为其中的 `.csv`/`.zip` 文件（按文件名排序），文件原样保留
+fn expand_paths(paths: &[PathBuf]) -> Result<Vec<PathBuf>, ImportError> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.is_file()
+                    && p.extension().is_some_and(|ext| {
+                        ext.eq_ignore_ascii_case("csv") || ext.eq_ignore_ascii_case("zip")
+                    })
+            })
+            .collect();
+        entries.sort();
+        files.extend(entries);
+    }
+    Ok(files)
+}
+
+/// 从 Binance 数据包文件名识别交易对和周期，如 `BTCUSDT-1m-2024-01.zip`
+pub fn infer_layout(file_name: &str) -> Option<(Symbol, Interval)> {
+    let mut parts = file_name.split('-');
+    let symbol = Symbol::parse(parts.next()?).ok()?;
+    let interval = match parts.next()? {
+        // Binance 用 1mo 表示月线
+        "1mo" => "1M",
+        other => other,
+    };
+    Some((symbol, Interval::parse(interval)?))
+}
+
+/// 读取数据文件，返回内容的 SHA-256 和 CSV 文本（ZIP 中的全部 CSV 依次拼接）
+fn read_dump(path: &Path) -> Result<(String, String), ImportError> {
+    let bytes = std::fs::read(path)?;
+    let sha256 = format!("{:x}", Sha256::digest(&bytes));
+
+    let is_zip = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+    if !is_zip {
+        let text = String::from_utf8(bytes)
+            .map_err(|_| ImportError::Invalid("文件不是 UTF-8 文本".to_string()))?;
+        return Ok((sha256, text));
+    }
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut text = String::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if entry.is_file() && entry.name().to_ascii_lowercase().ends_with(".csv") {
+            entry.read_to_string(&mut text)?;
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+    }
+    Ok((sha256, text))
+}
+
+/// 解析 Binance K线 CSV：`open_time,open,high,low,close,volume,close_time,...`
+///
+/// 可选的表头行会被跳过；按开盘时间去重，内容冲突的重复行、价格关系不合法、
+/// 未对齐到周期的行都视为校验失败，整个文件不会写入。
+pub fn parse_kline_csv(
+    text: &str,
+    exchange: &str,
+    interval: Interval,
+) -> Result<ParsedDump, ImportError> {
+    let mut klines: BTreeMap<i64, KlineData> = BTreeMap::new();
+    let mut duplicates = 0;
+    let mut errors = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        // 表头行
+        if index == 0 && fields[0].parse::<i64>().is_err() {
+            continue;
+        }
+
+        match parse_row(&fields, exchange, interval) {
+            Ok(kline) => match klines.get(&kline.timestamp) {
+                Some(existing) if same_candle(existing, &kline) => duplicates += 1,
+                Some(_) => errors.push(format!("第 {} 行与开盘时间相同的K线内容冲突", index + 1)),
+                None => {
+                    klines.insert(kline.timestamp, kline);
+                }
+            },
+            Err(message) => errors.push(format!("第 {} 行{}", index + 1, message)),
+        }
+        if errors.len() >= MAX_REPORTED_ERRORS {
+            break;
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(ImportError::Invalid(errors.join("; ")));
+    }
+    Ok(ParsedDump {
+        klines: klines.into_values().collect(),
+        duplicates,
+    })
+}
+
+fn parse_row(fields: &[&str], exchange: &str, interval: Interval) -> Result<KlineData, String> {
+    if fields.len() < 6 {
+        return Err(format!("字段数不足: {}", fields.len()));
+    }
+    let number = |index: usize, name: &str| {
+        decimal::parse(fields[index]).ok_or_else(|| format!("{} 无效: {}", name, fields[index]))
+    };
+    let timestamp = |index: usize, name: &str| {
+        fields[index]
+            .parse::<i64>()
+            .map(|ts| {
+                if ts >= MICROS_THRESHOLD {
+                    ts / 1000
+                } else {
+                    ts
+                }
+            })
+            .map_err(|_| format!("{} 无效: {}", name, fields[index]))
+    };
+
+    let kline = KlineData {
+        timestamp: timestamp(0, "开盘时间")?,
+        open: number(1, "开盘价")?,
+        high: number(2, "最高价")?,
+        low: number(3, "最低价")?,
+        close: number(4, "收盘价")?,
+        volume: number(5, "成交量")?,
+        source: exchange.to_string(),
+    };
+
+    if kline.low <= Decimal::ZERO
+        || kline.low > kline.open.min(kline.close)
+        || kline.high < kline.open.max(kline.close)
+    {
+        return Err("价格关系不合法（要求 0 < low ≤ open/close ≤ high）".to_string());
+    }
+    if kline.volume < Decimal::ZERO {
+        return Err("成交量为负".to_string());
+    }
+    if interval.bucket_start(kline.timestamp) != kline.timestamp {
+        return Err(format!(
+            "开盘时间 {} 未对齐到周期 {}",
+            kline.timestamp, interval
+        ));
+    }
+    if fields.len() > 6 {
+        let close_time = timestamp(6, "收盘时间")?;
+        if close_time != interval.next_bucket(kline.timestamp) - 1 {
+            return Err(format!("收盘时间 {} 与周期 {} 不符", close_time, interval));
+        }
+    }
+    Ok(kline)
+}
+
+/// 按数值比较，忽略小数位数差异（`1.10` 与 `1.1` 相同）
+fn same_candle(a: &KlineData, b: &KlineData) -> bool {
+    a.open == b.open
+        && a.high == b.high
+        && a.low == b.low
+        && a.close == b.close
+        && a.volume == b.volume
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: i64 = 60_000;
+
+    fn row(open_time: i64, close: &str) -> String {
+        format!(
+            "{},100.0,101.5,99.5,{},12.5,{},1262.5,42,6.0,603.0,0",
+            open_time,
+            close,
+            open_time + MINUTE - 1
+        )
+    }
+
+    fn one_minute() -> Interval {
+        Interval::parse("1m").unwrap()
+    }
+
+    #[test]
+    fn infers_symbol_and_interval_from_binance_file_name() {
+        let (symbol, interval) = infer_layout("BTCUSDT-1m-2024-01.zip").unwrap();
+        assert_eq!(symbol.to_string(), "BTC/USDT");
+        assert_eq!(interval.to_string(), "1m");
+
+        let (_, monthly) = infer_layout("ETHUSDT-1mo-2024-01.csv").unwrap();
+        assert_eq!(monthly.to_string(), "1M");
+        let (wrapped, _) = infer_layout("WBTC-1d-2024-01.csv").unwrap();
+        assert_eq!(wrapped.to_string(), "WBTC/USDT");
+        assert!(infer_layout("klines.csv").is_none());
+    }
+
+    #[test]
+    fn parses_with_header_dedupes_and_sorts() {
+        let text = [
+            "open_time,open,high,low,close,volume,close_time,quote_volume,count,taker_buy_volume,taker_buy_quote_volume,ignore".to_string(),
+            row(1_704_067_260_000, "100.5"),
+            row(1_704_067_200_000, "101.0"),
+            row(1_704_067_260_000, "100.5"),
+        ]
+        .join("\n");
+
+        let parsed = parse_kline_csv(&text, "binance", one_minute()).unwrap();
+
+        assert_eq!(parsed.duplicates, 1);
+        let times: Vec<i64> = parsed.klines.iter().map(|k| k.timestamp).collect();
+        assert_eq!(times, vec![1_704_067_200_000, 1_704_067_260_000]);
+        assert_eq!(parsed.klines[0].close, Decimal::from(101));
+        assert_eq!(parsed.klines[0].source, "binance");
+    }
+
+    #[test]
+    fn normalizes_microsecond_timestamps() {
+        let text = format!(
+            "1735689600000000,1.0,2.0,0.5,1.5,3.0,{}",
+            1_735_689_600_000_000_i64 + MINUTE * 1000 - 1
+        );
+
+        let parsed = parse_kline_csv(&text, "binance", one_minute()).unwrap();
+
+        assert_eq!(parsed.klines[0].timestamp, 1_735_689_600_000);
+    }
+
+    #[test]
+    fn rejects_conflicting_duplicates_and_invalid_rows() {
+        let conflicting = [
+            row(1_704_067_200_000, "100.5"),
+            row(1_704_067_200_000, "100.7"),
+        ]
+        .join("\n");
+        let error = parse_kline_csv(&conflicting, "binance", one_minute()).unwrap_err();
+        assert!(error.to_string().contains("第 2 行"), "{}", error);
+
+        let misaligned = row(1_704_067_230_000, "100.5");
+        assert!(parse_kline_csv(&misaligned, "binance", one_minute()).is_err());
+
+        let high_below_close = row(1_704_067_200_000, "102.0");
+        assert!(parse_kline_csv(&high_below_close, "binance", one_minute()).is_err());
+    }
+}
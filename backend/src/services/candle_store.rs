@@ -0,0 +1,429 @@
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use crate::models::{candle, Candle};
+use crate::services::coverage_store::{self, subtract_ranges, CoverageSeries, CoverageStore};
+use crate::services::market_data::interval::Interval;
+use crate::services::market_data::pagination::{fetch_range, RangePager};
+use crate::services::market_data::quality::{self, CheckedKlines, QualityIssue};
+use crate::services::market_data::resample::{choose_base_interval, resample};
+use crate::services::market_data::{
+    Clock, KlineData, KlineQuery, MarketDataError, MarketDataSource,
+};
+
+/// 单条 INSERT 语句写入的最大K线数量
+const SAVE_BATCH_SIZE: usize = 500;
+
+/// 本地K线存储：优先读库，只向数据源请求缺失的部分并回写
+///
+/// 请求过的区间记录在同步区间表中，数据源没有K线的区间（上线前、停机）不会重复请求。
+pub struct CandleStore {
+    db: DatabaseConnection,
+    coverage: CoverageStore,
+    clock: Clock,
+}
+
+impl CandleStore {
+    pub fn new(db: DatabaseConnection, clock: Clock) -> Self {
+        Self {
+            coverage: CoverageStore::new(db.clone()),
+            db,
+            clock,
+        }
+    }
+
+    /// 读取区间内已存储的K线（按开盘时间升序）
+    pub async fn load(
+        &self,
+        symbol: &str,
+        exchange: &str,
+        interval: &str,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<Vec<KlineData>, DbErr> {
+        let candles = Candle::find()
+            .filter(candle::Column::Symbol.eq(symbol))
+            .filter(candle::Column::Exchange.eq(exchange))
+            .filter(candle::Column::Interval.eq(interval))
+            .filter(candle::Column::OpenTime.between(start_time, end_time))
+            .order_by_asc(candle::Column::OpenTime)
+            .all(&self.db)
+            .await?;
+
+        Ok(candles
+            .into_iter()
+            .map(|c| KlineData {
+                timestamp: c.open_time,
+                open: c.open.normalize(),
+                high: c.high.normalize(),
+                low: c.low.normalize(),
+                close: c.close.normalize(),
+                volume: c.volume.normalize(),
+                source: c.exchange,
+            })
+            .collect())
+    }
+
+    /// 写入K线，同一根K线（交易对/交易所/周期/开盘时间）已存在时覆盖
+    pub async fn save(
+        &self,
+        symbol: &str,
+        exchange: &str,
+        interval: &str,
+        klines: &[KlineData],
+    ) -> Result<(), DbErr> {
+        for chunk in klines.chunks(SAVE_BATCH_SIZE) {
+            let models = chunk.iter().map(|k| candle::ActiveModel {
+                symbol: Set(symbol.to_string()),
+                exchange: Set(exchange.to_string()),
+                interval: Set(interval.to_string()),
+                open_time: Set(k.timestamp),
+                open: Set(k.open),
+                high: Set(k.high),
+                low: Set(k.low),
+                close: Set(k.close),
+                volume: Set(k.volume),
+                created_at: Set(Utc::now().into()),
+                ..Default::default()
+            });
+
+            Candle::insert_many(models)
+                .on_conflict(
+                    OnConflict::columns([
+                        candle::Column::Symbol,
+                        candle::Column::Exchange,
+                        candle::Column::Interval,
+                        candle::Column::OpenTime,
+                    ])
+                    .update_columns([
+                        candle::Column::Open,
+                        candle::Column::High,
+                        candle::Column::Low,
+                        candle::Column::Close,
+                        candle::Column::Volume,
+                    ])
+                    .to_owned(),
+                )
+                .exec_without_returning(&self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 获取K线：数据源原生支持的周期直接获取，其余周期由更细的基础周期重采样
+    pub async fn get_klines(
+        &self,
+        source: &dyn MarketDataSource,
+        query: &KlineQuery,
+    ) -> Result<Vec<KlineData>, MarketDataError> {
+        Ok(self.get_checked_klines(source, query).await?.klines)
+    }
+
+    /// 同 [`get_klines`](Self::get_klines)，并返回数据质量报告
+    ///
+    /// 数据源返回的K线在写入存储前经过 [`quality::sanitize`] 修正，
+    /// 报告包含修正记录、缺失周期、零成交量K线以及完整度、新鲜度和耗时。
+    pub async fn get_checked_klines(
+        &self,
+        source: &dyn MarketDataSource,
+        query: &KlineQuery,
+    ) -> Result<CheckedKlines, MarketDataError> {
+        let started = Instant::now();
+        let target = Interval::parse(&query.interval)
+            .ok_or_else(|| MarketDataError::InvalidInterval(query.interval.clone()))?;
+        let (klines, issues) = self.get_sanitized_klines(source, query, &target).await?;
+
+        let now = self.clock.now_millis();
+        let window = requested_window(query, &target, now);
+        let quality = quality::inspect(&klines, &target, issues, window, now, started.elapsed());
+        if quality.repaired + quality.dropped > 0 {
+            log::warn!(
+                "{} {} {} K线数据已修正 {} 根、剔除 {} 根",
+                source.name(),
+                query.symbol,
+                query.interval,
+                quality.repaired,
+                quality.dropped
+            );
+        }
+        Ok(CheckedKlines { klines, quality })
+    }
+
+    async fn get_sanitized_klines(
+        &self,
+        source: &dyn MarketDataSource,
+        query: &KlineQuery,
+        target: &Interval,
+    ) -> Result<(Vec<KlineData>, Vec<QualityIssue>), MarketDataError> {
+        let supported = source.supported_intervals();
+        if supported.contains(&target.to_string().as_str()) {
+            return self.get_native_klines(source, query, target).await;
+        }
+
+        let base = choose_base_interval(target, &supported).ok_or_else(|| {
+            MarketDataError::UnsupportedInterval {
+                exchange: source.name(),
+                interval: query.interval.clone(),
+            }
+        })?;
+
+        // 多取一个目标周期的基础K线，并把区间边界对齐到目标周期，保证首尾周期完整
+        let ratio = (target.approx_millis() / base.approx_millis()).max(1);
+        let base_limit = (i64::from(query.limit) + 1)
+            .saturating_mul(ratio)
+            .min(i64::from(u32::MAX)) as u32;
+        let base_query = KlineQuery {
+            interval: base.to_string(),
+            limit: base_limit,
+            start_time: query.start_time.map(|ts| target.bucket_start(ts)),
+            end_time: query.end_time.map(|ts| target.next_bucket(ts) - 1),
+            ..query.clone()
+        };
+
+        let (base_klines, issues) = self.get_native_klines(source, &base_query, &base).await?;
+        let klines = resample(&base_klines, target);
+
+        Ok((apply_limit(klines, query), issues))
+    }
+
+    /// 获取数据源原生周期的K线：先读本地存储，再从数据源分页补齐缺失且未请求过的区间并回写
+    ///
+    /// 只有提供原生OHLC的数据源会被缓存，其余直接透传。
+    /// 未收盘的K线不会入库，因此最新一根总是从数据源获取。
+    /// 每页获取后立即入库并记录同步区间，中途失败时已入库的页不会重复请求。
+    /// 从数据源获取的K线都先经过 [`quality::sanitize`]，返回发现的问题。
+    async fn get_native_klines(
+        &self,
+        source: &dyn MarketDataSource,
+        query: &KlineQuery,
+        interval: &Interval,
+    ) -> Result<(Vec<KlineData>, Vec<QualityIssue>), MarketDataError> {
+        let Some(step) = interval.millis() else {
+            return Ok(quality::sanitize(
+                source.fetch_klines(query).await?,
+                interval,
+            ));
+        };
+        let interval_name = interval.to_string();
+
+        let now = self.clock.now_millis();
+        let (start_time, end_time) = resolve_window(query, step, now);
+
+        if !source.capabilities().native_ohlc {
+            let klines = fetch_range(source, query, start_time, end_time, step, now).await?;
+            let (klines, issues) = quality::sanitize(klines, interval);
+            return Ok((apply_limit(klines, query), issues));
+        }
+
+        let symbol = query.symbol.to_string();
+        let exchange = source.name();
+
+        let stored = self
+            .load(&symbol, exchange, &interval_name, start_time, end_time)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!(
+                    "读取K线存储失败 {} {} {}: {}",
+                    exchange,
+                    symbol,
+                    interval,
+                    e
+                );
+                Vec::new()
+            });
+
+        let series = CoverageSeries {
+            dataset: coverage_store::CANDLES,
+            symbol: &symbol,
+            exchange,
+            interval: &interval_name,
+        };
+        let covered = self
+            .coverage
+            .load(&series, start_time, end_time)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!(
+                    "读取K线同步区间失败 {} {} {}: {}",
+                    exchange,
+                    symbol,
+                    interval,
+                    e
+                );
+                Vec::new()
+            });
+
+        let open_times: Vec<i64> = stored.iter().map(|k| k.timestamp).collect();
+        let missing = subtract_ranges(
+            &missing_ranges(&open_times, start_time, end_time.min(now), step),
+            &covered,
+        );
+        if missing.is_empty() {
+            return Ok((apply_limit(stored, query), Vec::new()));
+        }
+
+        // 逐页入库，中途失败时已获取的页保留下来，下次只需补齐剩余部分
+        let mut fetched = Vec::new();
+        let mut issues = Vec::new();
+        for &(from, to) in &missing {
+            let mut pager = RangePager::new(source, query, from, to, step, now);
+            while let Some(page) = pager.next_page().await? {
+                let (klines, page_issues) = quality::sanitize(page.klines, interval);
+                let closed: Vec<KlineData> = klines
+                    .iter()
+                    .filter(|k| k.timestamp + step <= now)
+                    .cloned()
+                    .collect();
+                let covered_to = synced_until(page.to, &closed, now, step);
+                self.save_page(&series, &closed, page.from, covered_to)
+                    .await;
+                fetched.extend(klines);
+                issues.extend(page_issues);
+            }
+        }
+
+        let mut merged: BTreeMap<i64, KlineData> =
+            stored.into_iter().map(|k| (k.timestamp, k)).collect();
+        merged.extend(fetched.into_iter().map(|k| (k.timestamp, k)));
+
+        Ok((apply_limit(merged.into_values().collect(), query), issues))
+    }
+
+    /// 写入一页已收盘的K线，全部入库后才把 `[from, covered_to]` 记为已同步
+    async fn save_page(
+        &self,
+        series: &CoverageSeries<'_>,
+        closed: &[KlineData],
+        from: i64,
+        covered_to: Option<i64>,
+    ) {
+        let result = async {
+            self.save(series.symbol, series.exchange, series.interval, closed)
+                .await?;
+            match covered_to {
+                Some(to) => self.coverage.record(series, from, to).await,
+                None => Ok(()),
+            }
+        }
+        .await;
+        if let Err(e) = result {
+            log::warn!(
+                "写入K线存储失败 {} {} {}: {}",
+                series.exchange,
+                series.symbol,
+                series.interval,
+                e
+            );
+        }
+    }
+}
+
+/// 根据查询参数确定请求的时间区间（闭区间，毫秒）
+///
+/// 指定起点时从起点向后取 `limit` 根，否则从终点（默认当前时间）向前取 `limit` 根。
+pub fn resolve_window(query: &KlineQuery, step: i64, now: i64) -> (i64, i64) {
+    let span = step * i64::from(query.limit.max(1));
+    match (query.start_time, query.end_time) {
+        (Some(start_time), end_time) => {
+            let end_time = end_time.unwrap_or(now).min(start_time + span);
+            (start_time, end_time)
+        }
+        (None, end_time) => {
+            let end_time = end_time.unwrap_or(now);
+            (end_time - span, end_time)
+        }
+    }
+}
+
+/// 查询实际请求的区间，固定长度周期按 `limit` 收窄到最多 `limit` 个周期，用于计算完整度
+fn requested_window(query: &KlineQuery, interval: &Interval, now: i64) -> (i64, i64) {
+    let (start, end) = resolve_window(query, interval.approx_millis(), now);
+    let Some(step) = interval.millis() else {
+        return (start, end);
+    };
+    let span = step * (i64::from(query.limit.max(1)) - 1);
+    if query.start_time.is_some() {
+        let first = interval.next_bucket(start - 1);
+        (start, end.min(first + span))
+    } else {
+        let last = interval.bucket_start(end);
+        (start.max(last - span), end)
+    }
+}
+
+/// 一页K线入库后可记为已同步的终点
+///
+/// 接近当前时间的页，上游可能尚未返回刚收盘的K线或只返回了一部分，
+/// 只记录到实际返回的最后一根已收盘K线；更早的页记录整页区间。
+fn synced_until(page_to: i64, closed: &[KlineData], now: i64, step: i64) -> Option<i64> {
+    if page_to > now - 2 * step {
+        closed.last().map(|k| k.timestamp + step - 1)
+    } else {
+        Some(page_to)
+    }
+}
+
+/// 按 `limit` 截取结果：指定起点时保留最早的部分，否则保留最新的部分
+pub fn apply_limit<T>(items: Vec<T>, query: &KlineQuery) -> Vec<T> {
+    let limit = query.limit as usize;
+    if query.start_time.is_some() {
+        items.into_iter().take(limit).collect()
+    } else {
+        let skip = items.len().saturating_sub(limit);
+        items.into_iter().skip(skip).collect()
+    }
+}
+
+/// 计算区间 `[start, end]` 内缺失的K线范围（闭区间，毫秒）
+///
+/// `open_times` 须升序排列；相邻K线间隔超过一个周期即视为缺口。
+pub fn missing_ranges(open_times: &[i64], start: i64, end: i64, step: i64) -> Vec<(i64, i64)> {
+    let mut ranges = Vec::new();
+    let mut cursor = start;
+
+    for &open_time in open_times {
+        if open_time - cursor >= step {
+            ranges.push((cursor, open_time - 1));
+        }
+        cursor = cursor.max(open_time + step);
+    }
+
+    if cursor <= end {
+        ranges.push((cursor, end));
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::kline;
+
+    const HOUR_MS: i64 = 3_600_000;
+
+    #[test]
+    fn syncs_trailing_page_only_up_to_returned_bars() {
+        let now = 100 * HOUR_MS + 60_000;
+        let bars = ["1", "1", "1", "1", "1"];
+        let closed = vec![kline(97 * HOUR_MS, bars), kline(98 * HOUR_MS, bars)];
+
+        // 上游尚未返回刚收盘的 99h K线
+        assert_eq!(
+            synced_until(now - 1, &closed, now, HOUR_MS),
+            Some(99 * HOUR_MS - 1)
+        );
+        assert_eq!(synced_until(now - 1, &[], now, HOUR_MS), None);
+        // 更早的页即使为空也整页记为已同步
+        assert_eq!(
+            synced_until(50 * HOUR_MS - 1, &[], now, HOUR_MS),
+            Some(50 * HOUR_MS - 1)
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+
+use crate::models::{market_data_coverage, MarketDataCoverage};
+
+/// K线的同步区间
+pub const CANDLES: &str = "candles";
+/// 资金费率的同步区间
+pub const FUNDING_RATES: &str = "funding_rates";
+
+/// 一组同步区间所属的数据序列
+#[derive(Debug, Clone, Copy)]
+pub struct CoverageSeries<'a> {
+    pub dataset: &'static str,
+    pub symbol: &'a str,
+    pub exchange: &'a str,
+    /// K线周期，不分周期的数据为空字符串
+    pub interval: &'a str,
+}
+
+/// 同步区间存储：记录已向数据源请求过的时间区间（闭区间，毫秒）
+///
+/// 数据源在区间内没有数据（交易对上线前、停机）时也会记录，之后不再重复请求；
+/// 中途失败的同步只记录已完成的部分，下次同步时补齐剩余的缺口。
+pub struct CoverageStore {
+    db: DatabaseConnection,
+}
+
+impl CoverageStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// 与 `[start, end]` 相交的已同步区间，按起点升序合并为互不重叠的区间
+    pub async fn load(
+        &self,
+        series: &CoverageSeries<'_>,
+        start: i64,
+        end: i64,
+    ) -> Result<Vec<(i64, i64)>, DbErr> {
+        let rows = select(series)
+            .filter(market_data_coverage::Column::StartTime.lte(end))
+            .filter(market_data_coverage::Column::EndTime.gte(start))
+            .order_by_asc(market_data_coverage::Column::StartTime)
+            .all(&self.db)
+            .await?;
+
+        Ok(merge_ranges(
+            rows.into_iter()
+                .map(|r| (r.start_time, r.end_time))
+                .collect(),
+        ))
+    }
+
+    /// 记录 `[start, end]` 已同步，与相交或相邻的已有区间合并为一条
+    pub async fn record(
+        &self,
+        series: &CoverageSeries<'_>,
+        start: i64,
+        end: i64,
+    ) -> Result<(), DbErr> {
+        if start > end {
+            return Ok(());
+        }
+
+        let txn = self.db.begin().await?;
+        let touching = select(series)
+            .filter(market_data_coverage::Column::StartTime.lte(end.saturating_add(1)))
+            .filter(market_data_coverage::Column::EndTime.gte(start.saturating_sub(1)))
+            .all(&txn)
+            .await?;
+
+        let merged_start = touching.iter().map(|r| r.start_time).fold(start, i64::min);
+        let merged_end = touching.iter().map(|r| r.end_time).fold(end, i64::max);
+        if !touching.is_empty() {
+            MarketDataCoverage::delete_many()
+                .filter(market_data_coverage::Column::Id.is_in(touching.iter().map(|r| r.id)))
+                .exec(&txn)
+                .await?;
+        }
+
+        MarketDataCoverage::insert(market_data_coverage::ActiveModel {
+            dataset: Set(series.dataset.to_string()),
+            symbol: Set(series.symbol.to_string()),
+            exchange: Set(series.exchange.to_string()),
+            interval: Set(series.interval.to_string()),
+            start_time: Set(merged_start),
+            end_time: Set(merged_end),
+            updated_at: Set(Utc::now().into()),
+            ..Default::default()
+        })
+        .exec_without_returning(&txn)
+        .await?;
+
+        txn.commit().await
+    }
+}
+
+fn select(series: &CoverageSeries<'_>) -> sea_orm::Select<MarketDataCoverage> {
+    MarketDataCoverage::find()
+        .filter(market_data_coverage::Column::Dataset.eq(series.dataset))
+        .filter(market_data_coverage::Column::Exchange.eq(series.exchange))
+        .filter(market_data_coverage::Column::Symbol.eq(series.symbol))
+        .filter(market_data_coverage::Column::Interval.eq(series.interval))
+}
+
+/// 合并相交或相邻的区间，结果按起点升序
+fn merge_ranges(mut ranges: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 从 `ranges` 中去掉 `covered` 覆盖的部分，`covered` 须升序且互不重叠
+pub fn subtract_ranges(ranges: &[(i64, i64)], covered: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut result = Vec::new();
+    for &(start, end) in ranges {
+        let mut cursor = start;
+        for &(covered_start, covered_end) in covered {
+            if covered_end < cursor || covered_start > end {
+                continue;
+            }
+            if covered_start > cursor {
+                result.push((cursor, covered_start - 1));
+            }
+            cursor = covered_end.saturating_add(1);
+            if cursor > end {
+                break;
+            }
+        }
+        if cursor <= end {
+            result.push((cursor, end));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_overlapping_and_adjacent_ranges() {
+        let merged = merge_ranges(vec![(20, 29), (0, 9), (10, 12), (25, 40), (50, 60)]);
+
+        assert_eq!(merged, vec![(0, 12), (20, 40), (50, 60)]);
+    }
+
+    #[test]
+    fn subtracts_covered_ranges() {
+        let covered = [(0, 9), (20, 29), (40, 100)];
+
+        assert_eq!(
+            subtract_ranges(&[(5, 45), (60, 70), (110, 120)], &covered),
+            vec![(10, 19), (30, 39), (110, 120)]
+        );
+        assert_eq!(subtract_ranges(&[(0, 9)], &covered), vec![]);
+    }
+}
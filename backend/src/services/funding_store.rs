@@ -0,0 +1,269 @@
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set,
+};
+
+use crate::models::{funding_rate, FundingRate as FundingRateEntity};
+use crate::services::coverage_store::{self, subtract_ranges, CoverageSeries, CoverageStore};
+use crate::services::market_data::{
+    FundingQuery, FundingRate, MarketDataError, MarketDataSource, Symbol,
+};
+
+/// 单条 INSERT 语句写入的最大记录数
+const SAVE_BATCH_SIZE: usize = 500;
+/// 同步时每页请求的记录数（交易所上限更小时按交易所上限返回）
+const SYNC_PAGE_SIZE: u32 = 1000;
+/// 单次同步最多请求的页数
+const MAX_SYNC_PAGES: usize = 20;
+
+/// 资金费率历史存储：从数据源增量同步已结算的资金费率，用于绘制长期走势
+///
+/// 同步过的区间记录在同步区间表中，中途中断的回补在下次同步时从中断处继续。
+pub struct FundingStore {
+    db: DatabaseConnection,
+    coverage: CoverageStore,
+}
+
+impl FundingStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self {
+            coverage: CoverageStore::new(db.clone()),
+            db,
+        }
+    }
+
+    /// 读取区间内已存储的资金费率（按结算时间升序）
+    ///
+    /// 指定起点时从起点向后取 `limit` 条，否则取最新的 `limit` 条。
+    pub async fn load(
+        &self,
+        symbol: &str,
+        exchange: &str,
+        query: &FundingQuery,
+    ) -> Result<Vec<FundingRate>, DbErr> {
+        let select = FundingRateEntity::find()
+            .filter(funding_rate::Column::Symbol.eq(symbol))
+            .filter(funding_rate::Column::Exchange.eq(exchange))
+            .filter(funding_rate::Column::FundingTime.between(
+                query.start_time.unwrap_or(0),
+                query.end_time.unwrap_or(i64::MAX),
+            ))
+            .limit(u64::from(query.limit));
+
+        let mut rows = if query.start_time.is_some() {
+            select
+                .order_by_asc(funding_rate::Column::FundingTime)
+                .all(&self.db)
+                .await?
+        } else {
+            select
+                .order_by_desc(funding_rate::Column::FundingTime)
+                .all(&self.db)
+                .await?
+        };
+        rows.sort_by_key(|r| r.funding_time);
+
+        Ok(rows
+            .into_iter()
+            .map(|r| FundingRate {
+                symbol: r.symbol,
+                funding_rate: r.funding_rate.normalize(),
+                funding_time: r.funding_time,
+                next_funding_time: None,
+                source: r.exchange,
+            })
+            .collect())
+    }
+
+    /// 写入资金费率，同一结算时间已存在时覆盖
+    pub async fn save(&self, rates: &[FundingRate]) -> Result<(), DbErr> {
+        for chunk in rates.chunks(SAVE_BATCH_SIZE) {
+            let models = chunk.iter().map(|r| funding_rate::ActiveModel {
+                symbol: Set(r.symbol.clone()),
+                exchange: Set(r.source.clone()),
+                funding_time: Set(r.funding_time),
+                // 资金费率列保留10位小数
+                funding_rate: Set(r.funding_rate.round_dp(10)),
+                created_at: Set(Utc::now().into()),
+                ..Default::default()
+            });
+
+            FundingRateEntity::insert_many(models)
+                .on_conflict(
+                    OnConflict::columns([
+                        funding_rate::Column::Symbol,
+                        funding_rate::Column::Exchange,
+                        funding_rate::Column::FundingTime,
+                    ])
+                    .update_column(funding_rate::Column::FundingRate)
+                    .to_owned(),
+                )
+                .exec_without_returning(&self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 从数据源同步资金费率，返回写入的记录数
+    ///
+    /// 补齐最后一个已同步区间之后的部分；指定 `start_time` 时同时补齐其后尚未同步的缺口
+    /// （包括上次因页数上限或请求失败而中断的回补）。尚未同步过且未指定起点时只同步最近一页。
+    pub async fn sync(
+        &self,
+        source: &dyn MarketDataSource,
+        symbol: &Symbol,
+        start_time: Option<i64>,
+    ) -> Result<usize, MarketDataError> {
+        let symbol_key = symbol.to_string();
+        let series = CoverageSeries {
+            dataset: coverage_store::FUNDING_RATES,
+            symbol: &symbol_key,
+            exchange: source.name(),
+            interval: "",
+        };
+        let covered = self
+            .coverage
+            .load(&series, i64::MIN, i64::MAX)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!(
+                    "读取资金费率同步区间失败 {} {}: {}",
+                    source.name(),
+                    symbol_key,
+                    e
+                );
+                Vec::new()
+            });
+
+        let mut ranges = Vec::new();
+        match covered.last() {
+            Some(&(_, synced_until)) => {
+                if let Some(start_time) = start_time.filter(|s| *s <= synced_until) {
+                    ranges.extend(
+                        subtract_ranges(&[(start_time, synced_until)], &covered)
+                            .into_iter()
+                            .map(|(from, until)| (Some(from), Some(until))),
+                    );
+                }
+                let from = start_time.map_or(synced_until + 1, |s| s.max(synced_until + 1));
+                ranges.push((Some(from), None));
+            }
+            None => ranges.push((start_time, None)),
+        }
+
+        let mut synced = 0;
+        for (from, until) in ranges {
+            synced += self
+                .sync_range(source, symbol, &series, from, until)
+                .await?;
+        }
+        Ok(synced)
+    }
+
+    /// 从 `from` 向后逐页同步，直到没有新记录或到达 `until`
+    ///
+    /// 每页写入后把 `from` 至该页最后一条记为已同步，中断时下次从断点继续；
+    /// `from` 为空时只同步最近一页。
+    async fn sync_range(
+        &self,
+        source: &dyn MarketDataSource,
+        symbol: &Symbol,
+        series: &CoverageSeries<'_>,
+        from: Option<i64>,
+        until: Option<i64>,
+    ) -> Result<usize, MarketDataError> {
+        let mut synced = 0;
+        let mut cursor = from;
+        for _ in 0..MAX_SYNC_PAGES {
+            let query = FundingQuery {
+                symbol: symbol.clone(),
+                limit: SYNC_PAGE_SIZE,
+                start_time: cursor,
+                end_time: until,
+            };
+            let page: Vec<FundingRate> = source
+                .fetch_funding_history(&query)
+                .await?
+                .into_iter()
+                .filter(|r| cursor.is_none_or(|cursor| r.funding_time >= cursor))
+                .collect();
+            let (Some(first), Some(last)) = (page.first(), page.last()) else {
+                // 区间内没有更多记录，整个区间已同步
+                if let (Some(from), Some(until)) = (from, until) {
+                    self.record_coverage(series, from, until).await;
+                }
+                break;
+            };
+            let (first, last) = (first.funding_time, last.funding_time);
+
+            if let Err(e) = self.save(&page).await {
+                log::warn!("写入资金费率存储失败 {} {}: {}", source.name(), symbol, e);
+                break;
+            }
+            synced += page.len();
+            self.record_coverage(series, from.unwrap_or(first), last)
+                .await;
+
+            if from.is_none() || until.is_some_and(|until| last >= until) {
+                break;
+            }
+            cursor = Some(last + 1);
+        }
+        Ok(synced)
+    }
+
+    async fn record_coverage(&self, series: &CoverageSeries<'_>, start: i64, end: i64) {
+        if let Err(e) = self.coverage.record(series, start, end).await {
+            log::warn!(
+                "写入资金费率同步区间失败 {} {}: {}",
+                series.exchange,
+                series.symbol,
+                e
+            );
+        }
+    }
+
+    /// 获取资金费率历史：先从数据源同步，再读取存储
+    ///
+    /// 数据源不可用时返回已存储的记录，存储中也没有数据时返回数据源的错误。
+    pub async fn get_history(
+        &self,
+        source: &dyn MarketDataSource,
+        query: &FundingQuery,
+    ) -> Result<Vec<FundingRate>, MarketDataError> {
+        let sync_error = match self.sync(source, &query.symbol, query.start_time).await {
+            Ok(_) => None,
+            Err(e) if e.is_client_error() => return Err(e),
+            Err(e) => {
+                println!(
+                    "⚠️ {} 资金费率同步失败，使用已存储的数据: {}",
+                    source.name(),
+                    e
+                );
+                Some(e)
+            }
+        };
+
+        let stored = self
+            .load(&query.symbol.to_string(), source.name(), query)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!(
+                    "读取资金费率存储失败 {} {}: {}",
+                    source.name(),
+                    query.symbol,
+                    e
+                );
+                Vec::new()
+            });
+
+        match sync_error {
+            Some(e) if stored.is_empty() => Err(e),
+            // 存储不可用时直接返回数据源的数据
+            None if stored.is_empty() => source.fetch_funding_history(query).await,
+            _ => Ok(stored),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::{decimal, KlineData};
+
+/// 永续合约相对现货的基差
+#[derive(Debug, Clone, Serialize)]
+pub struct BasisPoint {
+    pub timestamp: i64,
+    #[serde(with = "decimal")]
+    pub spot_price: Decimal,
+    /// 永续合约标记价格
+    #[serde(with = "decimal")]
+    pub perp_price: Decimal,
+    /// 合约价格 - 现货价格
+    #[serde(with = "decimal")]
+    pub basis: Decimal,
+    /// 基差占现货价格的百分比
+    pub basis_percent: f64,
+}
+
+impl BasisPoint {
+    pub fn new(timestamp: i64, spot_price: Decimal, perp_price: Decimal) -> Self {
+        let basis = perp_price - spot_price;
+        Self {
+            timestamp,
+            spot_price,
+            perp_price,
+            basis,
+            basis_percent: basis
+                .checked_div(spot_price)
+                .and_then(|ratio| ratio.to_f64())
+                .map_or(0.0, |ratio| ratio * 100.0),
+        }
+    }
+}
+
+/// 按开盘时间对齐现货K线和标记价格K线，用收盘价计算基差，只保留两边都有的时间点
+pub fn basis_series(spot: &[KlineData], perp: &[KlineData]) -> Vec<BasisPoint> {
+    let spot_closes: HashMap<i64, Decimal> = spot.iter().map(|k| (k.timestamp, k.close)).collect();
+    perp.iter()
+        .filter_map(|k| {
+            let spot_price = spot_closes.get(&k.timestamp)?;
+            Some(BasisPoint::new(k.timestamp, *spot_price, k.close))
+        })
+        .collect()
+}
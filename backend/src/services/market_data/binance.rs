@@ -0,0 +1,748 @@
+use async_trait::async_trait;
+use reqwest::Url;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::http::{build_client, get_json, opt_str_decimal, parse_levels, str_decimal, HttpClient};
+use super::interval::map_interval;
+use super::rate_limit::{BucketSpec, RateLimitRules, RateLimiter, RequestCost};
+use super::replay::HttpMode;
+use super::stream::{MarketEvent, StreamChannel, StreamKline, StreamProtocol, StreamTopic};
+use super::{
+    FundingQuery, FundingRate, Instrument, InstrumentStatus, KlineData, KlineQuery, MarkPrice,
+    MarketDataError, MarketDataSource, OpenInterest, OrderBook, PriceKind, PublicTrade,
+    SourceCapabilities, Symbol, Ticker, TradeQuery, TradeSide,
+};
+
+const NAME: &str = "binance";
+const BASE_URL: &str = "https://api.binance.com";
+/// U本位合约接口
+const FUTURES_URL: &str = "https://fapi.binance.com";
+/// 标准周期 -> Binance interval 参数
+const INTERVALS: &[(&str, &str)] = &[
+    ("1m", "1m"),
+    ("3m", "3m"),
+    ("5m", "5m"),
+    ("15m", "15m"),
+    ("30m", "30m"),
+    ("1h", "1h"),
+    ("2h", "2h"),
+    ("4h", "4h"),
+    ("6h", "6h"),
+    ("8h", "8h"),
+    ("12h", "12h"),
+    ("1d", "1d"),
+    ("3d", "3d"),
+    ("1w", "1w"),
+    ("1M", "1M"),
+];
+/// `/api/v3/klines` 单次最多返回1000根
+const MAX_LIMIT: u32 = 1000;
+/// `/api/v3/depth` 每侧最多返回5000档
+const MAX_DEPTH: u32 = 5000;
+/// `/api/v3/aggTrades` 单次最多返回1000条
+const MAX_TRADES: u32 = 1000;
+/// `/fapi/v1/markPriceKlines` 单次最多返回1500根
+const MAX_PRICE_KLINES: u32 = 1500;
+/// `/fapi/v1/fundingRate` 单次最多返回1000条
+const MAX_FUNDING_HISTORY: u32 = 1000;
+/// `/futures/data/openInterestHist` 单次最多返回500条
+const MAX_OPEN_INTEREST_HISTORY: u32 = 500;
+/// 标准周期 -> 持仓量统计周期
+const OPEN_INTEREST_PERIODS: &[(&str, &str)] = &[
+    ("5m", "5m"),
+    ("15m", "15m"),
+    ("30m", "30m"),
+    ("1h", "1h"),
+    ("2h", "2h"),
+    ("4h", "4h"),
+    ("6h", "6h"),
+    ("12h", "12h"),
+    ("1d", "1d"),
+];
+/// 交易对不存在的错误码
+const INVALID_SYMBOL_CODE: &str = "-1121";
+
+/// 现货和U本位合约分别按每分钟请求权重限频，资金费率历史和合约统计数据另有独立限制
+static RATE_LIMITS: RateLimitRules = RateLimitRules {
+    buckets: &[
+        BucketSpec::new("spot", 6000, Duration::from_secs(60)),
+        BucketSpec::new("futures", 2400, Duration::from_secs(60)),
+        BucketSpec::new("funding_history", 500, Duration::from_secs(300)),
+        BucketSpec::new("futures_data", 1000, Duration::from_secs(300)),
+    ],
+    cost: request_cost,
+    used_weight_headers: &[
+        ("spot", "x-mbx-used-weight-1m"),
+        ("futures", "x-mbx-used-weight-1m"),
+    ],
+};
+
+/// 按 Binance 公布的接口权重计算请求消耗，部分接口的权重随 `limit` 变化
+fn request_cost(url: &Url) -> RequestCost {
+    let limit = url
+        .query_pairs()
+        .find(|(k, _)| k == "limit")
+        .and_then(|(_, v)| v.parse::<u32>().ok());
+    match url.path() {
+        "/api/v3/klines" | "/api/v3/ticker/24hr" => RequestCost::new("spot", 2),
+        "/api/v3/aggTrades" => RequestCost::new("spot", 4),
+        "/api/v3/exchangeInfo" => RequestCost::new("spot", 20),
+        "/api/v3/depth" => {
+            let weight = match limit.unwrap_or(100) {
+                0..=100 => 5,
+                101..=500 => 25,
+                501..=1000 => 50,
+                _ => 250,
+            };
+            RequestCost::new("spot", weight)
+        }
+        "/fapi/v1/markPriceKlines" | "/fapi/v1/indexPriceKlines" => {
+            let weight = match limit.unwrap_or(500) {
+                0..=99 => 1,
+                100..=499 => 2,
+                500..=1000 => 5,
+                _ => 10,
+            };
+            RequestCost::new("futures", weight)
+        }
+        "/fapi/v1/fundingRate" => RequestCost::new("funding_history", 1),
+        path if path.starts_with("/futures/data/") => RequestCost::new("futures_data", 1),
+        path if path.starts_with("/fapi/") => RequestCost::new("futures", 1),
+        _ => RequestCost::new("spot", 1),
+    }
+}
+
+pub struct BinanceSource {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl BinanceSource {
+    pub fn new(mode: &HttpMode) -> Self {
+        Self {
+            client: build_client(NAME, mode),
+            limiter: RateLimiter::new(NAME, &RATE_LIMITS),
+        }
+    }
+
+    /// 错误码 -1121 表示交易对不存在
+    fn symbol_error(error: MarketDataError, symbol: &Symbol) -> MarketDataError {
+        match &error {
+            MarketDataError::Status { body, .. } if body.contains(INVALID_SYMBOL_CODE) => {
+                MarketDataError::unknown_symbol(NAME, symbol)
+            }
+            _ => error,
+        }
+    }
+
+    /// 合约的标记价格、指数价格和资金费率
+    async fn premium_index(&self, symbol: &Symbol) -> Result<Value, MarketDataError> {
+        let url = format!(
+            "{}/fapi/v1/premiumIndex?symbol={}",
+            FUTURES_URL,
+            symbol.binance()
+        );
+        get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))
+    }
+}
+
+impl Default for BinanceSource {
+    fn default() -> Self {
+        Self::new(&HttpMode::Live)
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for BinanceSource {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            klines: true,
+            ticker: true,
+            order_book: true,
+            trades: true,
+            native_ohlc: true,
+            base_volume: true,
+            max_klines_per_request: MAX_LIMIT,
+            history_page_size: Some(MAX_LIMIT),
+            perpetuals: true,
+        }
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        Some(&self.limiter)
+    }
+
+    fn supported_intervals(&self) -> Vec<&'static str> {
+        INTERVALS.iter().map(|(standard, _)| *standard).collect()
+    }
+
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError> {
+        let interval = map_interval(NAME, INTERVALS, &query.interval)?;
+
+        let mut url = format!(
+            "{}/api/v3/klines?symbol={}&interval={}&limit={}",
+            BASE_URL,
+            query.symbol.binance(),
+            interval,
+            query.limit.min(MAX_LIMIT)
+        );
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&startTime={}", start_time));
+        }
+        if let Some(end_time) = query.end_time {
+            url.push_str(&format!("&endTime={}", end_time));
+        }
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let rows = data
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "Invalid response format"))?;
+
+        let mut klines = Vec::new();
+        for kline in rows {
+            let kline_array = kline
+                .as_array()
+                .filter(|k| k.len() >= 6)
+                .ok_or_else(|| MarketDataError::parse(NAME, "Invalid kline format"))?;
+
+            klines.push(KlineData {
+                timestamp: kline_array[0]
+                    .as_i64()
+                    .ok_or_else(|| MarketDataError::parse(NAME, "Invalid timestamp"))?,
+                open: str_decimal(&kline_array[1], NAME, "开盘价")?,
+                high: str_decimal(&kline_array[2], NAME, "最高价")?,
+                low: str_decimal(&kline_array[3], NAME, "最低价")?,
+                close: str_decimal(&kline_array[4], NAME, "收盘价")?,
+                volume: str_decimal(&kline_array[5], NAME, "成交量")?,
+                source: NAME.to_string(),
+            });
+        }
+
+        Ok(klines)
+    }
+
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        let url = format!(
+            "{}/api/v3/ticker/24hr?symbol={}",
+            BASE_URL,
+            symbol.binance()
+        );
+
+        let ticker = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+
+        Ok(Ticker {
+            symbol: symbol.to_string(),
+            price: str_decimal(&ticker["lastPrice"], NAME, "最新价")?,
+            price_change_24h: opt_str_decimal(&ticker["priceChange"]),
+            price_change_percent_24h: opt_str_decimal(&ticker["priceChangePercent"]),
+            high_24h: opt_str_decimal(&ticker["highPrice"]),
+            low_24h: opt_str_decimal(&ticker["lowPrice"]),
+            volume_24h: opt_str_decimal(&ticker["volume"]),
+            quote_volume_24h: opt_str_decimal(&ticker["quoteVolume"]),
+            market_cap: None,
+            timestamp: ticker["closeTime"]
+                .as_i64()
+                .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+            source: NAME.to_string(),
+        })
+    }
+
+    async fn fetch_order_book(
+        &self,
+        symbol: &Symbol,
+        depth: u32,
+    ) -> Result<OrderBook, MarketDataError> {
+        let url = format!(
+            "{}/api/v3/depth?symbol={}&limit={}",
+            BASE_URL,
+            symbol.binance(),
+            depth.clamp(1, MAX_DEPTH)
+        );
+
+        let book = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+
+        Ok(OrderBook {
+            symbol: symbol.to_string(),
+            bids: parse_levels(&book["bids"], NAME)?,
+            asks: parse_levels(&book["asks"], NAME)?,
+            // 深度接口不返回时间，使用接收时间
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            source: NAME.to_string(),
+        })
+    }
+
+    /// 使用归集成交（aggTrades），按连续的归集ID翻页且无需API Key
+    async fn fetch_trades(&self, query: &TradeQuery) -> Result<Vec<PublicTrade>, MarketDataError> {
+        let limit = query.limit.clamp(1, MAX_TRADES);
+
+        let mut url = format!(
+            "{}/api/v3/aggTrades?symbol={}&limit={}",
+            BASE_URL,
+            query.symbol.binance(),
+            limit
+        );
+        let before = match &query.before {
+            Some(before) => {
+                let before: u64 = before
+                    .parse()
+                    .map_err(|_| MarketDataError::InvalidCursor(before.clone()))?;
+                url.push_str(&format!(
+                    "&fromId={}",
+                    before.saturating_sub(u64::from(limit))
+                ));
+                Some(before)
+            }
+            None => None,
+        };
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let rows = data
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "Invalid response format"))?;
+
+        let mut trades = Vec::new();
+        // 按时间从旧到新返回，倒序遍历转为从新到旧
+        for trade in rows.iter().rev() {
+            let id = trade["a"]
+                .as_u64()
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效成交ID"))?;
+            if before.is_some_and(|before| id >= before) {
+                continue;
+            }
+            trades.push(parse_agg_trade(trade, id, &query.symbol)?);
+        }
+
+        Ok(trades)
+    }
+
+    async fn fetch_instruments(&self) -> Result<Vec<Instrument>, MarketDataError> {
+        let url = format!("{}/api/v3/exchangeInfo?permissions=SPOT", BASE_URL);
+
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        let symbols = data["symbols"]
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无交易对数据"))?;
+
+        let instruments = symbols
+            .iter()
+            .filter_map(|info| {
+                let base = info["baseAsset"].as_str()?;
+                let quote = info["quoteAsset"].as_str()?;
+                let filter = |filter_type: &str, field: &str| {
+                    info["filters"]
+                        .as_array()?
+                        .iter()
+                        .find(|f| f["filterType"].as_str() == Some(filter_type))
+                        .and_then(|f| opt_str_decimal(&f[field]))
+                };
+                Some(Instrument {
+                    symbol: Symbol::new(base, quote).to_string(),
+                    base: base.to_string(),
+                    quote: quote.to_string(),
+                    exchange: NAME.to_string(),
+                    exchange_symbol: info["symbol"].as_str()?.to_string(),
+                    tick_size: filter("PRICE_FILTER", "tickSize"),
+                    lot_size: filter("LOT_SIZE", "stepSize"),
+                    min_size: filter("LOT_SIZE", "minQty"),
+                    status: match info["status"].as_str() {
+                        Some("TRADING") => InstrumentStatus::Trading,
+                        Some("PRE_TRADING") => InstrumentStatus::PreTrading,
+                        // 已下架的交易对状态为 BREAK
+                        Some("BREAK") => InstrumentStatus::Delisted,
+                        _ => InstrumentStatus::Halted,
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        println!("✅ Binance获取 {} 个现货交易对", instruments.len());
+        Ok(instruments)
+    }
+
+    /// `lastFundingRate` 为本期预测费率，在 `nextFundingTime` 结算
+    async fn fetch_funding_rate(&self, symbol: &Symbol) -> Result<FundingRate, MarketDataError> {
+        let index = self.premium_index(symbol).await?;
+        Ok(FundingRate {
+            symbol: symbol.to_string(),
+            funding_rate: str_decimal(&index["lastFundingRate"], NAME, "资金费率")?,
+            funding_time: index["nextFundingTime"]
+                .as_i64()
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效的结算时间"))?,
+            next_funding_time: None,
+            source: NAME.to_string(),
+        })
+    }
+
+    async fn fetch_funding_history(
+        &self,
+        query: &FundingQuery,
+    ) -> Result<Vec<FundingRate>, MarketDataError> {
+        let mut url = format!(
+            "{}/fapi/v1/fundingRate?symbol={}&limit={}",
+            FUTURES_URL,
+            query.symbol.binance(),
+            query.limit.clamp(1, MAX_FUNDING_HISTORY)
+        );
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&startTime={}", start_time));
+        }
+        if let Some(end_time) = query.end_time {
+            url.push_str(&format!("&endTime={}", end_time));
+        }
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let rows = data
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "Invalid response format"))?;
+
+        rows.iter()
+            .map(|rate| {
+                Ok(FundingRate {
+                    symbol: query.symbol.to_string(),
+                    funding_rate: str_decimal(&rate["fundingRate"], NAME, "资金费率")?,
+                    funding_time: rate["fundingTime"]
+                        .as_i64()
+                        .ok_or_else(|| MarketDataError::parse(NAME, "无效的结算时间"))?,
+                    next_funding_time: None,
+                    source: NAME.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    async fn fetch_open_interest(&self, symbol: &Symbol) -> Result<OpenInterest, MarketDataError> {
+        let url = format!(
+            "{}/fapi/v1/openInterest?symbol={}",
+            FUTURES_URL,
+            symbol.binance()
+        );
+
+        let oi = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+
+        Ok(OpenInterest {
+            symbol: symbol.to_string(),
+            timestamp: oi["time"]
+                .as_i64()
+                .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+            open_interest: str_decimal(&oi["openInterest"], NAME, "持仓量")?,
+            // 当前持仓量接口不返回持仓价值
+            open_interest_value: None,
+            source: NAME.to_string(),
+        })
+    }
+
+    /// 只提供最近30天的数据
+    async fn fetch_open_interest_history(
+        &self,
+        query: &KlineQuery,
+    ) -> Result<Vec<OpenInterest>, MarketDataError> {
+        let period = map_interval(NAME, OPEN_INTEREST_PERIODS, &query.interval)?;
+
+        let mut url = format!(
+            "{}/futures/data/openInterestHist?symbol={}&period={}&limit={}",
+            FUTURES_URL,
+            query.symbol.binance(),
+            period,
+            query.limit.clamp(1, MAX_OPEN_INTEREST_HISTORY)
+        );
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&startTime={}", start_time));
+        }
+        if let Some(end_time) = query.end_time {
+            url.push_str(&format!("&endTime={}", end_time));
+        }
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let rows = data
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "Invalid response format"))?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(OpenInterest {
+                    symbol: query.symbol.to_string(),
+                    timestamp: row["timestamp"]
+                        .as_i64()
+                        .ok_or_else(|| MarketDataError::parse(NAME, "Invalid timestamp"))?,
+                    open_interest: str_decimal(&row["sumOpenInterest"], NAME, "持仓量")?,
+                    open_interest_value: opt_str_decimal(&row["sumOpenInterestValue"]),
+                    source: NAME.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    async fn fetch_mark_price(&self, symbol: &Symbol) -> Result<MarkPrice, MarketDataError> {
+        let index = self.premium_index(symbol).await?;
+        Ok(MarkPrice {
+            symbol: symbol.to_string(),
+            mark_price: str_decimal(&index["markPrice"], NAME, "标记价格")?,
+            index_price: opt_str_decimal(&index["indexPrice"]),
+            timestamp: index["time"]
+                .as_i64()
+                .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+            source: NAME.to_string(),
+        })
+    }
+
+    async fn fetch_price_klines(
+        &self,
+        kind: PriceKind,
+        query: &KlineQuery,
+    ) -> Result<Vec<KlineData>, MarketDataError> {
+        let interval = map_interval(NAME, INTERVALS, &query.interval)?;
+
+        // 指数价格K线按 pair 查询
+        let (path, param) = match kind {
+            PriceKind::Mark => ("markPriceKlines", "symbol"),
+            PriceKind::Index => ("indexPriceKlines", "pair"),
+        };
+        let mut url = format!(
+            "{}/fapi/v1/{}?{}={}&interval={}&limit={}",
+            FUTURES_URL,
+            path,
+            param,
+            query.symbol.binance(),
+            interval,
+            query.limit.clamp(1, MAX_PRICE_KLINES)
+        );
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&startTime={}", start_time));
+        }
+        if let Some(end_time) = query.end_time {
+            url.push_str(&format!("&endTime={}", end_time));
+        }
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let rows = data
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "Invalid response format"))?;
+
+        rows.iter()
+            .map(|kline| {
+                let kline = kline
+                    .as_array()
+                    .filter(|k| k.len() >= 5)
+                    .ok_or_else(|| MarketDataError::parse(NAME, "Invalid kline format"))?;
+                Ok(KlineData {
+                    timestamp: kline[0]
+                        .as_i64()
+                        .ok_or_else(|| MarketDataError::parse(NAME, "Invalid timestamp"))?,
+                    open: str_decimal(&kline[1], NAME, "开盘价")?,
+                    high: str_decimal(&kline[2], NAME, "最高价")?,
+                    low: str_decimal(&kline[3], NAME, "最低价")?,
+                    close: str_decimal(&kline[4], NAME, "收盘价")?,
+                    volume: Decimal::ZERO,
+                    source: NAME.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// 归集成交，REST 和 WebSocket 推送的字段相同
+fn parse_agg_trade(
+    trade: &Value,
+    id: u64,
+    symbol: &Symbol,
+) -> Result<PublicTrade, MarketDataError> {
+    Ok(PublicTrade {
+        id: id.to_string(),
+        symbol: symbol.to_string(),
+        // m=true 表示买方是挂单方，即主动卖出
+        side: if trade["m"].as_bool().unwrap_or(false) {
+            TradeSide::Sell
+        } else {
+            TradeSide::Buy
+        },
+        price: str_decimal(&trade["p"], NAME, "成交价")?,
+        amount: str_decimal(&trade["q"], NAME, "成交量")?,
+        timestamp: trade["T"]
+            .as_i64()
+            .ok_or_else(|| MarketDataError::parse(NAME, "Invalid timestamp"))?,
+        source: NAME.to_string(),
+    })
+}
+
+/// Binance WebSocket 推送，使用 `SUBSCRIBE` 方法动态订阅
+pub struct BinanceStream {
+    url: String,
+    /// Binance symbol（如 `BTCUSDT`）-> 统一交易对，推送消息只带 Binance symbol
+    symbols: Mutex<HashMap<String, Symbol>>,
+    next_id: AtomicU64,
+}
+
+impl BinanceStream {
+    pub const URL: &'static str = "wss://stream.binance.com:9443/ws";
+
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            symbols: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn stream_name(topic: &StreamTopic) -> Option<String> {
+        let symbol = topic.symbol.binance().to_lowercase();
+        match &topic.channel {
+            StreamChannel::Ticker => Some(format!("{}@ticker", symbol)),
+            StreamChannel::Trade => Some(format!("{}@aggTrade", symbol)),
+            StreamChannel::Kline(interval) => map_interval(NAME, INTERVALS, interval)
+                .ok()
+                .map(|interval| format!("{}@kline_{}", symbol, interval)),
+        }
+    }
+
+    /// `SUBSCRIBE`/`UNSUBSCRIBE` 请求
+    fn request(&self, method: &str, topics: &[StreamTopic]) -> Option<String> {
+        let params: Vec<String> = topics.iter().filter_map(Self::stream_name).collect();
+        if params.is_empty() {
+            return None;
+        }
+        Some(
+            json!({
+                "method": method,
+                "params": params,
+                "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            })
+            .to_string(),
+        )
+    }
+
+    fn symbol(&self, exchange_symbol: &str) -> Result<Symbol, MarketDataError> {
+        match self.symbols.lock().unwrap().get(exchange_symbol) {
+            Some(symbol) => Ok(symbol.clone()),
+            None => Symbol::parse(exchange_symbol),
+        }
+    }
+}
+
+impl StreamProtocol for BinanceStream {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn supports(&self, channel: &StreamChannel) -> bool {
+        match channel {
+            StreamChannel::Kline(interval) => map_interval(NAME, INTERVALS, interval).is_ok(),
+            _ => true,
+        }
+    }
+
+    fn subscribe_message(&self, topics: &[StreamTopic]) -> Option<String> {
+        let mut symbols = self.symbols.lock().unwrap();
+        for topic in topics {
+            symbols.insert(topic.symbol.binance(), topic.symbol.clone());
+        }
+        self.request("SUBSCRIBE", topics)
+    }
+
+    fn unsubscribe_message(&self, topics: &[StreamTopic]) -> Option<String> {
+        self.request("UNSUBSCRIBE", topics)
+    }
+
+    fn parse_message(&self, text: &str) -> Result<Vec<MarketEvent>, MarketDataError> {
+        let message: Value = serde_json::from_str(text)
+            .map_err(|e| MarketDataError::parse(NAME, format!("无效的推送消息: {}", e)))?;
+        if let Some(error) = message.get("error") {
+            return Err(MarketDataError::stream(
+                NAME,
+                format!(
+                    "{} {}",
+                    error["code"],
+                    error["msg"].as_str().unwrap_or("Unknown error")
+                ),
+            ));
+        }
+
+        let event = match message["e"].as_str() {
+            // 订阅响应 {"result":null,"id":1}
+            None => return Ok(Vec::new()),
+            Some(event) => event,
+        };
+        let symbol = self.symbol(message["s"].as_str().unwrap_or_default())?;
+
+        let event = match event {
+            "24hrTicker" => MarketEvent::Ticker(Ticker {
+                symbol: symbol.to_string(),
+                price: str_decimal(&message["c"], NAME, "最新价")?,
+                price_change_24h: opt_str_decimal(&message["p"]),
+                price_change_percent_24h: opt_str_decimal(&message["P"]),
+                high_24h: opt_str_decimal(&message["h"]),
+                low_24h: opt_str_decimal(&message["l"]),
+                volume_24h: opt_str_decimal(&message["v"]),
+                quote_volume_24h: opt_str_decimal(&message["q"]),
+                market_cap: None,
+                timestamp: message["E"]
+                    .as_i64()
+                    .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+                source: NAME.to_string(),
+            }),
+            "aggTrade" => {
+                let id = message["a"]
+                    .as_u64()
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效成交ID"))?;
+                MarketEvent::Trade(parse_agg_trade(&message, id, &symbol)?)
+            }
+            "kline" => {
+                let k = &message["k"];
+                let native = k["i"].as_str().unwrap_or_default();
+                let interval = INTERVALS
+                    .iter()
+                    .find(|(_, n)| *n == native)
+                    .map(|(standard, _)| *standard)
+                    .ok_or_else(|| MarketDataError::parse(NAME, "未知的K线周期"))?;
+                MarketEvent::Kline(StreamKline {
+                    symbol: symbol.to_string(),
+                    interval: interval.to_string(),
+                    closed: k["x"].as_bool().unwrap_or(false),
+                    kline: KlineData {
+                        timestamp: k["t"]
+                            .as_i64()
+                            .ok_or_else(|| MarketDataError::parse(NAME, "Invalid timestamp"))?,
+                        open: str_decimal(&k["o"], NAME, "开盘价")?,
+                        high: str_decimal(&k["h"], NAME, "最高价")?,
+                        low: str_decimal(&k["l"], NAME, "最低价")?,
+                        close: str_decimal(&k["c"], NAME, "收盘价")?,
+                        volume: str_decimal(&k["v"], NAME, "成交量")?,
+                        source: NAME.to_string(),
+                    },
+                })
+            }
+            _ => return Ok(Vec::new()),
+        };
+        Ok(vec![event])
+    }
+}
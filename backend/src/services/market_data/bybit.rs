@@ -0,0 +1,438 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::http::{
+    build_client, get_json, opt_str_decimal, parse_levels, str_decimal, str_i64, HttpClient,
+};
+use super::interval::map_interval;
+use super::rate_limit::{BucketSpec, RateLimitRules, RateLimiter, RequestCost};
+use super::replay::HttpMode;
+use super::{
+    Instrument, InstrumentStatus, KlineData, KlineQuery, MarketDataError, MarketDataSource,
+    OrderBook, PublicTrade, SourceCapabilities, Symbol, Ticker, TradeQuery, TradeSide,
+};
+
+const NAME: &str = "bybit";
+const BASE_URL: &str = "https://api.bybit.com";
+/// 标准周期 -> Bybit interval 参数
+const INTERVALS: &[(&str, &str)] = &[
+    ("1m", "1"),
+    ("3m", "3"),
+    ("5m", "5"),
+    ("15m", "15"),
+    ("30m", "30"),
+    ("1h", "60"),
+    ("2h", "120"),
+    ("4h", "240"),
+    ("6h", "360"),
+    ("12h", "720"),
+    ("1d", "D"),
+    ("1w", "W"),
+    ("1M", "M"),
+];
+/// `/v5/market/kline` 单次最多返回1000根
+const MAX_LIMIT: u32 = 1000;
+/// 现货 `/v5/market/orderbook` 每侧最多返回200档
+const MAX_DEPTH: u32 = 200;
+/// 现货 `/v5/market/recent-trade` 单次最多返回60条
+const MAX_TRADES: u32 = 60;
+/// 交易对不存在的错误码
+const INVALID_SYMBOL_CODES: &[i64] = &[10001, 170121];
+
+/// 每个IP每5秒最多600次请求，各接口共用
+static RATE_LIMITS: RateLimitRules = RateLimitRules {
+    buckets: &[BucketSpec::new("ip", 600, Duration::from_secs(5))],
+    cost: |_| RequestCost::new("ip", 1),
+    used_weight_headers: &[],
+};
+
+pub struct BybitSource {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl BybitSource {
+    pub fn new(mode: &HttpMode) -> Self {
+        Self {
+            client: build_client(NAME, mode),
+            limiter: RateLimiter::new(NAME, &RATE_LIMITS),
+        }
+    }
+
+    /// 检查 Bybit 响应的 retCode 并取出 result 字段
+    fn unwrap_result(data: &Value) -> Result<&Value, MarketDataError> {
+        let code = data["retCode"].as_i64().unwrap_or(-1);
+        if code != 0 {
+            return Err(MarketDataError::api(
+                NAME,
+                format!(
+                    "{} {}",
+                    code,
+                    data["retMsg"].as_str().unwrap_or("Unknown error")
+                ),
+            ));
+        }
+        Ok(&data["result"])
+    }
+
+    /// 请求现货接口并取出 result 字段，交易对不存在时返回 `UnknownSymbol`
+    async fn get_result(&self, url: &str, symbol: &Symbol) -> Result<Value, MarketDataError> {
+        let data = get_json(&self.client, &self.limiter, url).await?;
+        match data["retCode"].as_i64() {
+            Some(code)
+                if INVALID_SYMBOL_CODES.contains(&code)
+                    && data["retMsg"]
+                        .as_str()
+                        .is_some_and(|msg| msg.to_lowercase().contains("symbol")) =>
+            {
+                Err(MarketDataError::unknown_symbol(NAME, symbol))
+            }
+            _ => Self::unwrap_result(&data).cloned(),
+        }
+    }
+}
+
+impl Default for BybitSource {
+    fn default() -> Self {
+        Self::new(&HttpMode::Live)
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for BybitSource {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            klines: true,
+            ticker: true,
+            order_book: true,
+            trades: true,
+            native_ohlc: true,
+            base_volume: true,
+            max_klines_per_request: MAX_LIMIT,
+            history_page_size: Some(MAX_LIMIT),
+            perpetuals: false,
+        }
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        Some(&self.limiter)
+    }
+
+    fn supported_intervals(&self) -> Vec<&'static str> {
+        INTERVALS.iter().map(|(standard, _)| *standard).collect()
+    }
+
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError> {
+        let interval = map_interval(NAME, INTERVALS, &query.interval)?;
+
+        let mut url = format!(
+            "{}/v5/market/kline?category=spot&symbol={}&interval={}&limit={}",
+            BASE_URL,
+            query.symbol.bybit(),
+            interval,
+            query.limit.clamp(1, MAX_LIMIT)
+        );
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&start={}", start_time));
+        }
+        if let Some(end_time) = query.end_time {
+            url.push_str(&format!("&end={}", end_time));
+        }
+
+        println!("🟣 Bybit请求: {}", url);
+
+        let result = self.get_result(&url, &query.symbol).await?;
+        let klines = parse_klines(&result)?;
+
+        println!("✅ Bybit成功获取 {} 个K线数据点", klines.len());
+        Ok(klines)
+    }
+
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        let url = format!(
+            "{}/v5/market/tickers?category=spot&symbol={}",
+            BASE_URL,
+            symbol.bybit()
+        );
+
+        let result = self.get_result(&url, symbol).await?;
+        parse_ticker(&result, symbol)
+    }
+
+    async fn fetch_order_book(
+        &self,
+        symbol: &Symbol,
+        depth: u32,
+    ) -> Result<OrderBook, MarketDataError> {
+        let url = format!(
+            "{}/v5/market/orderbook?category=spot&symbol={}&limit={}",
+            BASE_URL,
+            symbol.bybit(),
+            depth.clamp(1, MAX_DEPTH)
+        );
+
+        let result = self.get_result(&url, symbol).await?;
+        parse_order_book(&result, symbol)
+    }
+
+    /// 只提供最近的成交，不支持按游标翻页
+    async fn fetch_trades(&self, query: &TradeQuery) -> Result<Vec<PublicTrade>, MarketDataError> {
+        if query.before.is_some() {
+            return Err(MarketDataError::Unsupported {
+                exchange: NAME,
+                operation: "成交记录翻页",
+            });
+        }
+
+        let url = format!(
+            "{}/v5/market/recent-trade?category=spot&symbol={}&limit={}",
+            BASE_URL,
+            query.symbol.bybit(),
+            query.limit.clamp(1, MAX_TRADES)
+        );
+
+        let result = self.get_result(&url, &query.symbol).await?;
+        parse_trades(&result, &query.symbol)
+    }
+
+    async fn fetch_instruments(&self) -> Result<Vec<Instrument>, MarketDataError> {
+        let url = format!("{}/v5/market/instruments-info?category=spot", BASE_URL);
+
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        let instruments = parse_instruments(Self::unwrap_result(&data)?)?;
+
+        println!("✅ Bybit获取 {} 个现货交易对", instruments.len());
+        Ok(instruments)
+    }
+}
+
+/// Bybit K线格式: [startTime, open, high, low, close, volume, turnover]，按时间倒序返回
+fn parse_klines(result: &Value) -> Result<Vec<KlineData>, MarketDataError> {
+    let rows = result["list"]
+        .as_array()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?;
+
+    rows.iter()
+        .rev()
+        .map(|row| {
+            let candle = row
+                .as_array()
+                .filter(|c| c.len() >= 6)
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?;
+            Ok(KlineData {
+                timestamp: str_i64(&candle[0], NAME, "时间戳")?,
+                open: str_decimal(&candle[1], NAME, "开盘价")?,
+                high: str_decimal(&candle[2], NAME, "最高价")?,
+                low: str_decimal(&candle[3], NAME, "最低价")?,
+                close: str_decimal(&candle[4], NAME, "收盘价")?,
+                volume: str_decimal(&candle[5], NAME, "成交量")?,
+                source: NAME.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `price24hPcnt` 为小数形式的涨跌幅（0.01 表示 1%）
+fn parse_ticker(result: &Value, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+    let ticker = result["list"]
+        .as_array()
+        .and_then(|list| list.first())
+        .ok_or_else(|| MarketDataError::unknown_symbol(NAME, symbol))?;
+
+    let price = str_decimal(&ticker["lastPrice"], NAME, "最新价")?;
+    let prev_price = opt_str_decimal(&ticker["prevPrice24h"]);
+
+    Ok(Ticker {
+        symbol: symbol.to_string(),
+        price,
+        price_change_24h: prev_price.map(|prev| price - prev),
+        price_change_percent_24h: opt_str_decimal(&ticker["price24hPcnt"])
+            .map(|p| p * Decimal::ONE_HUNDRED),
+        high_24h: opt_str_decimal(&ticker["highPrice24h"]),
+        low_24h: opt_str_decimal(&ticker["lowPrice24h"]),
+        volume_24h: opt_str_decimal(&ticker["volume24h"]),
+        quote_volume_24h: opt_str_decimal(&ticker["turnover24h"]),
+        market_cap: None,
+        // 行情列表不带时间，使用接收时间
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        source: NAME.to_string(),
+    })
+}
+
+fn parse_order_book(result: &Value, symbol: &Symbol) -> Result<OrderBook, MarketDataError> {
+    Ok(OrderBook {
+        symbol: symbol.to_string(),
+        bids: parse_levels(&result["b"], NAME)?,
+        asks: parse_levels(&result["a"], NAME)?,
+        timestamp: result["ts"]
+            .as_i64()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无效时间戳"))?,
+        source: NAME.to_string(),
+    })
+}
+
+/// 按时间从新到旧返回
+fn parse_trades(result: &Value, symbol: &Symbol) -> Result<Vec<PublicTrade>, MarketDataError> {
+    result["list"]
+        .as_array()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无效的成交格式"))?
+        .iter()
+        .map(|trade| {
+            Ok(PublicTrade {
+                id: trade["execId"]
+                    .as_str()
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效成交ID"))?
+                    .to_string(),
+                symbol: symbol.to_string(),
+                side: match trade["side"].as_str() {
+                    Some("Sell") => TradeSide::Sell,
+                    _ => TradeSide::Buy,
+                },
+                price: str_decimal(&trade["price"], NAME, "成交价")?,
+                amount: str_decimal(&trade["size"], NAME, "成交量")?,
+                timestamp: str_i64(&trade["time"], NAME, "时间戳")?,
+                source: NAME.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_instruments(result: &Value) -> Result<Vec<Instrument>, MarketDataError> {
+    let list = result["list"]
+        .as_array()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无交易对数据"))?;
+
+    Ok(list
+        .iter()
+        .filter_map(|info| {
+            let base = info["baseCoin"].as_str()?;
+            let quote = info["quoteCoin"].as_str()?;
+            Some(Instrument {
+                symbol: Symbol::new(base, quote).to_string(),
+                base: base.to_string(),
+                quote: quote.to_string(),
+                exchange: NAME.to_string(),
+                exchange_symbol: info["symbol"].as_str()?.to_string(),
+                tick_size: opt_str_decimal(&info["priceFilter"]["tickSize"]),
+                lot_size: opt_str_decimal(&info["lotSizeFilter"]["basePrecision"]),
+                min_size: opt_str_decimal(&info["lotSizeFilter"]["minOrderQty"]),
+                status: match info["status"].as_str() {
+                    Some("Trading") => InstrumentStatus::Trading,
+                    Some("PreLaunch") => InstrumentStatus::PreTrading,
+                    Some("Closed") => InstrumentStatus::Delisted,
+                    _ => InstrumentStatus::Halted,
+                },
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::{dec, fixture};
+
+    fn btc_usdt() -> Symbol {
+        Symbol::new("BTC", "USDT")
+    }
+
+    #[test]
+    fn parses_klines_in_ascending_order() {
+        let data = fixture("bybit", "kline");
+        let klines = parse_klines(BybitSource::unwrap_result(&data).unwrap()).unwrap();
+
+        assert_eq!(klines.len(), 3);
+        assert!(klines.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        let first = &klines[0];
+        assert_eq!(first.timestamp, 1_717_020_000_000);
+        assert_eq!(first.open, dec("67820.01"));
+        assert_eq!(first.high, dec("67901.5"));
+        assert_eq!(first.low, dec("67700.0"));
+        assert_eq!(first.close, dec("67855.3"));
+        assert_eq!(first.volume, dec("112.532"));
+        assert_eq!(first.source, "bybit");
+    }
+
+    #[test]
+    fn parses_ticker_with_percent_change() {
+        let data = fixture("bybit", "ticker");
+        let ticker = parse_ticker(BybitSource::unwrap_result(&data).unwrap(), &btc_usdt()).unwrap();
+
+        assert_eq!(ticker.symbol, "BTC/USDT");
+        assert_eq!(ticker.price, dec("68012.5"));
+        assert_eq!(ticker.price_change_percent_24h, Some(dec("1.25")));
+        assert_eq!(ticker.price_change_24h, Some(dec("840.3")));
+        assert_eq!(ticker.high_24h, Some(dec("68400.0")));
+        assert_eq!(ticker.low_24h, Some(dec("66950.1")));
+        assert_eq!(ticker.volume_24h, Some(dec("15234.876")));
+        assert_eq!(ticker.quote_volume_24h, Some(dec("1030876543.21")));
+    }
+
+    #[test]
+    fn parses_order_book_levels() {
+        let data = fixture("bybit", "orderbook");
+        let book =
+            parse_order_book(BybitSource::unwrap_result(&data).unwrap(), &btc_usdt()).unwrap();
+
+        assert_eq!(book.timestamp, 1_717_023_600_123);
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(book.asks.len(), 2);
+        assert_eq!(book.bids[0].price, dec("68012.4"));
+        assert_eq!(book.bids[0].amount, dec("0.512"));
+        assert_eq!(book.asks[0].price, dec("68012.5"));
+        assert!(book.bids[0].price > book.bids[1].price);
+        assert!(book.asks[0].price < book.asks[1].price);
+    }
+
+    #[test]
+    fn parses_trades_with_taker_side() {
+        let data = fixture("bybit", "trades");
+        let trades = parse_trades(BybitSource::unwrap_result(&data).unwrap(), &btc_usdt()).unwrap();
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].id, "2290000000061666327");
+        assert_eq!(trades[0].side, TradeSide::Buy);
+        assert_eq!(trades[0].price, dec("68012.5"));
+        assert_eq!(trades[0].amount, dec("0.0021"));
+        assert_eq!(trades[0].timestamp, 1_717_023_600_456);
+        assert_eq!(trades[1].side, TradeSide::Sell);
+    }
+
+    #[test]
+    fn parses_instruments_and_status() {
+        let data = fixture("bybit", "instruments");
+        let instruments = parse_instruments(BybitSource::unwrap_result(&data).unwrap()).unwrap();
+
+        assert_eq!(instruments.len(), 2);
+        let btc = &instruments[0];
+        assert_eq!(btc.symbol, "BTC/USDT");
+        assert_eq!(btc.exchange_symbol, "BTCUSDT");
+        assert_eq!(btc.tick_size, Some(dec("0.01")));
+        assert_eq!(btc.lot_size, Some(dec("0.000001")));
+        assert_eq!(btc.min_size, Some(dec("0.000048")));
+        assert!(btc.is_trading());
+        assert_eq!(instruments[1].status, InstrumentStatus::PreTrading);
+    }
+
+    #[test]
+    fn reports_api_errors() {
+        let data = fixture("bybit", "error");
+        let error = BybitSource::unwrap_result(&data).unwrap_err();
+        assert!(matches!(error, MarketDataError::Api { .. }));
+        assert!(error.to_string().contains("10001"));
+    }
+
+    #[test]
+    fn maps_intervals() {
+        assert_eq!(map_interval(NAME, INTERVALS, "1h").unwrap(), "60");
+        assert_eq!(map_interval(NAME, INTERVALS, "1d").unwrap(), "D");
+        assert_eq!(map_interval(NAME, INTERVALS, "1M").unwrap(), "M");
+        assert!(map_interval(NAME, INTERVALS, "8h").is_err());
+    }
+}
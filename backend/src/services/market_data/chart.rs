@@ -0,0 +1,625 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::interval::{Interval, IntervalUnit};
+use super::KlineData;
+
+/// 衍生图表的锚点周期候选：UTC 自然日、周、月、年
+const ANCHOR_PERIODS: [Interval; 4] = [
+    Interval {
+        count: 1,
+        unit: IntervalUnit::Day,
+    },
+    Interval {
+        count: 1,
+        unit: IntervalUnit::Week,
+    },
+    Interval {
+        count: 1,
+        unit: IntervalUnit::Month,
+    },
+    Interval {
+        count: 12,
+        unit: IntervalUnit::Month,
+    },
+];
+/// 锚点周期至少包含的基础K线数量，不足时使用更长的候选周期（最长为一年）
+const MIN_ANCHOR_CANDLES: i64 = 1000;
+/// Renko 砖块和区间K线的价格范围相对首根K线收盘价的最小比例
+const MIN_BOX_FRACTION: Decimal = Decimal::from_parts(1, 0, 0, false, 6);
+/// Renko 按 ATR 计算砖块大小时的默认周期
+pub const DEFAULT_ATR_PERIOD: u32 = 14;
+/// ATR 砖块大小保留的有效数字位数，避免数据微小修订改变价格网格
+const ATR_SIGNIFICANT_DIGITS: u32 = 2;
+/// 平均K线价格保留的小数位数
+const HEIKIN_ASHI_SCALE: u32 = 12;
+
+/// K线图表类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartType {
+    /// 普通K线
+    #[default]
+    Candles,
+    /// 平均K线（Heikin-Ashi）
+    HeikinAshi,
+    /// 砖形图，价格每移动一个砖块大小生成一块砖，反转需要两个砖块
+    Renko,
+    /// 区间K线，每根K线最高价与最低价之差等于 `box_size`
+    Range,
+    /// 成交量K线，累计成交量每达到 `box_size` 生成一根
+    Volume,
+}
+
+impl ChartType {
+    pub fn is_derived(&self) -> bool {
+        *self != Self::Candles
+    }
+}
+
+/// 衍生图表参数
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChartOptions {
+    pub chart_type: ChartType,
+    /// Renko 砖块大小、区间K线的价格范围或成交量K线的成交量；Renko 为空时按 ATR 计算
+    pub box_size: Option<Decimal>,
+    /// Renko 按 ATR 计算砖块大小时的周期
+    pub atr_period: Option<u32>,
+}
+
+/// 衍生图表的一根K线
+///
+/// `timestamp` 为第一根参与计算的基础K线的开盘时间，Renko 和区间K线在同一根
+/// 基础K线内可能生成多根，此时时间相同。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChartBar {
+    #[serde(flatten)]
+    pub kline: KlineData,
+    /// 最后一根参与计算的基础K线的开盘时间
+    pub end_time: i64,
+}
+
+/// 衍生图表结果
+#[derive(Debug, Clone)]
+pub struct ChartSeries {
+    pub bars: Vec<ChartBar>,
+    /// 实际使用的砖块大小（Renko 按 ATR 计算时为计算结果）
+    pub box_size: Option<Decimal>,
+}
+
+/// 基础周期为 `step` 毫秒时的锚点周期：包含至少 1000 根基础K线的最短 UTC 自然日、周、月或年
+pub fn anchor_period(step: i64) -> Interval {
+    ANCHOR_PERIODS
+        .into_iter()
+        .find(|period| period.approx_millis() / step.max(1) >= MIN_ANCHOR_CANDLES)
+        .unwrap_or(ANCHOR_PERIODS[ANCHOR_PERIODS.len() - 1])
+}
+
+/// 窗口起点所在锚点周期（见 [`anchor_period`]）的开始时间，衍生图表从这里开始计算
+///
+/// 锚点只取决于日历，起点落在同一锚点周期内的窗口得到相同的砖块和K线边界。
+pub fn anchor_time(window_start: i64, step: i64) -> i64 {
+    anchor_period(step).bucket_start(window_start)
+}
+
+/// 由升序排列的基础K线构建衍生图表，生成超过 `max_bars` 根时返回错误
+///
+/// 结果只取决于输入K线和参数：Renko 砖块对齐到砖块大小的整数倍，
+/// ATR 砖块大小由最早的 `atr_period` 根K线计算，成交量K线按从第一根K线起的累计成交量分段。
+pub fn build_chart(
+    klines: &[KlineData],
+    options: &ChartOptions,
+    max_bars: usize,
+) -> Result<ChartSeries, String> {
+    let positive = |size: Option<Decimal>| -> Result<Option<Decimal>, String> {
+        match size {
+            Some(size) if size <= Decimal::ZERO => Err("box_size 必须大于0".to_string()),
+            size => Ok(size),
+        }
+    };
+    let box_size = positive(options.box_size)?;
+
+    match options.chart_type {
+        ChartType::Candles => Ok(ChartSeries {
+            bars: klines.iter().map(single_bar).collect(),
+            box_size: None,
+        }),
+        ChartType::HeikinAshi => Ok(ChartSeries {
+            bars: heikin_ashi(klines),
+            box_size: None,
+        }),
+        ChartType::Renko => {
+            let size = match box_size {
+                Some(size) => size,
+                None => {
+                    let period = options.atr_period.unwrap_or(DEFAULT_ATR_PERIOD);
+                    if period == 0 {
+                        return Err("atr_period 必须大于0".to_string());
+                    }
+                    atr_box_size(klines, period as usize)
+                        .ok_or_else(|| "K线数据不足，无法按 ATR 计算砖块大小".to_string())?
+                }
+            };
+            check_price_box(klines, size)?;
+            Ok(ChartSeries {
+                bars: renko(klines, size, max_bars)?,
+                box_size: Some(size),
+            })
+        }
+        ChartType::Range => {
+            let size = box_size.ok_or_else(|| "区间K线需要指定 box_size".to_string())?;
+            check_price_box(klines, size)?;
+            Ok(ChartSeries {
+                bars: range_bars(klines, size, max_bars)?,
+                box_size: Some(size),
+            })
+        }
+        ChartType::Volume => {
+            let size = box_size.ok_or_else(|| "成交量K线需要指定 box_size".to_string())?;
+            check_volume_box(klines, size)?;
+            Ok(ChartSeries {
+                bars: volume_bars(klines, size)?,
+                box_size: Some(size),
+            })
+        }
+    }
+}
+
+/// 价格类砖块过小时生成的K线数量不可控，要求不小于首根K线收盘价的百万分之一
+fn check_price_box(klines: &[KlineData], size: Decimal) -> Result<(), String> {
+    let Some(first) = klines.first() else {
+        return Ok(());
+    };
+    let min = first.close * MIN_BOX_FRACTION;
+    if size < min {
+        return Err(format!("box_size 过小，不能小于 {}", min.normalize()));
+    }
+    Ok(())
+}
+
+/// 成交量K线的每根成交量同样要求不小于平均成交量的百万分之一
+fn check_volume_box(klines: &[KlineData], size: Decimal) -> Result<(), String> {
+    if klines.is_empty() {
+        return Ok(());
+    }
+    let total = klines
+        .iter()
+        .try_fold(Decimal::ZERO, |sum, k| sum.checked_add(k.volume))
+        .unwrap_or(Decimal::MAX);
+    let min = total / Decimal::from(klines.len()) * MIN_BOX_FRACTION;
+    if size < min {
+        return Err(format!("box_size 过小，不能小于 {}", min.normalize()));
+    }
+    Ok(())
+}
+
+fn too_many_bars(max_bars: usize) -> String {
+    format!(
+        "生成的K线超过 {} 根，请增大 box_size 或缩小时间范围",
+        max_bars
+    )
+}
+
+fn single_bar(kline: &KlineData) -> ChartBar {
+    ChartBar {
+        kline: kline.clone(),
+        end_time: kline.timestamp,
+    }
+}
+
+/// 平均K线：收盘价为开高低收均值，开盘价为上一根平均K线开收盘的中点
+fn heikin_ashi(klines: &[KlineData]) -> Vec<ChartBar> {
+    let mut bars: Vec<ChartBar> = Vec::with_capacity(klines.len());
+    for kline in klines {
+        let close = ((kline.open + kline.high + kline.low + kline.close) / Decimal::from(4))
+            .round_dp(HEIKIN_ASHI_SCALE);
+        let open = match bars.last() {
+            Some(prev) => (prev.kline.open + prev.kline.close) / Decimal::TWO,
+            None => (kline.open + kline.close) / Decimal::TWO,
+        }
+        .round_dp(HEIKIN_ASHI_SCALE);
+        bars.push(ChartBar {
+            kline: KlineData {
+                timestamp: kline.timestamp,
+                open,
+                high: kline.high.max(open).max(close),
+                low: kline.low.min(open).min(close),
+                close,
+                volume: kline.volume,
+                source: kline.source.clone(),
+            },
+            end_time: kline.timestamp,
+        });
+    }
+    bars
+}
+
+/// 最早 `period` 根K线的平均真实波幅，保留两位有效数字
+fn atr_box_size(klines: &[KlineData], period: usize) -> Option<Decimal> {
+    if klines.len() < period {
+        return None;
+    }
+    let total: Decimal = klines
+        .iter()
+        .take(period)
+        .enumerate()
+        .map(|(i, k)| {
+            let range = k.high - k.low;
+            match i.checked_sub(1).map(|prev| klines[prev].close) {
+                Some(prev_close) => range
+                    .max((k.high - prev_close).abs())
+                    .max((k.low - prev_close).abs()),
+                None => range,
+            }
+        })
+        .sum();
+    let atr = total / Decimal::from(period);
+    atr.round_sf(ATR_SIGNIFICANT_DIGITS)
+        .filter(|size| *size > Decimal::ZERO)
+}
+
+/// 按收盘价生成砖块，砖块边界为 `size` 的整数倍
+///
+/// 同方向延续需要收盘价越过上一块砖一个砖块大小，反转需要越过两个；
+/// 砖块的成交量为自上一块砖以来的基础K线成交量，同一根K线生成多块砖时计入第一块。
+fn renko(klines: &[KlineData], size: Decimal, max_bars: usize) -> Result<Vec<ChartBar>, String> {
+    let Some(first) = klines.first() else {
+        return Ok(Vec::new());
+    };
+    let level = (first.close / size).floor() * size;
+    let (mut top, mut bottom) = (level, level);
+    let mut bars = Vec::new();
+    let mut pending_volume = Decimal::ZERO;
+    // 下一块砖的开始时间，上一块砖所在K线之后的第一根K线
+    let mut pending_start: Option<i64> = None;
+
+    for kline in klines {
+        pending_volume += kline.volume;
+        let start = pending_start.get_or_insert(kline.timestamp);
+        let mut formed = false;
+        loop {
+            let (open, close) = if kline.close >= top + size {
+                (top, top + size)
+            } else if kline.close <= bottom - size {
+                (bottom, bottom - size)
+            } else {
+                break;
+            };
+            if bars.len() >= max_bars {
+                return Err(too_many_bars(max_bars));
+            }
+            (top, bottom) = (open.max(close), open.min(close));
+            bars.push(ChartBar {
+                kline: KlineData {
+                    timestamp: *start,
+                    open,
+                    high: top,
+                    low: bottom,
+                    close,
+                    volume: std::mem::take(&mut pending_volume),
+                    source: kline.source.clone(),
+                },
+                end_time: kline.timestamp,
+            });
+            *start = kline.timestamp;
+            formed = true;
+        }
+        if formed {
+            pending_start = None;
+        }
+    }
+    Ok(bars)
+}
+
+/// 区间K线：按K线内价格路径（阳线 开→低→高→收，阴线 开→高→低→收）推进，
+/// 范围达到 `size` 时在边界价收盘，下一根以同一价格开盘
+///
+/// 基础K线的成交量计入该K线开始时所在的区间K线；最后一根区间K线可能未走完。
+fn range_bars(
+    klines: &[KlineData],
+    size: Decimal,
+    max_bars: usize,
+) -> Result<Vec<ChartBar>, String> {
+    let Some(first) = klines.first() else {
+        return Ok(Vec::new());
+    };
+    let open_bar = |price: Decimal, kline: &KlineData| ChartBar {
+        kline: KlineData {
+            timestamp: kline.timestamp,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: Decimal::ZERO,
+            source: kline.source.clone(),
+        },
+        end_time: kline.timestamp,
+    };
+    let mut bars = Vec::new();
+    let mut bar = open_bar(first.open, first);
+
+    for kline in klines {
+        bar.kline.volume += kline.volume;
+        bar.end_time = kline.timestamp;
+        let path = if kline.close >= kline.open {
+            [kline.open, kline.low, kline.high, kline.close]
+        } else {
+            [kline.open, kline.high, kline.low, kline.close]
+        };
+        for price in path {
+            loop {
+                let limit = if price > bar.kline.high && price - bar.kline.low >= size {
+                    bar.kline.low + size
+                } else if price < bar.kline.low && bar.kline.high - price >= size {
+                    bar.kline.high - size
+                } else {
+                    bar.kline.high = bar.kline.high.max(price);
+                    bar.kline.low = bar.kline.low.min(price);
+                    break;
+                };
+                if bars.len() >= max_bars {
+                    return Err(too_many_bars(max_bars));
+                }
+                bar.kline.high = bar.kline.high.max(limit);
+                bar.kline.low = bar.kline.low.min(limit);
+                bar.kline.close = limit;
+                let next = open_bar(limit, kline);
+                bars.push(std::mem::replace(&mut bar, next));
+            }
+        }
+        bar.kline.close = kline.close;
+    }
+    bars.push(bar);
+    Ok(bars)
+}
+
+/// 成交量K线：从第一根K线起累计成交量，每越过 `size` 的整数倍结束一根
+///
+/// 以基础K线为最小单位，单根K线越过多个整数倍时只结束一根。
+fn volume_bars(klines: &[KlineData], size: Decimal) -> Result<Vec<ChartBar>, String> {
+    let mut bars: Vec<ChartBar> = Vec::new();
+    let mut current: Option<ChartBar> = None;
+    let mut cumulative = Decimal::ZERO;
+    let mut next_threshold = size;
+
+    for kline in klines {
+        match current.as_mut() {
+            Some(bar) => {
+                bar.kline.high = bar.kline.high.max(kline.high);
+                bar.kline.low = bar.kline.low.min(kline.low);
+                bar.kline.close = kline.close;
+                bar.kline.volume += kline.volume;
+                bar.end_time = kline.timestamp;
+            }
+            None => current = Some(single_bar(kline)),
+        }
+
+        cumulative += kline.volume;
+        if cumulative >= next_threshold {
+            next_threshold = cumulative
+                .checked_div(size)
+                .and_then(|ticks| (ticks.floor() + Decimal::ONE).checked_mul(size))
+                .ok_or_else(|| "box_size 相对成交量过小".to_string())?;
+            bars.extend(current.take());
+        }
+    }
+    bars.extend(current);
+    Ok(bars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::{dec, kline};
+
+    const MINUTE_MS: i64 = 60_000;
+    const MAX_BARS: usize = 1000;
+
+    fn ohlc(bar: &ChartBar) -> [Decimal; 4] {
+        [
+            bar.kline.open,
+            bar.kline.high,
+            bar.kline.low,
+            bar.kline.close,
+        ]
+    }
+
+    #[test]
+    fn renko_bricks_align_to_grid_and_need_two_boxes_to_reverse() {
+        let klines = vec![
+            kline(0, ["103", "104", "102", "103", "1"]),
+            kline(MINUTE_MS, ["103", "126", "103", "125", "2"]),
+            kline(2 * MINUTE_MS, ["125", "125", "108", "109", "3"]),
+            kline(3 * MINUTE_MS, ["109", "110", "98", "99", "4"]),
+        ];
+        let options = ChartOptions {
+            chart_type: ChartType::Renko,
+            box_size: Some(dec("10")),
+            atr_period: None,
+        };
+
+        let bars = build_chart(&klines, &options, MAX_BARS).unwrap().bars;
+
+        // 100 → 110 → 120，回落到 109 不足两块砖，跌到 99 时反转为 [100, 110]
+        let prices: Vec<_> = bars.iter().map(ohlc).collect();
+        assert_eq!(
+            prices,
+            vec![
+                [dec("100"), dec("110"), dec("100"), dec("110")],
+                [dec("110"), dec("120"), dec("110"), dec("120")],
+                [dec("110"), dec("110"), dec("100"), dec("100")],
+            ]
+        );
+        assert_eq!(bars[0].kline.volume, dec("3"));
+        assert_eq!(bars[1].kline.volume, Decimal::ZERO);
+        assert_eq!(bars[2].kline.volume, dec("7"));
+        assert_eq!(bars[2].kline.timestamp, 2 * MINUTE_MS);
+        assert_eq!(bars[2].end_time, 3 * MINUTE_MS);
+    }
+
+    #[test]
+    fn renko_atr_box_size_uses_earliest_candles() {
+        let klines: Vec<_> = (0..20)
+            .map(|i| kline(i * MINUTE_MS, ["100", "101.5", "100", "101", "1"]))
+            .collect();
+        let options = ChartOptions {
+            chart_type: ChartType::Renko,
+            box_size: None,
+            atr_period: Some(3),
+        };
+
+        let series = build_chart(&klines, &options, MAX_BARS).unwrap();
+
+        assert_eq!(series.box_size, Some(dec("1.5")));
+    }
+
+    #[test]
+    fn range_bars_split_at_exact_range() {
+        let klines = vec![
+            kline(0, ["100", "104", "99", "103", "4"]),
+            kline(MINUTE_MS, ["103", "103", "101", "102", "2"]),
+        ];
+        let options = ChartOptions {
+            chart_type: ChartType::Range,
+            box_size: Some(dec("2")),
+            atr_period: None,
+        };
+
+        let bars = build_chart(&klines, &options, MAX_BARS).unwrap().bars;
+
+        // 阳线路径 100 → 99 → 104 → 103：[99, 101] 收于 101，[101, 103] 收于 103，
+        // 阴线回落到 101 时 [102, 104] 收于 102，最后一根未走完
+        let prices: Vec<_> = bars.iter().map(ohlc).collect();
+        assert_eq!(
+            prices,
+            vec![
+                [dec("100"), dec("101"), dec("99"), dec("101")],
+                [dec("101"), dec("103"), dec("101"), dec("103")],
+                [dec("103"), dec("104"), dec("102"), dec("102")],
+                [dec("102"), dec("102"), dec("101"), dec("102")],
+            ]
+        );
+        assert!(bars[..3]
+            .iter()
+            .all(|b| b.kline.high - b.kline.low == dec("2")));
+        assert_eq!(bars[0].kline.volume, dec("4"));
+        assert_eq!(bars[2].kline.volume, dec("2"));
+    }
+
+    #[test]
+    fn volume_bars_close_on_cumulative_thresholds() {
+        let klines = vec![
+            kline(0, ["100", "101", "99", "100", "4"]),
+            kline(MINUTE_MS, ["100", "102", "100", "101", "7"]),
+            kline(2 * MINUTE_MS, ["101", "101", "98", "99", "3"]),
+            kline(3 * MINUTE_MS, ["99", "100", "97", "98", "1"]),
+        ];
+        let options = ChartOptions {
+            chart_type: ChartType::Volume,
+            box_size: Some(dec("10")),
+            atr_period: None,
+        };
+
+        let bars = build_chart(&klines, &options, MAX_BARS).unwrap().bars;
+
+        assert_eq!(bars.len(), 2);
+        assert_eq!(
+            ohlc(&bars[0]),
+            [dec("100"), dec("102"), dec("99"), dec("101")]
+        );
+        assert_eq!(bars[0].kline.volume, dec("11"));
+        assert_eq!(bars[0].end_time, MINUTE_MS);
+        assert_eq!(bars[1].kline.timestamp, 2 * MINUTE_MS);
+        assert_eq!(bars[1].kline.volume, dec("4"));
+    }
+
+    #[test]
+    fn rejects_volume_box_too_small_for_average_volume() {
+        let klines = vec![
+            kline(0, ["100", "101", "99", "100", "4"]),
+            kline(MINUTE_MS, ["100", "102", "100", "101", "8"]),
+        ];
+        let options = |size: &str| ChartOptions {
+            chart_type: ChartType::Volume,
+            box_size: Some(dec(size)),
+            atr_period: None,
+        };
+
+        assert!(build_chart(&klines, &options("0.000006"), MAX_BARS).is_ok());
+        assert!(build_chart(&klines, &options("0.000005"), MAX_BARS).is_err());
+        assert!(build_chart(
+            &klines,
+            &options("0.0000000000000000000000000001"),
+            MAX_BARS
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn heikin_ashi_uses_previous_midpoint() {
+        let klines = vec![
+            kline(0, ["100", "110", "90", "104", "1"]),
+            kline(MINUTE_MS, ["104", "108", "100", "106", "1"]),
+        ];
+        let options = ChartOptions {
+            chart_type: ChartType::HeikinAshi,
+            ..Default::default()
+        };
+
+        let bars = build_chart(&klines, &options, MAX_BARS).unwrap().bars;
+
+        assert_eq!(
+            ohlc(&bars[0]),
+            [dec("102"), dec("110"), dec("90"), dec("101")]
+        );
+        assert_eq!(
+            ohlc(&bars[1]),
+            [dec("101.5"), dec("108"), dec("100"), dec("104.5")]
+        );
+    }
+
+    #[test]
+    fn anchors_to_calendar_periods_independent_of_window() {
+        // 2024-05-15 13:37 UTC（周三）
+        let window_start = 1_715_780_220_000;
+        let day = 1_715_731_200_000;
+        let monday = 1_715_558_400_000;
+        let may = 1_714_521_600_000;
+        let year = 1_704_067_200_000;
+
+        assert_eq!(anchor_time(window_start, MINUTE_MS), day);
+        assert_eq!(anchor_time(window_start, 5 * MINUTE_MS), monday);
+        assert_eq!(anchor_time(window_start, 15 * MINUTE_MS), may);
+        assert_eq!(anchor_time(window_start, 60 * MINUTE_MS), year);
+        assert_eq!(anchor_time(window_start, 1440 * MINUTE_MS), year);
+        // 窗口在锚点周期内移动时锚点不变
+        assert_eq!(anchor_time(may + 40 * MINUTE_MS, 15 * MINUTE_MS), may);
+    }
+
+    #[test]
+    fn rejects_tiny_boxes_and_caps_bar_count() {
+        let klines = vec![
+            kline(0, ["100", "101", "99", "100", "1"]),
+            kline(MINUTE_MS, ["100", "200", "100", "200", "1"]),
+        ];
+        let mut options = ChartOptions {
+            chart_type: ChartType::Renko,
+            box_size: Some(dec("0.00001")),
+            atr_period: None,
+        };
+        assert!(build_chart(&klines, &options, MAX_BARS)
+            .unwrap_err()
+            .contains("box_size 过小"));
+
+        // 100 → 200 需要 1000 块砖，超过上限
+        options.box_size = Some(dec("0.1"));
+        assert!(build_chart(&klines, &options, 999).is_err());
+        assert_eq!(
+            build_chart(&klines, &options, 1000).unwrap().bars.len(),
+            1000
+        );
+
+        options.chart_type = ChartType::Range;
+        assert!(build_chart(&klines, &options, 999).is_err());
+    }
+}
@@ -0,0 +1,34 @@
+/// 行情服务使用的当前时间
+///
+/// 默认取系统时间；设置 `MARKET_CLOCK_NOW`（毫秒时间戳）后固定为该时间。录制和回放上游响应时
+/// 固定时间，默认区间、K线是否收盘、近期接口的选择等依赖当前时间的请求地址保持不变。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Clock {
+    #[default]
+    System,
+    Fixed(i64),
+}
+
+impl Clock {
+    pub fn from_env() -> Self {
+        match std::env::var("MARKET_CLOCK_NOW").map(|s| s.parse::<i64>()) {
+            Ok(Ok(now)) => {
+                println!("🕒 行情服务时间固定为 {}", now);
+                Self::Fixed(now)
+            }
+            Ok(Err(_)) => {
+                println!("⚠️ MARKET_CLOCK_NOW 不是有效的毫秒时间戳，使用系统时间");
+                Self::System
+            }
+            Err(_) => Self::System,
+        }
+    }
+
+    /// 当前时间（毫秒时间戳）
+    pub fn now_millis(&self) -> i64 {
+        match self {
+            Self::System => chrono::Utc::now().timestamp_millis(),
+            Self::Fixed(now) => *now,
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::time::Duration;
+
+use super::decimal::percent_change;
+use super::http::{
+    build_client, get_json, num_decimal, opt_str_decimal, parse_levels, str_decimal, HttpClient,
+};
+use super::interval::map_interval;
+use super::rate_limit::{BucketSpec, RateLimitRules, RateLimiter, RequestCost};
+use super::replay::HttpMode;
+use super::{
+    Instrument, InstrumentStatus, KlineData, KlineQuery, MarketDataError, MarketDataSource,
+    OrderBook, PublicTrade, SourceCapabilities, Symbol, Ticker, TradeQuery, TradeSide,
+};
+
+const NAME: &str = "coinbase";
+const BASE_URL: &str = "https://api.exchange.coinbase.com";
+/// 标准周期 -> Coinbase granularity（秒），只支持这六种
+const INTERVALS: &[(&str, &str)] = &[
+    ("1m", "60"),
+    ("5m", "300"),
+    ("15m", "900"),
+    ("1h", "3600"),
+    ("6h", "21600"),
+    ("1d", "86400"),
+];
+/// `/products/{id}/candles` 单次最多返回300根
+const MAX_LIMIT: u32 = 300;
+/// `/products/{id}/trades` 单次最多返回1000条
+const MAX_TRADES: u32 = 1000;
+
+/// 公共接口每个IP每秒10次，允许突发15次
+static RATE_LIMITS: RateLimitRules = RateLimitRules {
+    buckets: &[BucketSpec::new("public", 15, Duration::from_millis(1500))],
+    cost: |_| RequestCost::new("public", 1),
+    used_weight_headers: &[],
+};
+
+pub struct CoinbaseSource {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl CoinbaseSource {
+    pub fn new(mode: &HttpMode) -> Self {
+        Self {
+            client: build_client(NAME, mode),
+            limiter: RateLimiter::new(NAME, &RATE_LIMITS),
+        }
+    }
+
+    /// 不存在的 product id 返回 404 NotFound
+    fn symbol_error(error: MarketDataError, symbol: &Symbol) -> MarketDataError {
+        match &error {
+            MarketDataError::Status { status, .. } if status.as_u16() == 404 => {
+                MarketDataError::unknown_symbol(NAME, symbol)
+            }
+            _ => error,
+        }
+    }
+
+    async fn get(&self, url: &str, symbol: &Symbol) -> Result<Value, MarketDataError> {
+        get_json(&self.client, &self.limiter, url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))
+    }
+}
+
+impl Default for CoinbaseSource {
+    fn default() -> Self {
+        Self::new(&HttpMode::Live)
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for CoinbaseSource {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            klines: true,
+            ticker: true,
+            order_book: true,
+            trades: true,
+            native_ohlc: true,
+            base_volume: true,
+            max_klines_per_request: MAX_LIMIT,
+            history_page_size: Some(MAX_LIMIT),
+            perpetuals: false,
+        }
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        Some(&self.limiter)
+    }
+
+    fn supported_intervals(&self) -> Vec<&'static str> {
+        INTERVALS.iter().map(|(standard, _)| *standard).collect()
+    }
+
+    /// 接口没有 limit 参数，按区间请求后截取；只指定一端时按 `limit` 推算另一端
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError> {
+        let granularity = map_interval(NAME, INTERVALS, &query.interval)?;
+        let step_ms = granularity.parse::<i64>().unwrap_or(60) * 1000;
+        let limit = query.limit.clamp(1, MAX_LIMIT);
+        let span = step_ms * i64::from(limit - 1);
+
+        let range = match (query.start_time, query.end_time) {
+            (Some(start), Some(end)) => Some((start, end)),
+            (Some(start), None) => Some((start, start + span)),
+            (None, Some(end)) => Some((end - span, end)),
+            (None, None) => None,
+        };
+
+        let mut url = format!(
+            "{}/products/{}/candles?granularity={}",
+            BASE_URL,
+            query.symbol.coinbase(),
+            granularity
+        );
+        if let Some((start, end)) = range {
+            url.push_str(&format!(
+                "&start={}&end={}",
+                format_time(start),
+                format_time(end)
+            ));
+        }
+
+        println!("🔵 Coinbase请求: {}", url);
+
+        let data = self.get(&url, &query.symbol).await?;
+        let mut klines = parse_candles(&data)?;
+        if let Some((start, end)) = range {
+            klines.retain(|k| k.timestamp >= start && k.timestamp <= end);
+        }
+        if query.start_time.is_none() {
+            let skip = klines.len().saturating_sub(limit as usize);
+            klines.drain(..skip);
+        } else {
+            klines.truncate(limit as usize);
+        }
+
+        println!("✅ Coinbase成功获取 {} 个K线数据点", klines.len());
+        Ok(klines)
+    }
+
+    /// 最新价取自 ticker，24小时统计取自 stats
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        let product_id = symbol.coinbase();
+        let ticker_url = format!("{}/products/{}/ticker", BASE_URL, product_id);
+        let stats_url = format!("{}/products/{}/stats", BASE_URL, product_id);
+
+        let (ticker, stats) =
+            futures_util::try_join!(self.get(&ticker_url, symbol), self.get(&stats_url, symbol))?;
+        parse_ticker(&ticker, &stats, symbol)
+    }
+
+    /// level=2 返回按价位聚合的完整订单簿，按请求档数截取
+    async fn fetch_order_book(
+        &self,
+        symbol: &Symbol,
+        depth: u32,
+    ) -> Result<OrderBook, MarketDataError> {
+        let url = format!("{}/products/{}/book?level=2", BASE_URL, symbol.coinbase());
+
+        let data = self.get(&url, symbol).await?;
+        let mut book = parse_order_book(&data, symbol)?;
+        book.bids.truncate(depth.max(1) as usize);
+        book.asks.truncate(depth.max(1) as usize);
+        Ok(book)
+    }
+
+    /// `after` 返回早于该成交ID的记录
+    async fn fetch_trades(&self, query: &TradeQuery) -> Result<Vec<PublicTrade>, MarketDataError> {
+        let mut url = format!(
+            "{}/products/{}/trades?limit={}",
+            BASE_URL,
+            query.symbol.coinbase(),
+            query.limit.clamp(1, MAX_TRADES)
+        );
+        if let Some(before) = &query.before {
+            before
+                .parse::<u64>()
+                .map_err(|_| MarketDataError::InvalidCursor(before.clone()))?;
+            url.push_str(&format!("&after={}", before));
+        }
+
+        let data = self.get(&url, &query.symbol).await?;
+        parse_trades(&data, &query.symbol)
+    }
+
+    async fn fetch_instruments(&self) -> Result<Vec<Instrument>, MarketDataError> {
+        let url = format!("{}/products", BASE_URL);
+
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        let instruments = parse_instruments(&data)?;
+
+        println!("✅ Coinbase获取 {} 个现货交易对", instruments.len());
+        Ok(instruments)
+    }
+}
+
+fn format_time(timestamp_ms: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(timestamp_ms)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+/// 解析 ISO 8601 时间为毫秒时间戳
+fn parse_time(value: &Value) -> Result<i64, MarketDataError> {
+    value
+        .as_str()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.timestamp_millis())
+        .ok_or_else(|| MarketDataError::parse(NAME, "无效时间戳"))
+}
+
+/// Coinbase K线格式: [time(秒), low, high, open, close, volume]，数值为数字，按时间倒序返回
+fn parse_candles(data: &Value) -> Result<Vec<KlineData>, MarketDataError> {
+    let rows = data
+        .as_array()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?;
+
+    let number = |value: &Value, field: &str| num_decimal(value, NAME, field);
+
+    rows.iter()
+        .rev()
+        .map(|row| {
+            let candle = row
+                .as_array()
+                .filter(|c| c.len() >= 6)
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?;
+            Ok(KlineData {
+                timestamp: candle[0]
+                    .as_i64()
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效时间戳"))?
+                    * 1000,
+                open: number(&candle[3], "开盘价")?,
+                high: number(&candle[2], "最高价")?,
+                low: number(&candle[1], "最低价")?,
+                close: number(&candle[4], "收盘价")?,
+                volume: number(&candle[5], "成交量")?,
+                source: NAME.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_ticker(ticker: &Value, stats: &Value, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+    let price = str_decimal(&ticker["price"], NAME, "最新价")?;
+    let open_24h = opt_str_decimal(&stats["open"]);
+
+    Ok(Ticker {
+        symbol: symbol.to_string(),
+        price,
+        price_change_24h: open_24h.map(|open| price - open),
+        price_change_percent_24h: open_24h.and_then(|open| percent_change(price, open)),
+        high_24h: opt_str_decimal(&stats["high"]),
+        low_24h: opt_str_decimal(&stats["low"]),
+        volume_24h: opt_str_decimal(&stats["volume"]),
+        quote_volume_24h: None,
+        market_cap: None,
+        timestamp: parse_time(&ticker["time"])?,
+        source: NAME.to_string(),
+    })
+}
+
+/// 档位格式: [price, size, num_orders]
+fn parse_order_book(data: &Value, symbol: &Symbol) -> Result<OrderBook, MarketDataError> {
+    Ok(OrderBook {
+        symbol: symbol.to_string(),
+        bids: parse_levels(&data["bids"], NAME)?,
+        asks: parse_levels(&data["asks"], NAME)?,
+        timestamp: parse_time(&data["time"]).unwrap_or_else(|_| Utc::now().timestamp_millis()),
+        source: NAME.to_string(),
+    })
+}
+
+/// 按时间从新到旧返回；`side` 为挂单方方向，主动成交方向与之相反
+fn parse_trades(data: &Value, symbol: &Symbol) -> Result<Vec<PublicTrade>, MarketDataError> {
+    data.as_array()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无效的成交格式"))?
+        .iter()
+        .map(|trade| {
+            Ok(PublicTrade {
+                id: trade["trade_id"]
+                    .as_u64()
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效成交ID"))?
+                    .to_string(),
+                symbol: symbol.to_string(),
+                side: match trade["side"].as_str() {
+                    Some("buy") => TradeSide::Sell,
+                    _ => TradeSide::Buy,
+                },
+                price: str_decimal(&trade["price"], NAME, "成交价")?,
+                amount: str_decimal(&trade["size"], NAME, "成交量")?,
+                timestamp: parse_time(&trade["time"])?,
+                source: NAME.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_instruments(data: &Value) -> Result<Vec<Instrument>, MarketDataError> {
+    let products = data
+        .as_array()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无交易对数据"))?;
+
+    Ok(products
+        .iter()
+        .filter_map(|product| {
+            let base = product["base_currency"].as_str()?;
+            let quote = product["quote_currency"].as_str()?;
+            Some(Instrument {
+                symbol: Symbol::new(base, quote).to_string(),
+                base: base.to_string(),
+                quote: quote.to_string(),
+                exchange: NAME.to_string(),
+                exchange_symbol: product["id"].as_str()?.to_string(),
+                tick_size: opt_str_decimal(&product["quote_increment"]),
+                lot_size: opt_str_decimal(&product["base_increment"]),
+                min_size: opt_str_decimal(&product["base_min_size"]),
+                status: match product["status"].as_str() {
+                    Some("delisted") => InstrumentStatus::Delisted,
+                    Some("online") if product["trading_disabled"] != Value::Bool(true) => {
+                        InstrumentStatus::Trading
+                    }
+                    _ => InstrumentStatus::Halted,
+                },
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::{dec, fixture};
+
+    fn btc_usd() -> Symbol {
+        Symbol::new("BTC", "USD")
+    }
+
+    #[test]
+    fn parses_candles_reordering_ohlc_columns() {
+        let klines = parse_candles(&fixture("coinbase", "candles")).unwrap();
+
+        assert_eq!(klines.len(), 3);
+        assert!(klines.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        let first = &klines[0];
+        assert_eq!(first.timestamp, 1_717_020_000_000);
+        assert_eq!(first.open, dec("67830.12"));
+        assert_eq!(first.high, dec("67910.0"));
+        assert_eq!(first.low, dec("67702.5"));
+        assert_eq!(first.close, dec("67861.04"));
+        assert_eq!(first.volume, dec("245.8731"));
+        assert_eq!(first.source, "coinbase");
+    }
+
+    #[test]
+    fn parses_ticker_from_ticker_and_stats() {
+        let ticker = parse_ticker(
+            &fixture("coinbase", "ticker"),
+            &fixture("coinbase", "stats"),
+            &btc_usd(),
+        )
+        .unwrap();
+
+        assert_eq!(ticker.symbol, "BTC/USD");
+        assert_eq!(ticker.price, dec("68015.0"));
+        assert_eq!(ticker.price_change_24h, Some(dec("1015")));
+        let percent = ticker.price_change_percent_24h.unwrap();
+        assert!((percent - dec("1.514925373")).abs() < dec("0.000001"));
+        assert_eq!(ticker.high_24h, Some(dec("68420.0")));
+        assert_eq!(ticker.low_24h, Some(dec("66900.0")));
+        assert_eq!(ticker.volume_24h, Some(dec("9876.54321")));
+        assert_eq!(ticker.timestamp, 1_717_023_600_123);
+    }
+
+    #[test]
+    fn parses_order_book_levels() {
+        let book = parse_order_book(&fixture("coinbase", "book"), &btc_usd()).unwrap();
+
+        assert_eq!(book.timestamp, 1_717_023_600_456);
+        assert_eq!(book.bids.len(), 3);
+        assert_eq!(book.asks.len(), 2);
+        assert_eq!(book.bids[0].price, dec("68014.99"));
+        assert_eq!(book.bids[0].amount, dec("0.75"));
+        assert_eq!(book.asks[0].price, dec("68015.0"));
+    }
+
+    #[test]
+    fn parses_trades_inverting_maker_side() {
+        let trades = parse_trades(&fixture("coinbase", "trades"), &btc_usd()).unwrap();
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].id, "651294011");
+        // 挂单方为卖单，主动成交方为买方
+        assert_eq!(trades[0].side, TradeSide::Buy);
+        assert_eq!(trades[1].side, TradeSide::Sell);
+        assert_eq!(trades[0].price, dec("68015.0"));
+        assert_eq!(trades[0].amount, dec("0.00314"));
+        assert_eq!(trades[0].timestamp, 1_717_023_600_789);
+    }
+
+    #[test]
+    fn parses_instruments_and_status() {
+        let instruments = parse_instruments(&fixture("coinbase", "products")).unwrap();
+
+        assert_eq!(instruments.len(), 3);
+        let btc = &instruments[0];
+        assert_eq!(btc.symbol, "BTC/USD");
+        assert_eq!(btc.exchange_symbol, "BTC-USD");
+        assert_eq!(btc.tick_size, Some(dec("0.01")));
+        assert_eq!(btc.lot_size, Some(dec("0.00000001")));
+        assert!(btc.is_trading());
+        assert_eq!(instruments[1].status, InstrumentStatus::Halted);
+        assert_eq!(instruments[2].status, InstrumentStatus::Delisted);
+    }
+
+    #[test]
+    fn maps_stablecoin_quotes_to_usd() {
+        assert_eq!(Symbol::new("BTC", "USDT").coinbase(), "BTC-USD");
+        assert_eq!(Symbol::new("ETH", "BTC").coinbase(), "ETH-BTC");
+        assert_eq!(map_interval(NAME, INTERVALS, "6h").unwrap(), "21600");
+        assert!(map_interval(NAME, INTERVALS, "4h").is_err());
+    }
+}
@@ -0,0 +1,321 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use super::decimal;
+use super::http::{build_client, get_json, num_decimal, HttpClient};
+use super::interval::map_interval;
+use super::rate_limit::{BucketSpec, RateLimitRules, RateLimiter, RequestCost};
+use super::replay::HttpMode;
+use super::{
+    GlobalMarketMetrics, KlineData, KlineQuery, MarketDataError, MarketDataSource,
+    SourceCapabilities, Symbol, Ticker,
+};
+
+const NAME: &str = "coingecko";
+const BASE_URL: &str = "https://api.coingecko.com";
+/// CoinGecko 只提供价格点，按小时聚合为K线
+const INTERVALS: &[(&str, &str)] = &[("1h", "hourly")];
+
+/// 免费接口按 Demo 套餐的每分钟30次限频（无密钥时实际限额可能更低）
+static RATE_LIMITS: RateLimitRules = RateLimitRules {
+    buckets: &[BucketSpec::new("public", 30, Duration::from_secs(60))],
+    cost: |_| RequestCost::new("public", 1),
+    used_weight_headers: &[],
+};
+
+pub struct CoinGeckoSource {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl CoinGeckoSource {
+    pub fn new(mode: &HttpMode) -> Self {
+        Self {
+            client: build_client(NAME, mode),
+            limiter: RateLimiter::new(NAME, &RATE_LIMITS),
+        }
+    }
+
+    /// 映射为 coin id 和 vs_currency，未收录的币种返回错误
+    fn map_coin(symbol: &Symbol) -> Result<(&'static str, String), MarketDataError> {
+        symbol
+            .coingecko()
+            .ok_or_else(|| MarketDataError::unknown_symbol(NAME, symbol))
+    }
+}
+
+impl Default for CoinGeckoSource {
+    fn default() -> Self {
+        Self::new(&HttpMode::Live)
+    }
+}
+
+/// 把 `(时间戳, 价格, 成交额)` 价格点按整点聚合为小时K线
+///
+/// 返回的价格点间隔随区间长度变化（1天以内约5分钟一个），按整点分桶：每根K线以桶内第一个价格开盘、
+/// 下一桶第一个价格收盘，高低价取桶内价格与收盘价的极值；最后一根K线以桶内最后一个价格收盘。
+/// 成交额为滚动24小时值，取桶内第一个点。
+fn hourly_klines(points: &[(i64, Decimal, Decimal)]) -> Vec<KlineData> {
+    const HOUR_MS: i64 = 3_600_000;
+
+    let mut buckets: Vec<(i64, Vec<Decimal>, Decimal)> = Vec::new();
+    for &(timestamp, price, volume) in points {
+        let start = timestamp - timestamp.rem_euclid(HOUR_MS);
+        match buckets.last_mut() {
+            Some((last, prices, _)) if *last == start => prices.push(price),
+            _ => buckets.push((start, vec![price], volume)),
+        }
+    }
+
+    buckets
+        .iter()
+        .enumerate()
+        .map(|(i, (timestamp, prices, volume))| {
+            let open = prices[0];
+            let close = buckets
+                .get(i + 1)
+                .map_or(prices[prices.len() - 1], |next| next.1[0]);
+            let (high, low) = prices
+                .iter()
+                .fold((close, close), |(high, low), &p| (high.max(p), low.min(p)));
+            KlineData {
+                timestamp: *timestamp,
+                open,
+                high,
+                low,
+                close,
+                volume: *volume,
+                source: NAME.to_string(),
+            }
+        })
+        .collect()
+}
+
+#[async_trait]
+impl MarketDataSource for CoinGeckoSource {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            klines: true,
+            ticker: true,
+            order_book: false,
+            trades: false,
+            native_ohlc: false,
+            // total_volumes 为美元成交额
+            base_volume: false,
+            max_klines_per_request: 24,
+            // market_chart/range 在1天以内返回5分钟价格点、90天以内按小时返回，均聚合为小时K线
+            history_page_size: Some(90 * 24),
+            perpetuals: false,
+        }
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        Some(&self.limiter)
+    }
+
+    fn supported_intervals(&self) -> Vec<&'static str> {
+        INTERVALS.iter().map(|(standard, _)| *standard).collect()
+    }
+
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError> {
+        map_interval(NAME, INTERVALS, &query.interval)?;
+
+        let (coin_id, vs_currency) = Self::map_coin(&query.symbol)?;
+        let url = match (query.start_time, query.end_time) {
+            (None, None) => format!(
+                "{}/api/v3/coins/{}/market_chart?vs_currency={}&days=1",
+                BASE_URL, coin_id, vs_currency
+            ),
+            (start_time, end_time) => {
+                let end_time = end_time.unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+                let start_time = start_time.unwrap_or(end_time - 86_400_000);
+                format!(
+                    "{}/api/v3/coins/{}/market_chart/range?vs_currency={}&from={}&to={}",
+                    BASE_URL,
+                    coin_id,
+                    vs_currency,
+                    start_time / 1000,
+                    end_time / 1000
+                )
+            }
+        };
+
+        println!("🟢 CoinGecko请求: {}", url);
+
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+
+        let prices = data["prices"]
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无价格数据"))?;
+        let volumes = data["total_volumes"]
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无成交量数据"))?;
+
+        let mut points = Vec::new();
+        for (i, price_point) in prices.iter().enumerate() {
+            let price_array = price_point
+                .as_array()
+                .filter(|p| p.len() >= 2)
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效价格格式"))?;
+            let timestamp = price_array[0]
+                .as_i64()
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效时间戳"))?;
+            let price = num_decimal(&price_array[1], NAME, "价格")?;
+
+            let volume = volumes
+                .get(i)
+                .and_then(|v| v.as_array())
+                .and_then(|v| v.get(1))
+                .and_then(decimal::from_json)
+                .unwrap_or_default();
+
+            points.push((timestamp, price, volume));
+        }
+
+        let klines = hourly_klines(&points);
+
+        println!("✅ CoinGecko成功获取 {} 个数据点", klines.len());
+        Ok(klines)
+    }
+
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        let (coin_id, vs_currency) = Self::map_coin(symbol)?;
+
+        let url = format!(
+            "{}/api/v3/simple/price?ids={}&vs_currencies={}&include_market_cap=true&include_24hr_vol=true&include_24hr_change=true&include_last_updated_at=true",
+            BASE_URL, coin_id, vs_currency
+        );
+
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        let coin = &data[coin_id];
+        let field = |suffix: &str| decimal::from_json(&coin[format!("{}{}", vs_currency, suffix)]);
+
+        let price = field("").ok_or_else(|| MarketDataError::parse(NAME, "无价格数据"))?;
+        let change_percent = field("_24h_change");
+
+        Ok(Ticker {
+            symbol: symbol.to_string(),
+            price,
+            // 由涨跌幅反推24小时涨跌额
+            price_change_24h: change_percent.and_then(|p| {
+                let base = price.checked_div(Decimal::ONE + p / Decimal::ONE_HUNDRED)?;
+                Some(price - base)
+            }),
+            price_change_percent_24h: change_percent,
+            high_24h: None,
+            low_24h: None,
+            volume_24h: None,
+            quote_volume_24h: field("_24h_vol"),
+            market_cap: field("_market_cap"),
+            timestamp: coin["last_updated_at"]
+                .as_i64()
+                .map(|ts| ts * 1000)
+                .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+            source: NAME.to_string(),
+        })
+    }
+
+    async fn fetch_market_caps(
+        &self,
+        symbols: &[Symbol],
+    ) -> Result<HashMap<Symbol, Decimal>, MarketDataError> {
+        let coins: Vec<(&Symbol, &'static str, String)> = symbols
+            .iter()
+            .filter_map(|symbol| {
+                let (coin_id, vs_currency) = Self::map_coin(symbol).ok()?;
+                Some((symbol, coin_id, vs_currency))
+            })
+            .collect();
+        if coins.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let ids: BTreeSet<&str> = coins.iter().map(|(_, id, _)| *id).collect();
+        let vs_currencies: BTreeSet<&str> = coins.iter().map(|(_, _, vs)| vs.as_str()).collect();
+        let url = format!(
+            "{}/api/v3/simple/price?ids={}&vs_currencies={}&include_market_cap=true",
+            BASE_URL,
+            ids.into_iter().collect::<Vec<_>>().join(","),
+            vs_currencies.into_iter().collect::<Vec<_>>().join(",")
+        );
+
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        Ok(coins
+            .into_iter()
+            .filter_map(|(symbol, coin_id, vs_currency)| {
+                let market_cap =
+                    decimal::from_json(&data[coin_id][format!("{}_market_cap", vs_currency)])?;
+                Some((symbol.clone(), market_cap))
+            })
+            .collect())
+    }
+
+    async fn fetch_global_metrics(&self) -> Result<GlobalMarketMetrics, MarketDataError> {
+        let url = format!("{}/api/v3/global", BASE_URL);
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        let data = &data["data"];
+        let usd = |field: &str| {
+            data[field]["usd"]
+                .as_f64()
+                .ok_or_else(|| MarketDataError::parse(NAME, format!("无 {} 数据", field)))
+        };
+        let dominance = |coin: &str| {
+            data["market_cap_percentage"][coin]
+                .as_f64()
+                .ok_or_else(|| MarketDataError::parse(NAME, format!("无 {} 市值占比", coin)))
+        };
+
+        Ok(GlobalMarketMetrics {
+            total_market_cap: usd("total_market_cap")?,
+            total_volume_24h: usd("total_volume")?,
+            btc_dominance: dominance("btc")?,
+            eth_dominance: dominance("eth")?,
+            timestamp: data["updated_at"]
+                .as_i64()
+                .map(|ts| ts * 1000)
+                .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+            source: NAME.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::dec;
+
+    #[test]
+    fn aggregates_five_minute_points_into_hourly_klines() {
+        const HOUR: i64 = 3_600_000;
+        let point = |ts: i64, price: &str| (ts, dec(price), dec("1000"));
+        let points = vec![
+            point(HOUR + 60_000, "100"),
+            point(HOUR + 300_000, "104"),
+            point(HOUR + 600_000, "98"),
+            point(2 * HOUR + 60_000, "101"),
+            point(2 * HOUR + 300_000, "103"),
+        ];
+
+        let klines = hourly_klines(&points);
+        assert_eq!(klines.len(), 2);
+
+        let first = &klines[0];
+        assert_eq!(first.timestamp, HOUR);
+        assert_eq!(first.open, dec("100"));
+        assert_eq!(first.high, dec("104"));
+        assert_eq!(first.low, dec("98"));
+        // 以下一小时的第一个价格收盘
+        assert_eq!(first.close, dec("101"));
+
+        let last = &klines[1];
+        assert_eq!(last.timestamp, 2 * HOUR);
+        assert_eq!((last.open, last.close), (dec("101"), dec("103")));
+        assert_eq!((last.high, last.low), (dec("103"), dec("101")));
+    }
+}
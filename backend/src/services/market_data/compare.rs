@@ -0,0 +1,214 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use super::consensus::{deviation_bps, median};
+use super::{decimal, OrderBook, Ticker};
+
+/// 单个数据源的报价及其相对中位数的偏离
+#[derive(Debug, Clone, Serialize)]
+pub struct VenueQuote {
+    pub source: String,
+    #[serde(with = "decimal")]
+    pub price: Decimal,
+    /// 价格相对各源中位数的价差（基点，带符号）
+    pub spread_bps: f64,
+    pub timestamp: i64,
+    /// 数据时间相对最新数据源的滞后（毫秒）
+    pub lag_ms: i64,
+    #[serde(with = "decimal::option")]
+    pub best_bid: Option<Decimal>,
+    #[serde(with = "decimal::option")]
+    pub best_ask: Option<Decimal>,
+}
+
+/// 最优报价所在的数据源
+#[derive(Debug, Clone, Serialize)]
+pub struct BestQuote {
+    pub source: String,
+    #[serde(with = "decimal")]
+    pub price: Decimal,
+}
+
+/// 同一交易对在各数据源之间的价格比较
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceComparison {
+    pub symbol: String,
+    #[serde(with = "decimal")]
+    pub median_price: Decimal,
+    /// 最高价与最低价之差占中位数的比例（基点）
+    pub max_spread_bps: f64,
+    /// 各数据源数据时间的最大差值（毫秒）
+    pub timestamp_skew_ms: i64,
+    /// 买一价最高的数据源
+    pub best_bid: Option<BestQuote>,
+    /// 卖一价最低的数据源
+    pub best_ask: Option<BestQuote>,
+    /// 最优买价高于最优卖价，即跨交易所存在可套利的倒挂
+    pub crossed: bool,
+    pub venues: Vec<VenueQuote>,
+}
+
+/// 根据各数据源的行情和订单簿计算价差、时间偏差和最优买卖价
+///
+/// 订单簿按数据源名称与行情对应，没有订单簿的数据源只参与价格比较；
+/// 没有任何行情时返回 `None`。
+pub fn compare_venues(tickers: &[Ticker], books: &[OrderBook]) -> Option<PriceComparison> {
+    let median_price = median(tickers.iter().map(|t| t.price).collect())?;
+    let newest = tickers.iter().map(|t| t.timestamp).max()?;
+    let oldest = tickers.iter().map(|t| t.timestamp).min()?;
+    let min_price = tickers.iter().map(|t| t.price).min()?;
+    let max_price = tickers.iter().map(|t| t.price).max()?;
+
+    let venues: Vec<VenueQuote> = tickers
+        .iter()
+        .map(|ticker| {
+            let book = books.iter().find(|b| b.source == ticker.source);
+            VenueQuote {
+                source: ticker.source.clone(),
+                price: ticker.price,
+                spread_bps: deviation_bps(ticker.price, median_price),
+                timestamp: ticker.timestamp,
+                lag_ms: newest - ticker.timestamp,
+                best_bid: book.and_then(|b| b.bids.first()).map(|l| l.price),
+                best_ask: book.and_then(|b| b.asks.first()).map(|l| l.price),
+            }
+        })
+        .collect();
+
+    let best_bid = books
+        .iter()
+        .filter_map(|b| b.bids.first().map(|l| (b, l.price)))
+        .max_by_key(|(_, price)| *price)
+        .map(|(book, price)| BestQuote {
+            source: book.source.clone(),
+            price,
+        });
+    let best_ask = books
+        .iter()
+        .filter_map(|b| b.asks.first().map(|l| (b, l.price)))
+        .min_by_key(|(_, price)| *price)
+        .map(|(book, price)| BestQuote {
+            source: book.source.clone(),
+            price,
+        });
+    let crossed = match (&best_bid, &best_ask) {
+        (Some(bid), Some(ask)) => bid.source != ask.source && bid.price > ask.price,
+        _ => false,
+    };
+
+    Some(PriceComparison {
+        symbol: tickers[0].symbol.clone(),
+        median_price,
+        max_spread_bps: deviation_bps(max_price, median_price)
+            - deviation_bps(min_price, median_price),
+        timestamp_skew_ms: newest - oldest,
+        best_bid,
+        best_ask,
+        crossed,
+        venues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::dec;
+    use crate::services::market_data::OrderBookLevel;
+
+    fn ticker(source: &str, price: &str, timestamp: i64) -> Ticker {
+        Ticker {
+            symbol: "BTC/USDT".to_string(),
+            price: dec(price),
+            price_change_24h: None,
+            price_change_percent_24h: None,
+            high_24h: None,
+            low_24h: None,
+            volume_24h: None,
+            quote_volume_24h: None,
+            market_cap: None,
+            timestamp,
+            source: source.to_string(),
+        }
+    }
+
+    fn book(source: &str, bid: &str, ask: &str) -> OrderBook {
+        let level = |price| OrderBookLevel {
+            price: dec(price),
+            amount: dec("1"),
+        };
+        OrderBook {
+            symbol: "BTC/USDT".to_string(),
+            bids: vec![level(bid)],
+            asks: vec![level(ask)],
+            timestamp: 0,
+            source: source.to_string(),
+        }
+    }
+
+    fn tickers() -> Vec<Ticker> {
+        vec![
+            ticker("a", "100", 1_000),
+            ticker("b", "101", 1_500),
+            ticker("c", "102", 1_200),
+        ]
+    }
+
+    #[test]
+    fn reports_spread_and_skew_against_median() {
+        let comparison = compare_venues(&tickers(), &[]).unwrap();
+
+        assert_eq!(comparison.median_price, dec("101"));
+        // (102 - 100) / 101
+        assert!((comparison.max_spread_bps - 198.0198).abs() < 1e-3);
+        assert_eq!(comparison.timestamp_skew_ms, 500);
+
+        let a = &comparison.venues[0];
+        assert!((a.spread_bps + 99.0099).abs() < 1e-3);
+        assert_eq!(a.lag_ms, 500);
+        assert_eq!(comparison.venues[1].spread_bps, 0.0);
+        assert_eq!(comparison.venues[1].lag_ms, 0);
+        assert!(comparison.best_bid.is_none());
+        assert!(!comparison.crossed);
+    }
+
+    #[test]
+    fn picks_best_bid_and_ask_across_books() {
+        let books = [book("a", "99.9", "100.1"), book("b", "100.95", "101.05")];
+        let comparison = compare_venues(&tickers(), &books).unwrap();
+
+        let best_bid = comparison.best_bid.unwrap();
+        assert_eq!(
+            (best_bid.source.as_str(), best_bid.price),
+            ("b", dec("100.95"))
+        );
+        let best_ask = comparison.best_ask.unwrap();
+        assert_eq!(
+            (best_ask.source.as_str(), best_ask.price),
+            ("a", dec("100.1"))
+        );
+        // b 的买一高于 a 的卖一
+        assert!(comparison.crossed);
+
+        assert_eq!(comparison.venues[1].best_bid, Some(dec("100.95")));
+        // 没有订单簿的数据源只参与价格比较
+        assert_eq!(comparison.venues[2].best_bid, None);
+        assert_eq!(comparison.venues[2].best_ask, None);
+    }
+
+    #[test]
+    fn crossed_requires_different_venues() {
+        let normal = [book("a", "100", "100.2"), book("b", "100.1", "100.3")];
+        assert!(!compare_venues(&tickers(), &normal).unwrap().crossed);
+
+        // 同一交易所自身买卖价倒挂不算跨交易所套利
+        let own = [book("a", "100.2", "100.1")];
+        let comparison = compare_venues(&tickers(), &own).unwrap();
+        assert_eq!(comparison.best_bid.unwrap().source, "a");
+        assert!(!comparison.crossed);
+    }
+
+    #[test]
+    fn returns_none_without_tickers() {
+        assert!(compare_venues(&[], &[book("a", "1", "2")]).is_none());
+    }
+}
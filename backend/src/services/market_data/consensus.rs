@@ -0,0 +1,307 @@
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::interval::Interval;
+use super::{decimal, KlineData};
+
+/// 聚合K线的数据源标识
+pub const AGGREGATE_SOURCE: &str = "aggregate";
+
+/// 默认偏离阈值（基点），超过即标记为异常
+pub const DEFAULT_DEVIATION_THRESHOLD_BPS: f64 = 50.0;
+
+/// 共识价格的计算方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsensusMethod {
+    /// 各数据源价格的中位数
+    #[default]
+    Median,
+    /// 按基础币成交量加权的均价，排除偏离中位数过大的数据源
+    Vwap,
+}
+
+/// 单个数据源参与聚合的K线序列
+pub struct SourceSeries {
+    pub source: String,
+    pub klines: Vec<KlineData>,
+    /// 成交量是否以基础币计价，只有这类数据源参与成交量和 VWAP 计算
+    pub base_volume: bool,
+}
+
+/// 单个数据源相对共识价格的偏离
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceDeviation {
+    pub source: String,
+    #[serde(with = "decimal")]
+    pub close: Decimal,
+    /// 收盘价相对中位数的偏离（基点，带符号）
+    pub close_deviation_bps: f64,
+    /// 开高低收中偏离最大的一项（基点，绝对值）
+    pub max_deviation_bps: f64,
+    /// 是否超过偏离阈值
+    pub outlier: bool,
+}
+
+/// 多数据源共识K线
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsensusKline {
+    #[serde(flatten)]
+    pub kline: KlineData,
+    pub source_count: usize,
+    pub deviations: Vec<SourceDeviation>,
+}
+
+/// 按周期开始时间对齐多个数据源的K线，逐根计算共识 OHLCV
+///
+/// 时间戳不在周期边界上的数据点（如 CoinGecko 的整点附近价格）归入所在周期，
+/// 同一数据源在一个周期内只取第一根；
+/// 偏离始终相对各源的中位数计算，避免单一异常源拉偏参照价；
+/// 成交量取基础币计价数据源的中位数，没有这类数据源时取全部数据源的中位数。
+pub fn build_consensus(
+    series: &[SourceSeries],
+    interval: &Interval,
+    method: ConsensusMethod,
+    threshold_bps: f64,
+) -> Vec<ConsensusKline> {
+    let mut buckets: BTreeMap<i64, Vec<(&SourceSeries, &KlineData)>> = BTreeMap::new();
+    for source_series in series {
+        for kline in &source_series.klines {
+            let members = buckets
+                .entry(interval.bucket_start(kline.timestamp))
+                .or_default();
+            if !members.iter().any(|(s, _)| std::ptr::eq(*s, source_series)) {
+                members.push((source_series, kline));
+            }
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(timestamp, members)| consensus_bucket(timestamp, &members, method, threshold_bps))
+        .collect()
+}
+
+fn consensus_bucket(
+    timestamp: i64,
+    members: &[(&SourceSeries, &KlineData)],
+    method: ConsensusMethod,
+    threshold_bps: f64,
+) -> ConsensusKline {
+    let median_of = |field: fn(&KlineData) -> Decimal| {
+        median(members.iter().map(|(_, k)| field(k)).collect()).unwrap_or_default()
+    };
+    let reference = [
+        median_of(|k| k.open),
+        median_of(|k| k.high),
+        median_of(|k| k.low),
+        median_of(|k| k.close),
+    ];
+
+    let deviations: Vec<SourceDeviation> = members
+        .iter()
+        .map(|(series, kline)| {
+            let prices = [kline.open, kline.high, kline.low, kline.close];
+            let max_deviation_bps = prices
+                .iter()
+                .zip(reference.iter())
+                .map(|(price, median)| deviation_bps(*price, *median).abs())
+                .fold(0.0, f64::max);
+            SourceDeviation {
+                source: series.source.clone(),
+                close: kline.close,
+                close_deviation_bps: deviation_bps(kline.close, reference[3]),
+                max_deviation_bps,
+                outlier: max_deviation_bps > threshold_bps,
+            }
+        })
+        .collect();
+
+    let base_volumes: Vec<Decimal> = members
+        .iter()
+        .filter(|(series, _)| series.base_volume)
+        .map(|(_, k)| k.volume)
+        .collect();
+    let volume = median(base_volumes)
+        .or_else(|| median(members.iter().map(|(_, k)| k.volume).collect()))
+        .unwrap_or_default();
+
+    let [mut open, mut high, mut low, mut close] = reference;
+    if method == ConsensusMethod::Vwap {
+        let weighted: Vec<&KlineData> = members
+            .iter()
+            .zip(deviations.iter())
+            .filter(|((series, kline), deviation)| {
+                series.base_volume && kline.volume > Decimal::ZERO && !deviation.outlier
+            })
+            .map(|((_, kline), _)| *kline)
+            .collect();
+        let total_volume: Decimal = weighted.iter().map(|k| k.volume).sum();
+        if total_volume > Decimal::ZERO {
+            let vwap = |field: fn(&KlineData) -> Decimal| {
+                weighted
+                    .iter()
+                    .map(|k| field(k) * k.volume)
+                    .sum::<Decimal>()
+                    / total_volume
+            };
+            open = vwap(|k| k.open);
+            high = vwap(|k| k.high);
+            low = vwap(|k| k.low);
+            close = vwap(|k| k.close);
+        }
+    }
+
+    ConsensusKline {
+        kline: KlineData {
+            timestamp,
+            open,
+            // 各字段独立取值后仍保证 high/low 包住开收盘价
+            high: high.max(open).max(close),
+            low: low.min(open).min(close),
+            close,
+            volume,
+            source: AGGREGATE_SOURCE.to_string(),
+        },
+        source_count: members.len(),
+        deviations,
+    }
+}
+
+pub fn median(mut values: Vec<Decimal>) -> Option<Decimal> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort();
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / Decimal::TWO
+    } else {
+        values[mid]
+    })
+}
+
+/// 相对参照价的偏离（基点），按精确小数计算后转为浮点数
+pub fn deviation_bps(price: Decimal, reference: Decimal) -> f64 {
+    (price - reference)
+        .checked_div(reference)
+        .and_then(|ratio| ratio.to_f64())
+        .map_or(0.0, |ratio| ratio * 10_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::{dec, kline};
+
+    const HOUR_MS: i64 = 3_600_000;
+
+    /// 开高低收相同的K线序列
+    fn source(name: &str, base_volume: bool, bars: &[(i64, &str, &str)]) -> SourceSeries {
+        SourceSeries {
+            source: name.to_string(),
+            klines: bars
+                .iter()
+                .map(|(timestamp, price, volume)| {
+                    kline(*timestamp, [price, price, price, price, volume])
+                })
+                .collect(),
+            base_volume,
+        }
+    }
+
+    fn hourly(series: &[SourceSeries], method: ConsensusMethod) -> Vec<ConsensusKline> {
+        build_consensus(
+            series,
+            &Interval::parse("1h").unwrap(),
+            method,
+            DEFAULT_DEVIATION_THRESHOLD_BPS,
+        )
+    }
+
+    fn venues() -> Vec<SourceSeries> {
+        vec![
+            source("a", true, &[(0, "100.6", "1")]),
+            source("b", true, &[(0, "100.8", "4")]),
+            // 偏离中位数约 891 基点
+            source("c", true, &[(0, "110", "10")]),
+            // 成交量以计价币计，不参与 VWAP
+            source("d", false, &[(0, "100.7", "50000")]),
+        ]
+    }
+
+    #[test]
+    fn median_uses_middle_price_and_flags_outliers() {
+        let consensus = hourly(&venues(), ConsensusMethod::Median);
+
+        assert_eq!(consensus.len(), 1);
+        let bar = &consensus[0];
+        assert_eq!(bar.kline.close, dec("100.75"));
+        assert_eq!(bar.kline.source, AGGREGATE_SOURCE);
+        assert_eq!(bar.source_count, 4);
+        let outliers: Vec<_> = bar
+            .deviations
+            .iter()
+            .filter(|d| d.outlier)
+            .map(|d| d.source.as_str())
+            .collect();
+        assert_eq!(outliers, vec!["c"]);
+        assert!(bar.deviations[2].close_deviation_bps > 850.0);
+        assert!(bar.deviations[0].close_deviation_bps < 0.0);
+    }
+
+    #[test]
+    fn vwap_weights_base_volume_and_excludes_outliers() {
+        let consensus = hourly(&venues(), ConsensusMethod::Vwap);
+
+        // (100.6 * 1 + 100.8 * 4) / 5，异常源 c 和计价币成交量的 d 不参与
+        assert_eq!(consensus[0].kline.close, dec("100.76"));
+        assert_eq!(consensus[0].kline.open, dec("100.76"));
+        // 偏离仍相对中位数计算
+        assert!(consensus[0].deviations[2].outlier);
+    }
+
+    #[test]
+    fn volume_prefers_base_volume_sources() {
+        let consensus = hourly(&venues(), ConsensusMethod::Median);
+        // 基础币计价的 1、4、10 取中位数
+        assert_eq!(consensus[0].kline.volume, dec("4"));
+
+        // 没有基础币计价的数据源时取全部数据源的中位数
+        let quote_only = vec![
+            source("x", false, &[(0, "100", "200")]),
+            source("y", false, &[(0, "100", "400")]),
+        ];
+        let consensus = hourly(&quote_only, ConsensusMethod::Vwap);
+        assert_eq!(consensus[0].kline.volume, dec("300"));
+        // VWAP 没有可用权重时退回中位数
+        assert_eq!(consensus[0].kline.close, dec("100"));
+    }
+
+    #[test]
+    fn takes_one_sample_per_source_per_bucket() {
+        let series = vec![
+            // 整点附近的价格点，同一小时内只取第一个
+            source(
+                "points",
+                false,
+                &[
+                    (300_000, "100", "0"),
+                    (2_100_000, "200", "0"),
+                    (HOUR_MS + 300_000, "104", "0"),
+                ],
+            ),
+            source("bars", true, &[(0, "102", "5"), (HOUR_MS, "106", "5")]),
+        ];
+        let consensus = hourly(&series, ConsensusMethod::Median);
+
+        let timestamps: Vec<_> = consensus.iter().map(|c| c.kline.timestamp).collect();
+        assert_eq!(timestamps, vec![0, HOUR_MS]);
+        assert_eq!(consensus[0].source_count, 2);
+        assert_eq!(consensus[0].kline.close, dec("101"));
+        assert_eq!(consensus[1].kline.close, dec("105"));
+        assert_eq!(consensus[1].kline.volume, dec("5"));
+    }
+}
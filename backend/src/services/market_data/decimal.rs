@@ -0,0 +1,148 @@
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_json::Value;
+use std::future::Future;
+use std::str::FromStr;
+
+/// 价格、数量等小数字段在 JSON 中的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecimalFormat {
+    /// JSON 数字（默认，与旧接口兼容），超过约15位有效数字时可能有舍入
+    #[default]
+    Number,
+    /// 字符串，保留交易所返回的全部精度，如 `"67850.10000000"`
+    String,
+}
+
+tokio::task_local! {
+    static FORMAT: DecimalFormat;
+}
+
+/// 当前请求的输出格式，未设置时为 [`DecimalFormat::Number`]
+pub fn current() -> DecimalFormat {
+    FORMAT.try_with(|format| *format).unwrap_or_default()
+}
+
+/// 在 `future` 执行期间（包括跨越 `.await`）按 `format` 序列化小数字段
+pub async fn scope<F: Future>(format: DecimalFormat, future: F) -> F::Output {
+    FORMAT.scope(format, future).await
+}
+
+/// 同步版本的 [`scope`]，用于在请求之外（如流式导出）按指定格式序列化
+pub fn with_format<R>(format: DecimalFormat, f: impl FnOnce() -> R) -> R {
+    FORMAT.sync_scope(format, f)
+}
+
+/// 解析 JSON 中的小数：字符串按原文解析，数字按其文本形式解析（兼容科学计数法）
+pub fn from_json(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(s) => parse(s),
+        Value::Number(n) => parse(&n.to_string()),
+        _ => None,
+    }
+}
+
+/// 解析小数文本，空字符串返回 `None`
+pub fn parse(input: &str) -> Option<Decimal> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    Decimal::from_str(input)
+        .or_else(|_| Decimal::from_scientific(input))
+        .ok()
+}
+
+/// 相对 `base` 的涨跌幅（百分比），`base` 为0时返回 `None`
+pub fn percent_change(price: Decimal, base: Decimal) -> Option<Decimal> {
+    (price - base)
+        .checked_div(base)
+        .map(|ratio| ratio * Decimal::ONE_HUNDRED)
+}
+
+/// 用于 `#[serde(with = "decimal")]`
+pub fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+    match current() {
+        DecimalFormat::Number => serializer.serialize_f64(value.to_f64().unwrap_or_default()),
+        DecimalFormat::String => serializer.collect_str(value),
+    }
+}
+
+/// 同时接受字符串和数字
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    from_json(&value).ok_or_else(|| serde::de::Error::custom(format!("无效的小数: {}", value)))
+}
+
+/// 可选字段，用于 `#[serde(with = "decimal::option")]`
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Decimal>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => super::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Decimal>, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Null => Ok(None),
+            value => from_json(&value)
+                .map(Some)
+                .ok_or_else(|| serde::de::Error::custom(format!("无效的小数: {}", value))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::dec;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Price {
+        #[serde(with = "super")]
+        price: Decimal,
+        #[serde(with = "super::option", default)]
+        volume: Option<Decimal>,
+    }
+
+    #[test]
+    fn serializes_as_number_or_exact_string() {
+        let price = Price {
+            price: dec("67850.10000001"),
+            volume: None,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&price).unwrap(),
+            r#"{"price":67850.10000001,"volume":null}"#
+        );
+        assert_eq!(
+            with_format(DecimalFormat::String, || serde_json::to_string(&price)).unwrap(),
+            r#"{"price":"67850.10000001","volume":null}"#
+        );
+        // 作用域结束后恢复默认格式
+        assert!(serde_json::to_string(&price)
+            .unwrap()
+            .contains("67850.10000001,"));
+    }
+
+    #[test]
+    fn parses_strings_numbers_and_scientific_notation() {
+        let price: Price = serde_json::from_str(r#"{"price":"0.00001234","volume":1e-7}"#).unwrap();
+
+        assert_eq!(price.price, dec("0.00001234"));
+        assert_eq!(price.volume, Some(dec("0.0000001")));
+        assert_eq!(parse(""), None);
+        assert_eq!(from_json(&serde_json::json!(0.1)), Some(dec("0.1")));
+    }
+}
@@ -0,0 +1,161 @@
+use thiserror::Error;
+
+use super::symbol::Symbol;
+
+/// 市场数据源错误
+#[derive(Debug, Error)]
+pub enum MarketDataError {
+    #[error("未知数据源: {0}")]
+    UnknownSource(String),
+
+    #[error("HTTP客户端创建失败: {0}")]
+    Client(#[source] reqwest::Error),
+
+    #[error("{exchange} 请求失败: {error}")]
+    Request {
+        exchange: &'static str,
+        #[source]
+        error: reqwest::Error,
+    },
+
+    #[error("{exchange} HTTP错误: {status} {body}")]
+    Status {
+        exchange: &'static str,
+        status: reqwest::StatusCode,
+        /// 响应体（截断），便于识别交易所的业务错误码
+        body: String,
+    },
+
+    #[error("{exchange} API错误: {message}")]
+    Api {
+        exchange: &'static str,
+        message: String,
+    },
+
+    #[error("{exchange} 数据解析失败: {message}")]
+    Parse {
+        exchange: &'static str,
+        message: String,
+    },
+
+    #[error("无效的交易对: {0}")]
+    InvalidSymbol(String),
+
+    #[error("{exchange} 不支持交易对 {symbol}")]
+    UnknownSymbol {
+        exchange: &'static str,
+        symbol: String,
+    },
+
+    #[error("无效的翻页游标: {0}")]
+    InvalidCursor(String),
+
+    #[error("无效的时间周期: {0}")]
+    InvalidInterval(String),
+
+    #[error("{exchange} 不支持时间周期 {interval}")]
+    UnsupportedInterval {
+        exchange: &'static str,
+        interval: String,
+    },
+
+    #[error("{exchange} 查询区间过大，超过 {pages} 页")]
+    RangeTooLarge {
+        exchange: &'static str,
+        pages: usize,
+    },
+
+    #[error("{exchange} 已熔断，{retry_after_secs} 秒后重试")]
+    CircuitOpen {
+        exchange: &'static str,
+        retry_after_secs: u64,
+    },
+
+    #[error("{exchange} 请求频率超限，约 {retry_after_ms} 毫秒后可重试")]
+    RateLimited {
+        exchange: &'static str,
+        retry_after_ms: u64,
+    },
+
+    #[error("{exchange} 不支持 {operation}")]
+    Unsupported {
+        exchange: &'static str,
+        operation: &'static str,
+    },
+
+    #[error("导出失败: {0}")]
+    Export(String),
+
+    #[error("{exchange} 回放数据错误: {message}")]
+    Replay {
+        exchange: &'static str,
+        message: String,
+    },
+
+    #[error("{exchange} 实时推送错误: {message}")]
+    Stream {
+        exchange: &'static str,
+        message: String,
+    },
+}
+
+impl MarketDataError {
+    /// 由请求参数引起的错误（应返回 400），而非数据源不可用
+    pub fn is_client_error(&self) -> bool {
+        matches!(
+            self,
+            Self::InvalidInterval(_)
+                | Self::UnsupportedInterval { .. }
+                | Self::InvalidSymbol(_)
+                | Self::UnknownSymbol { .. }
+                | Self::InvalidCursor(_)
+        )
+    }
+
+    /// 被限频时建议的重试等待时间（秒，向上取整）
+    pub fn retry_after_secs(&self) -> Option<u64> {
+        match self {
+            Self::RateLimited { retry_after_ms, .. } => Some(retry_after_ms.div_ceil(1000).max(1)),
+            _ => None,
+        }
+    }
+
+    pub fn unknown_symbol(exchange: &'static str, symbol: &Symbol) -> Self {
+        Self::UnknownSymbol {
+            exchange,
+            symbol: symbol.to_string(),
+        }
+    }
+
+    pub fn request(exchange: &'static str, error: reqwest::Error) -> Self {
+        Self::Request { exchange, error }
+    }
+
+    pub fn parse(exchange: &'static str, message: impl Into<String>) -> Self {
+        Self::Parse {
+            exchange,
+            message: message.into(),
+        }
+    }
+
+    pub fn api(exchange: &'static str, message: impl Into<String>) -> Self {
+        Self::Api {
+            exchange,
+            message: message.into(),
+        }
+    }
+
+    pub fn replay(exchange: &'static str, message: impl Into<String>) -> Self {
+        Self::Replay {
+            exchange,
+            message: message.into(),
+        }
+    }
+
+    pub fn stream(exchange: &'static str, message: impl ToString) -> Self {
+        Self::Stream {
+            exchange,
+            message: message.to_string(),
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::decimal::{self, DecimalFormat};
+use super::indicators::{Indicator, IndicatorSpec};
+use super::{KlineData, MarketDataError};
+
+/// K线导出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Parquet,
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Parquet => "application/vnd.apache.parquet",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// JSON Lines 的一行：K线字段在前，指标列在后
+#[derive(Serialize)]
+struct JsonRow<'a> {
+    #[serde(flatten)]
+    kline: &'a KlineData,
+    #[serde(flatten)]
+    indicators: BTreeMap<String, Option<f64>>,
+}
+
+/// 把K线按批编码为导出格式，指标跨批次连续计算
+///
+/// 每批K线编码后即可发送，Parquet 每批写为一个行组，内存占用只与批大小有关。
+pub struct KlineEncoder {
+    format: ExportFormat,
+    indicators: Vec<Indicator>,
+    parquet: Option<SerializedFileWriter<Vec<u8>>>,
+    /// JSON Lines 中价格字段的格式，取创建时所在请求的 `decimals` 参数
+    decimals: DecimalFormat,
+}
+
+impl KlineEncoder {
+    pub fn new(
+        format: ExportFormat,
+        indicators: &[IndicatorSpec],
+    ) -> Result<Self, MarketDataError> {
+        let parquet = match format {
+            ExportFormat::Parquet => {
+                let schema =
+                    parse_message_type(&parquet_schema(indicators)).map_err(export_error)?;
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .build();
+                Some(
+                    SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(properties))
+                        .map_err(export_error)?,
+                )
+            }
+            ExportFormat::Csv | ExportFormat::Jsonl => None,
+        };
+        Ok(Self {
+            format,
+            indicators: indicators.iter().copied().map(Indicator::new).collect(),
+            parquet,
+            decimals: decimal::current(),
+        })
+    }
+
+    /// 文件头：CSV 的表头行，其余格式为空
+    pub fn header(&self) -> Vec<u8> {
+        match self.format {
+            ExportFormat::Csv => {
+                let mut columns = vec![
+                    "timestamp".to_string(),
+                    "open".to_string(),
+                    "high".to_string(),
+                    "low".to_string(),
+                    "close".to_string(),
+                    "volume".to_string(),
+                    "source".to_string(),
+                ];
+                columns.extend(self.indicators.iter().map(|i| i.spec().to_string()));
+                format!("{}\n", columns.join(",")).into_bytes()
+            }
+            ExportFormat::Parquet | ExportFormat::Jsonl => Vec::new(),
+        }
+    }
+
+    /// 编码一批按时间升序的K线，返回可以立即发送的字节
+    pub fn encode(&mut self, klines: &[KlineData]) -> Result<Vec<u8>, MarketDataError> {
+        let values: Vec<Vec<Option<f64>>> = self
+            .indicators
+            .iter_mut()
+            .map(|indicator| {
+                klines
+                    .iter()
+                    .map(|k| indicator.next(k.close.to_f64().unwrap_or_default()))
+                    .collect()
+            })
+            .collect();
+
+        match self.format {
+            ExportFormat::Csv => Ok(self.encode_csv(klines, &values)),
+            ExportFormat::Jsonl => Ok(self.encode_jsonl(klines, &values)),
+            ExportFormat::Parquet => self.encode_parquet(klines, &values).map_err(export_error),
+        }
+    }
+
+    /// 结束导出，返回剩余的字节（Parquet 文件尾）
+    pub fn finish(self) -> Result<Vec<u8>, MarketDataError> {
+        match self.parquet {
+            Some(writer) => writer.into_inner().map_err(export_error),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn encode_csv(&self, klines: &[KlineData], values: &[Vec<Option<f64>>]) -> Vec<u8> {
+        let mut out = String::new();
+        for (row, k) in klines.iter().enumerate() {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{}",
+                k.timestamp, k.open, k.high, k.low, k.close, k.volume, k.source
+            ));
+            for column in values {
+                out.push(',');
+                if let Some(value) = column[row] {
+                    out.push_str(&value.to_string());
+                }
+            }
+            out.push('\n');
+        }
+        out.into_bytes()
+    }
+
+    fn encode_jsonl(&self, klines: &[KlineData], values: &[Vec<Option<f64>>]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (row, kline) in klines.iter().enumerate() {
+            let indicators = self
+                .indicators
+                .iter()
+                .zip(values)
+                .map(|(indicator, column)| (indicator.spec().to_string(), column[row]))
+                .collect();
+            // 只含数值和字符串，序列化不会失败
+            let _ = decimal::with_format(self.decimals, || {
+                serde_json::to_writer(&mut out, &JsonRow { kline, indicators })
+            });
+            out.push(b'\n');
+        }
+        out
+    }
+
+    fn encode_parquet(
+        &mut self,
+        klines: &[KlineData],
+        values: &[Vec<Option<f64>>],
+    ) -> Result<Vec<u8>, ParquetError> {
+        let Some(writer) = self.parquet.as_mut() else {
+            return Ok(Vec::new());
+        };
+        if klines.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let timestamps: Vec<i64> = klines.iter().map(|k| k.timestamp).collect();
+        // Parquet 中价格仍为 DOUBLE，需要精确值时使用 CSV 或 JSON Lines
+        let column = |field: fn(&KlineData) -> Decimal| -> Vec<f64> {
+            klines
+                .iter()
+                .map(|k| field(k).to_f64().unwrap_or_default())
+                .collect()
+        };
+        let prices: [Vec<f64>; 5] = [
+            column(|k| k.open),
+            column(|k| k.high),
+            column(|k| k.low),
+            column(|k| k.close),
+            column(|k| k.volume),
+        ];
+        let sources: Vec<ByteArray> = klines.iter().map(|k| k.source.as_str().into()).collect();
+
+        let mut row_group = writer.next_row_group()?;
+        let mut column = next_column(&mut row_group)?;
+        column
+            .typed::<Int64Type>()
+            .write_batch(&timestamps, None, None)?;
+        column.close()?;
+        for price in &prices {
+            let mut column = next_column(&mut row_group)?;
+            column
+                .typed::<DoubleType>()
+                .write_batch(price, None, None)?;
+            column.close()?;
+        }
+        let mut column = next_column(&mut row_group)?;
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&sources, None, None)?;
+        column.close()?;
+        // 指标列可为空，预热期间的空值通过定义级别表示
+        for indicator in values {
+            let present: Vec<f64> = indicator.iter().flatten().copied().collect();
+            let levels: Vec<i16> = indicator.iter().map(|v| i16::from(v.is_some())).collect();
+            let mut column = next_column(&mut row_group)?;
+            column
+                .typed::<DoubleType>()
+                .write_batch(&present, Some(&levels), None)?;
+            column.close()?;
+        }
+        row_group.close()?;
+
+        // 已写出的行组可以立即发送，文件尾在 finish 时写出
+        Ok(std::mem::take(writer.inner_mut()))
+    }
+}
+
+fn next_column<'a>(
+    row_group: &'a mut parquet::file::writer::SerializedRowGroupWriter<'_, Vec<u8>>,
+) -> Result<parquet::file::writer::SerializedColumnWriter<'a>, ParquetError> {
+    row_group
+        .next_column()?
+        .ok_or_else(|| ParquetError::General("列数与结构定义不一致".to_string()))
+}
+
+fn parquet_schema(indicators: &[IndicatorSpec]) -> String {
+    let mut fields = vec![
+        "REQUIRED INT64 timestamp (TIMESTAMP(MILLIS,true));".to_string(),
+        "REQUIRED DOUBLE open;".to_string(),
+        "REQUIRED DOUBLE high;".to_string(),
+        "REQUIRED DOUBLE low;".to_string(),
+        "REQUIRED DOUBLE close;".to_string(),
+        "REQUIRED DOUBLE volume;".to_string(),
+        "REQUIRED BYTE_ARRAY source (UTF8);".to_string(),
+    ];
+    fields.extend(
+        indicators
+            .iter()
+            .map(|spec| format!("OPTIONAL DOUBLE {};", spec)),
+    );
+    format!("message kline {{ {} }}", fields.join(" "))
+}
+
+fn export_error(error: ParquetError) -> MarketDataError {
+    MarketDataError::Export(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::kline;
+    use actix_web::web::Bytes;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    fn klines(closes: &[&str]) -> Vec<KlineData> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, close)| kline(i as i64 * 60_000, [close, close, close, close, "1"]))
+            .collect()
+    }
+
+    fn sma_2() -> Vec<IndicatorSpec> {
+        IndicatorSpec::parse_list("sma_2").unwrap()
+    }
+
+    #[test]
+    fn csv_continues_indicators_across_batches() {
+        let rows = klines(&["10", "20", "40"]);
+        let mut encoder = KlineEncoder::new(ExportFormat::Csv, &sma_2()).unwrap();
+
+        let mut out = encoder.header();
+        out.extend(encoder.encode(&rows[..1]).unwrap());
+        out.extend(encoder.encode(&rows[1..]).unwrap());
+        out.extend(encoder.finish().unwrap());
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "timestamp,open,high,low,close,volume,source,sma_2\n\
+             0,10,10,10,10,1,test,\n\
+             60000,20,20,20,20,1,test,15\n\
+             120000,40,40,40,40,1,test,30\n"
+        );
+    }
+
+    #[test]
+    fn jsonl_writes_one_object_per_row_with_null_warmup() {
+        let mut encoder = decimal::with_format(DecimalFormat::String, || {
+            KlineEncoder::new(ExportFormat::Jsonl, &sma_2()).unwrap()
+        });
+        assert!(encoder.header().is_empty());
+
+        let out = encoder.encode(&klines(&["10.50", "20"])).unwrap();
+        let rows: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(rows.len(), 2);
+        // 小数格式取创建编码器时的设置
+        assert_eq!(rows[0]["close"], "10.50");
+        assert_eq!(rows[0]["sma_2"], serde_json::Value::Null);
+        assert_eq!(rows[1]["timestamp"], 60_000);
+        assert_eq!(rows[1]["sma_2"].as_f64(), Some(15.25));
+    }
+
+    #[test]
+    fn parquet_writes_a_row_group_per_batch_and_footer_on_finish() {
+        let rows = klines(&["10", "20", "40", "80", "160"]);
+        let mut encoder = KlineEncoder::new(ExportFormat::Parquet, &sma_2()).unwrap();
+
+        let mut file = encoder.header();
+        file.extend(encoder.encode(&rows[..2]).unwrap());
+        file.extend(encoder.encode(&[]).unwrap());
+        file.extend(encoder.encode(&rows[2..]).unwrap());
+        // 文件尾之前的字节已可发送，但还不是完整文件
+        assert!(SerializedFileReader::new(Bytes::from(file.clone())).is_err());
+        file.extend(encoder.finish().unwrap());
+
+        let reader = SerializedFileReader::new(Bytes::from(file)).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 5);
+        let groups: Vec<_> = metadata.row_groups().iter().map(|g| g.num_rows()).collect();
+        assert_eq!(groups, vec![2, 3]);
+        let schema = metadata.file_metadata().schema_descr();
+        assert_eq!(schema.num_columns(), 8);
+        assert_eq!(schema.column(7).name(), "sma_2");
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::http::{build_client, get_json, HttpClient};
+use super::rate_limit::{BucketSpec, RateLimitRules, RateLimiter, RequestCost};
+use super::replay::HttpMode;
+use super::MarketDataError;
+
+const NAME: &str = "alternative";
+const BASE_URL: &str = "https://api.alternative.me";
+
+/// 公开接口限制为每分钟60次
+static RATE_LIMITS: RateLimitRules = RateLimitRules {
+    buckets: &[BucketSpec::new("public", 60, Duration::from_secs(60))],
+    cost: |_| RequestCost::new("public", 1),
+    used_weight_headers: &[],
+};
+
+/// 加密货币恐惧贪婪指数（每日更新）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FearGreedIndex {
+    /// 0（极度恐惧）- 100（极度贪婪）
+    pub value: u8,
+    /// 如 `Extreme Fear`、`Greed`
+    pub classification: String,
+    pub timestamp: i64,
+}
+
+/// alternative.me 恐惧贪婪指数接口
+pub struct FearGreedClient {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl FearGreedClient {
+    pub fn new(mode: &HttpMode) -> Self {
+        Self {
+            client: build_client(NAME, mode),
+            limiter: RateLimiter::new(NAME, &RATE_LIMITS),
+        }
+    }
+
+    /// 最近 `days` 天的指数，按时间从新到旧排列
+    pub async fn fetch(&self, days: u32) -> Result<Vec<FearGreedIndex>, MarketDataError> {
+        let url = format!("{}/fng/?limit={}", BASE_URL, days.max(1));
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        if let Some(error) = data["metadata"]["error"].as_str() {
+            return Err(MarketDataError::api(NAME, error));
+        }
+
+        data["data"]
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无指数数据"))?
+            .iter()
+            .map(|entry| {
+                // 数值和时间戳都以字符串返回
+                let number = |field: &str| {
+                    entry[field]
+                        .as_str()
+                        .and_then(|s| s.parse::<i64>().ok())
+                        .ok_or_else(|| MarketDataError::parse(NAME, format!("无效的 {}", field)))
+                };
+                Ok(FearGreedIndex {
+                    value: u8::try_from(number("value")?.clamp(0, 100)).unwrap_or_default(),
+                    classification: entry["value_classification"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_string(),
+                    timestamp: number("timestamp")? * 1000,
+                })
+            })
+            .collect()
+    }
+
+    /// 当日指数
+    pub async fn latest(&self) -> Result<FearGreedIndex, MarketDataError> {
+        self.fetch(1)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无指数数据"))
+    }
+}
+
+impl Default for FearGreedClient {
+    fn default() -> Self {
+        Self::new(&HttpMode::Live)
+    }
+}
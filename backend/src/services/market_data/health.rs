@@ -0,0 +1,706 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::rate_limit::{RateLimitSnapshot, RateLimiter};
+use super::{
+    FundingQuery, FundingRate, GlobalMarketMetrics, Instrument, KlineData, KlineQuery, MarkPrice,
+    MarketDataError, MarketDataSource, OpenInterest, OrderBook, PriceKind, PublicTrade,
+    SourceCapabilities, Symbol, Ticker, TradeQuery,
+};
+
+/// 滚动窗口保留的最近请求数
+const WINDOW_SIZE: usize = 50;
+/// 连续失败达到该次数即熔断
+const CONSECUTIVE_FAILURE_THRESHOLD: u32 = 3;
+/// 窗口内请求数达到该值后才按错误率熔断
+const MIN_REQUESTS_FOR_ERROR_RATE: usize = 10;
+/// 窗口错误率达到该值即熔断
+const ERROR_RATE_THRESHOLD: f64 = 0.5;
+/// 首次熔断的冷却时间，连续熔断时翻倍
+const BASE_COOL_OFF: Duration = Duration::from_secs(30);
+const MAX_COOL_OFF: Duration = Duration::from_secs(300);
+/// 探测请求超过该时间未返回（如请求被取消）视为丢失，允许重新探测
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 熔断器按接口分组，永续合约接口故障不影响现货请求
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointGroup {
+    Spot,
+    Perpetual,
+}
+
+impl EndpointGroup {
+    fn label(self) -> &'static str {
+        match self {
+            EndpointGroup::Spot => "现货",
+            EndpointGroup::Perpetual => "永续合约",
+        }
+    }
+}
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// 正常放行请求
+    Closed,
+    /// 熔断中，请求直接失败
+    Open,
+    /// 冷却结束，放行一个探测请求
+    HalfOpen,
+}
+
+struct Outcome {
+    latency_ms: u64,
+    success: bool,
+}
+
+struct HealthState {
+    window: VecDeque<Outcome>,
+    circuit: CircuitState,
+    open_until: Option<Instant>,
+    cool_off: Duration,
+    probe_started: Option<Instant>,
+    consecutive_failures: u32,
+    total_requests: u64,
+    total_failures: u64,
+    last_success_at: Option<i64>,
+    last_failure_at: Option<i64>,
+    last_error: Option<String>,
+}
+
+/// 单个数据源一组接口的健康统计与熔断器
+pub struct SourceHealth {
+    source: &'static str,
+    group: EndpointGroup,
+    state: Mutex<HealthState>,
+}
+
+/// 数据源健康状态快照
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthSnapshot {
+    pub source: &'static str,
+    pub group: EndpointGroup,
+    pub state: CircuitState,
+    /// 滚动窗口内的请求数
+    pub window_requests: usize,
+    pub error_rate: f64,
+    pub avg_latency_ms: Option<u64>,
+    pub p95_latency_ms: Option<u64>,
+    pub consecutive_failures: u32,
+    pub total_requests: u64,
+    pub total_failures: u64,
+    /// 最近一次成功/失败的时间（毫秒时间戳）
+    pub last_success_at: Option<i64>,
+    pub last_failure_at: Option<i64>,
+    pub last_error: Option<String>,
+    /// 熔断剩余冷却时间（秒）
+    pub retry_after_secs: Option<u64>,
+    /// 上游请求限频器状态
+    pub rate_limit: Option<RateLimitSnapshot>,
+    /// 永续合约接口的熔断器，仅支持永续合约的数据源返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub perpetual: Option<Box<HealthSnapshot>>,
+}
+
+impl SourceHealth {
+    pub fn new(source: &'static str, group: EndpointGroup) -> Self {
+        Self {
+            source,
+            group,
+            state: Mutex::new(HealthState {
+                window: VecDeque::with_capacity(WINDOW_SIZE),
+                circuit: CircuitState::Closed,
+                open_until: None,
+                cool_off: BASE_COOL_OFF,
+                probe_started: None,
+                consecutive_failures: 0,
+                total_requests: 0,
+                total_failures: 0,
+                last_success_at: None,
+                last_failure_at: None,
+                last_error: None,
+            }),
+        }
+    }
+
+    /// 请求前检查熔断器，熔断中返回剩余冷却时间（秒）
+    fn try_acquire(&self) -> Result<(), u64> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        match state.circuit {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => match state.open_until {
+                Some(until) if until > now => Err((until - now).as_secs().max(1)),
+                _ => {
+                    state.circuit = CircuitState::HalfOpen;
+                    state.probe_started = Some(now);
+                    Ok(())
+                }
+            },
+            CircuitState::HalfOpen => match state.probe_started {
+                Some(started) if now - started < PROBE_TIMEOUT => Err(1),
+                _ => {
+                    state.probe_started = Some(now);
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn record(&self, latency: Duration, error: Option<&MarketDataError>) {
+        let mut state = self.state.lock().unwrap();
+        let now = chrono::Utc::now().timestamp_millis();
+
+        if state.window.len() == WINDOW_SIZE {
+            state.window.pop_front();
+        }
+        state.window.push_back(Outcome {
+            latency_ms: latency.as_millis() as u64,
+            success: error.is_none(),
+        });
+        state.total_requests += 1;
+        state.probe_started = None;
+
+        match error {
+            None => {
+                state.consecutive_failures = 0;
+                state.last_success_at = Some(now);
+                if state.circuit != CircuitState::Closed {
+                    println!(
+                        "✅ 数据源 {} {}接口恢复，关闭熔断器",
+                        self.source,
+                        self.group.label()
+                    );
+                    // 丢弃熔断前的失败记录，避免恢复后因历史错误率立即再次熔断
+                    state.window.retain(|o| o.success);
+                    state.circuit = CircuitState::Closed;
+                    state.open_until = None;
+                    state.cool_off = BASE_COOL_OFF;
+                }
+            }
+            Some(e) => {
+                state.consecutive_failures += 1;
+                state.total_failures += 1;
+                state.last_failure_at = Some(now);
+                state.last_error = Some(e.to_string());
+
+                let should_open = match state.circuit {
+                    // 探测失败立即重新熔断，并延长冷却时间
+                    CircuitState::HalfOpen => {
+                        state.cool_off = (state.cool_off * 2).min(MAX_COOL_OFF);
+                        true
+                    }
+                    CircuitState::Open => false,
+                    CircuitState::Closed => {
+                        state.consecutive_failures >= CONSECUTIVE_FAILURE_THRESHOLD
+                            || (state.window.len() >= MIN_REQUESTS_FOR_ERROR_RATE
+                                && error_rate(&state.window) >= ERROR_RATE_THRESHOLD)
+                    }
+                };
+                if should_open {
+                    println!(
+                        "🔌 熔断数据源 {} {}接口 {} 秒: {}",
+                        self.source,
+                        self.group.label(),
+                        state.cool_off.as_secs(),
+                        e
+                    );
+                    state.circuit = CircuitState::Open;
+                    state.open_until = Some(Instant::now() + state.cool_off);
+                }
+            }
+        }
+    }
+
+    /// 请求未实际发出（如参数错误）时释放探测名额
+    fn release(&self) {
+        self.state.lock().unwrap().probe_started = None;
+    }
+
+    pub fn snapshot(&self) -> HealthSnapshot {
+        let state = self.state.lock().unwrap();
+        let mut latencies: Vec<u64> = state.window.iter().map(|o| o.latency_ms).collect();
+        latencies.sort_unstable();
+        let avg_latency_ms =
+            (!latencies.is_empty()).then(|| latencies.iter().sum::<u64>() / latencies.len() as u64);
+        let p95_latency_ms = latencies
+            .get((latencies.len() * 95 / 100).min(latencies.len().saturating_sub(1)))
+            .copied();
+        let now = Instant::now();
+
+        HealthSnapshot {
+            source: self.source,
+            group: self.group,
+            state: state.circuit,
+            window_requests: state.window.len(),
+            error_rate: error_rate(&state.window),
+            avg_latency_ms,
+            p95_latency_ms,
+            consecutive_failures: state.consecutive_failures,
+            total_requests: state.total_requests,
+            total_failures: state.total_failures,
+            last_success_at: state.last_success_at,
+            last_failure_at: state.last_failure_at,
+            last_error: state.last_error.clone(),
+            retry_after_secs: state
+                .open_until
+                .filter(|until| *until > now && state.circuit == CircuitState::Open)
+                .map(|until| (until - now).as_secs().max(1)),
+            rate_limit: None,
+            perpetual: None,
+        }
+    }
+}
+
+fn error_rate(window: &VecDeque<Outcome>) -> f64 {
+    if window.is_empty() {
+        return 0.0;
+    }
+    window.iter().filter(|o| !o.success).count() as f64 / window.len() as f64
+}
+
+/// 带健康统计和熔断的数据源包装，注册表中的数据源都经过此包装
+///
+/// 现货和永续合约接口各用一个熔断器（资金费率、持仓量、标记价格等走永续合约熔断器）。
+pub struct MonitoredSource {
+    inner: Arc<dyn MarketDataSource>,
+    spot: SourceHealth,
+    perpetual: SourceHealth,
+}
+
+impl MonitoredSource {
+    pub fn new(inner: Arc<dyn MarketDataSource>) -> Self {
+        Self {
+            spot: SourceHealth::new(inner.name(), EndpointGroup::Spot),
+            perpetual: SourceHealth::new(inner.name(), EndpointGroup::Perpetual),
+            inner,
+        }
+    }
+
+    /// 现货熔断器状态，支持永续合约的数据源附带永续合约熔断器状态
+    pub fn health(&self) -> HealthSnapshot {
+        HealthSnapshot {
+            rate_limit: self.inner.rate_limiter().map(RateLimiter::snapshot),
+            perpetual: self
+                .inner
+                .capabilities()
+                .perpetuals
+                .then(|| Box::new(self.perpetual.snapshot())),
+            ..self.spot.snapshot()
+        }
+    }
+
+    async fn call<T, F>(&self, group: EndpointGroup, request: F) -> Result<T, MarketDataError>
+    where
+        F: Future<Output = Result<T, MarketDataError>>,
+    {
+        let health = match group {
+            EndpointGroup::Spot => &self.spot,
+            EndpointGroup::Perpetual => &self.perpetual,
+        };
+        if let Err(retry_after_secs) = health.try_acquire() {
+            return Err(MarketDataError::CircuitOpen {
+                exchange: self.inner.name(),
+                retry_after_secs,
+            });
+        }
+
+        let started = Instant::now();
+        let result = request.await;
+        match &result {
+            // 参数错误、不支持的操作和限频与数据源是否可用无关，不计入统计
+            Err(e)
+                if e.is_client_error()
+                    || matches!(
+                        e,
+                        MarketDataError::Unsupported { .. } | MarketDataError::RateLimited { .. }
+                    ) =>
+            {
+                health.release()
+            }
+            Ok(_) => health.record(started.elapsed(), None),
+            Err(e) => health.record(started.elapsed(), Some(e)),
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for MonitoredSource {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.inner.rate_limiter()
+    }
+
+    fn supported_intervals(&self) -> Vec<&'static str> {
+        self.inner.supported_intervals()
+    }
+
+    fn history_page_size_from(&self, start_time: i64, step: i64, now: i64) -> Option<u32> {
+        self.inner.history_page_size_from(start_time, step, now)
+    }
+
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError> {
+        self.call(EndpointGroup::Spot, self.inner.fetch_klines(query))
+            .await
+    }
+
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        self.call(EndpointGroup::Spot, self.inner.fetch_ticker(symbol))
+            .await
+    }
+
+    async fn fetch_order_book(
+        &self,
+        symbol: &Symbol,
+        depth: u32,
+    ) -> Result<OrderBook, MarketDataError> {
+        self.call(
+            EndpointGroup::Spot,
+            self.inner.fetch_order_book(symbol, depth),
+        )
+        .await
+    }
+
+    async fn fetch_trades(&self, query: &TradeQuery) -> Result<Vec<PublicTrade>, MarketDataError> {
+        self.call(EndpointGroup::Spot, self.inner.fetch_trades(query))
+            .await
+    }
+
+    async fn fetch_instruments(&self) -> Result<Vec<Instrument>, MarketDataError> {
+        self.call(EndpointGroup::Spot, self.inner.fetch_instruments())
+            .await
+    }
+
+    async fn fetch_funding_rate(&self, symbol: &Symbol) -> Result<FundingRate, MarketDataError> {
+        self.call(
+            EndpointGroup::Perpetual,
+            self.inner.fetch_funding_rate(symbol),
+        )
+        .await
+    }
+
+    async fn fetch_funding_history(
+        &self,
+        query: &FundingQuery,
+    ) -> Result<Vec<FundingRate>, MarketDataError> {
+        self.call(
+            EndpointGroup::Perpetual,
+            self.inner.fetch_funding_history(query),
+        )
+        .await
+    }
+
+    async fn fetch_open_interest(&self, symbol: &Symbol) -> Result<OpenInterest, MarketDataError> {
+        self.call(
+            EndpointGroup::Perpetual,
+            self.inner.fetch_open_interest(symbol),
+        )
+        .await
+    }
+
+    async fn fetch_open_interest_history(
+        &self,
+        query: &KlineQuery,
+    ) -> Result<Vec<OpenInterest>, MarketDataError> {
+        self.call(
+            EndpointGroup::Perpetual,
+            self.inner.fetch_open_interest_history(query),
+        )
+        .await
+    }
+
+    async fn fetch_mark_price(&self, symbol: &Symbol) -> Result<MarkPrice, MarketDataError> {
+        self.call(
+            EndpointGroup::Perpetual,
+            self.inner.fetch_mark_price(symbol),
+        )
+        .await
+    }
+
+    async fn fetch_price_klines(
+        &self,
+        kind: PriceKind,
+        query: &KlineQuery,
+    ) -> Result<Vec<KlineData>, MarketDataError> {
+        self.call(
+            EndpointGroup::Perpetual,
+            self.inner.fetch_price_klines(kind, query),
+        )
+        .await
+    }
+
+    async fn fetch_global_metrics(&self) -> Result<GlobalMarketMetrics, MarketDataError> {
+        self.call(EndpointGroup::Spot, self.inner.fetch_global_metrics())
+            .await
+    }
+
+    async fn fetch_market_caps(
+        &self,
+        symbols: &[Symbol],
+    ) -> Result<HashMap<Symbol, Decimal>, MarketDataError> {
+        self.call(EndpointGroup::Spot, self.inner.fetch_market_caps(symbols))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 按设定结果返回的数据源，每次请求耗时1秒
+    struct StubSource {
+        calls: AtomicUsize,
+        error: Mutex<Option<fn() -> MarketDataError>>,
+    }
+
+    impl StubSource {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicUsize::new(0),
+                error: Mutex::new(None),
+            })
+        }
+
+        fn respond_with(&self, error: Option<fn() -> MarketDataError>) {
+            *self.error.lock().unwrap() = error;
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl MarketDataSource for StubSource {
+        fn name(&self) -> &'static str {
+            "stub"
+        }
+
+        fn capabilities(&self) -> SourceCapabilities {
+            SourceCapabilities {
+                klines: true,
+                ticker: false,
+                order_book: false,
+                trades: false,
+                native_ohlc: true,
+                base_volume: true,
+                max_klines_per_request: 100,
+                history_page_size: None,
+                perpetuals: true,
+            }
+        }
+
+        fn supported_intervals(&self) -> Vec<&'static str> {
+            vec!["1h"]
+        }
+
+        async fn fetch_klines(
+            &self,
+            _query: &KlineQuery,
+        ) -> Result<Vec<KlineData>, MarketDataError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            match *self.error.lock().unwrap() {
+                Some(error) => Err(error()),
+                None => Ok(Vec::new()),
+            }
+        }
+
+        async fn fetch_ticker(&self, _symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+            Err(MarketDataError::Unsupported {
+                exchange: self.name(),
+                operation: "行情",
+            })
+        }
+
+        async fn fetch_funding_rate(
+            &self,
+            _symbol: &Symbol,
+        ) -> Result<FundingRate, MarketDataError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err(upstream_error())
+        }
+    }
+
+    fn upstream_error() -> MarketDataError {
+        MarketDataError::Api {
+            exchange: "stub",
+            message: "服务不可用".to_string(),
+        }
+    }
+
+    fn client_error() -> MarketDataError {
+        MarketDataError::InvalidSymbol("???".to_string())
+    }
+
+    fn monitored() -> (Arc<StubSource>, MonitoredSource) {
+        let stub = StubSource::new();
+        let source = MonitoredSource::new(stub.clone());
+        (stub, source)
+    }
+
+    async fn fetch(source: &MonitoredSource) -> Result<Vec<KlineData>, MarketDataError> {
+        source
+            .fetch_klines(&KlineQuery {
+                symbol: Symbol::parse("BTC/USDT").unwrap(),
+                interval: "1h".to_string(),
+                limit: 1,
+                start_time: None,
+                end_time: None,
+            })
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_after_consecutive_failures() {
+        let (stub, source) = monitored();
+        stub.respond_with(Some(upstream_error));
+
+        for _ in 0..2 {
+            assert!(fetch(&source).await.is_err());
+        }
+        assert_eq!(source.health().state, CircuitState::Closed);
+        assert!(fetch(&source).await.is_err());
+
+        assert_eq!(source.health().state, CircuitState::Open);
+        assert_eq!(source.health().retry_after_secs, Some(30));
+        // 熔断期间不再请求数据源
+        let error = fetch(&source).await.unwrap_err();
+        assert!(matches!(
+            error,
+            MarketDataError::CircuitOpen {
+                retry_after_secs: 30,
+                ..
+            }
+        ));
+        assert_eq!(stub.calls(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_when_error_rate_reaches_half_of_window() {
+        let (stub, source) = monitored();
+
+        for i in 0..9 {
+            stub.respond_with((i % 2 == 1).then_some(upstream_error as fn() -> _));
+            let _ = fetch(&source).await;
+        }
+        // 9 次请求中失败 4 次，且从未连续失败
+        assert_eq!(source.health().state, CircuitState::Closed);
+        stub.respond_with(Some(upstream_error));
+        let _ = fetch(&source).await;
+
+        let health = source.health();
+        assert_eq!(health.state, CircuitState::Open);
+        assert_eq!(health.window_requests, 10);
+        assert_eq!(health.error_rate, 0.5);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn half_open_probe_doubles_cool_off_until_recovery() {
+        let (stub, source) = monitored();
+        stub.respond_with(Some(upstream_error));
+        for _ in 0..3 {
+            let _ = fetch(&source).await;
+        }
+
+        let mut cool_off = 30;
+        for expected in [60, 120, 240, 300, 300] {
+            tokio::time::advance(Duration::from_secs(cool_off)).await;
+            // 冷却结束只放行一个探测请求，探测返回前其余请求仍被拒绝
+            let (probe, concurrent) = tokio::join!(fetch(&source), fetch(&source));
+            assert!(matches!(probe, Err(MarketDataError::Api { .. })));
+            assert!(matches!(
+                concurrent,
+                Err(MarketDataError::CircuitOpen { .. })
+            ));
+            assert_eq!(source.health().retry_after_secs, Some(expected));
+            cool_off = expected;
+        }
+        assert_eq!(stub.calls(), 8);
+
+        tokio::time::advance(Duration::from_secs(cool_off)).await;
+        stub.respond_with(None);
+        assert!(fetch(&source).await.is_ok());
+
+        assert_eq!(source.health().state, CircuitState::Closed);
+        // 恢复后冷却时间重置
+        stub.respond_with(Some(upstream_error));
+        for _ in 0..3 {
+            let _ = fetch(&source).await;
+        }
+        assert_eq!(source.health().retry_after_secs, Some(30));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn client_errors_are_excluded_from_health() {
+        let (stub, source) = monitored();
+        stub.respond_with(Some(upstream_error));
+        for _ in 0..2 {
+            let _ = fetch(&source).await;
+        }
+        stub.respond_with(Some(client_error));
+        for _ in 0..5 {
+            assert!(matches!(
+                fetch(&source).await,
+                Err(MarketDataError::InvalidSymbol(_))
+            ));
+        }
+
+        let health = source.health();
+        assert_eq!(health.state, CircuitState::Closed);
+        assert_eq!(health.window_requests, 2);
+        assert_eq!(health.consecutive_failures, 2);
+
+        stub.respond_with(Some(upstream_error));
+        let _ = fetch(&source).await;
+        assert_eq!(source.health().state, CircuitState::Open);
+
+        // 探测请求遇到参数错误时释放探测名额，下一个请求可以继续探测
+        tokio::time::advance(Duration::from_secs(30)).await;
+        stub.respond_with(Some(client_error));
+        assert!(fetch(&source).await.is_err());
+        assert_eq!(source.health().state, CircuitState::HalfOpen);
+        stub.respond_with(None);
+        assert!(fetch(&source).await.is_ok());
+        assert_eq!(source.health().state, CircuitState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn perpetual_failures_do_not_open_spot_circuit() {
+        let (stub, source) = monitored();
+        let symbol = Symbol::parse("BTC/USDT").unwrap();
+        for _ in 0..3 {
+            assert!(source.fetch_funding_rate(&symbol).await.is_err());
+        }
+
+        let health = source.health();
+        assert_eq!(health.state, CircuitState::Closed);
+        let perpetual = health.perpetual.unwrap();
+        assert_eq!(perpetual.group, EndpointGroup::Perpetual);
+        assert_eq!(perpetual.state, CircuitState::Open);
+        assert!(matches!(
+            source.fetch_funding_rate(&symbol).await,
+            Err(MarketDataError::CircuitOpen { .. })
+        ));
+        // 现货请求照常发出
+        assert!(fetch(&source).await.is_ok());
+        assert_eq!(stub.calls(), 4);
+    }
+}
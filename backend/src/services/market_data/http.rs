@@ -0,0 +1,227 @@
+use reqwest::StatusCode;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::decimal;
+use super::rate_limit::RateLimiter;
+use super::replay::HttpMode;
+use super::{MarketDataError, OrderBookLevel};
+
+/// 错误响应体最多保留的字符数
+const ERROR_BODY_MAX_CHARS: usize = 200;
+
+/// 上游请求超时时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// 单个主机保留的空闲连接数
+const POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+/// 数据源HTTP客户端的代理与TLS配置
+///
+/// - `MARKET_PROXY`：全部数据源使用的代理，支持 `http://`、`https://`、`socks5://`、`socks5h://`，
+///   未设置时依次读取 `HTTPS_PROXY`、`HTTP_PROXY`
+/// - `MARKET_PROXY_<数据源>`：单个数据源的代理（如 `MARKET_PROXY_OKX`），设为 `direct` 表示直连
+/// - `MARKET_NO_PROXY`：不走代理的主机列表，逗号分隔，未设置时读取 `NO_PROXY`
+/// - `MARKET_TLS_INSECURE=true`：关闭证书校验，仅用于调试会替换证书的代理
+#[derive(Debug, Clone, Default)]
+pub struct HttpClientConfig {
+    pub proxy: Option<String>,
+    pub no_proxy: Option<String>,
+    pub accept_invalid_certs: bool,
+}
+
+impl HttpClientConfig {
+    /// 读取指定数据源的配置，数据源级别的代理优先于全局代理
+    pub fn from_env(exchange: &str) -> Self {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let source_var = format!("MARKET_PROXY_{}", exchange.to_ascii_uppercase());
+        let proxy = match var(&source_var) {
+            Some(proxy) if proxy.eq_ignore_ascii_case("direct") => None,
+            Some(proxy) => Some(proxy),
+            None => var("MARKET_PROXY")
+                .or_else(|| var("HTTPS_PROXY"))
+                .or_else(|| var("HTTP_PROXY")),
+        };
+
+        Self {
+            proxy,
+            no_proxy: var("MARKET_NO_PROXY").or_else(|| var("NO_PROXY")),
+            accept_invalid_certs: var("MARKET_TLS_INSECURE")
+                .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true")),
+        }
+    }
+
+    /// 创建带连接池的客户端，同一数据源的请求复用连接
+    pub fn build(&self, exchange: &'static str) -> Result<reqwest::Client, MarketDataError> {
+        // 不读取系统代理环境变量，代理只由本配置决定
+        let mut builder = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .user_agent("QuantConsole/1.0")
+            .no_proxy();
+
+        if let Some(proxy_url) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(MarketDataError::Client)?
+                .no_proxy(
+                    self.no_proxy
+                        .as_deref()
+                        .and_then(reqwest::NoProxy::from_string),
+                );
+            println!("🔧 {} 使用代理: {}", exchange, redact_proxy_url(proxy_url));
+            builder = builder.proxy(proxy);
+        }
+        if self.accept_invalid_certs {
+            println!("⚠️ {} 已关闭TLS证书校验（MARKET_TLS_INSECURE）", exchange);
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        builder.build().map_err(MarketDataError::Client)
+    }
+}
+
+/// 数据源的HTTP客户端及其请求模式（实时、录制或回放）
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    mode: HttpMode,
+}
+
+/// 按环境变量配置创建数据源共享的HTTP客户端，配置无效时启动失败
+pub fn build_client(exchange: &'static str, mode: &HttpMode) -> HttpClient {
+    let client = HttpClientConfig::from_env(exchange)
+        .build(exchange)
+        .unwrap_or_else(|e| panic!("{} 代理配置无效: {}", exchange, e));
+    HttpClient {
+        client,
+        mode: mode.clone(),
+    }
+}
+
+/// 隐藏代理地址中的用户名和密码
+fn redact_proxy_url(proxy_url: &str) -> String {
+    match reqwest::Url::parse(proxy_url) {
+        Ok(mut url) if !url.username().is_empty() || url.password().is_some() => {
+            let _ = url.set_username("***");
+            let _ = url.set_password(None);
+            url.to_string()
+        }
+        _ => proxy_url.to_string(),
+    }
+}
+
+/// 经过限频器发送GET请求并解析JSON，非2xx状态码视为错误，上游限频返回 `RateLimited`
+///
+/// 录制/回放模式（见 [`HttpMode`]）在这里统一处理，数据源适配器无需感知。
+pub async fn get_json(
+    client: &HttpClient,
+    limiter: &RateLimiter,
+    url: &str,
+) -> Result<Value, MarketDataError> {
+    let exchange = limiter.exchange();
+    let mode = &client.mode;
+    if let Some(recorded) = mode.replay(exchange, url).await {
+        let recorded = recorded?;
+        return match recorded.status() {
+            status if status.is_success() => Ok(recorded.body),
+            status => Err(status_error(exchange, status, recorded.text())),
+        };
+    }
+
+    limiter.acquire(url).await?;
+    let response = client
+        .client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| MarketDataError::request(exchange, e))?;
+
+    if let Some(pause) = limiter.observe(url, response.status(), response.headers()) {
+        return Err(MarketDataError::RateLimited {
+            exchange,
+            retry_after_ms: pause.as_millis() as u64,
+        });
+    }
+
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| MarketDataError::request(exchange, e))?;
+    mode.record(exchange, url, status, &body).await;
+
+    if !status.is_success() {
+        return Err(status_error(exchange, status, body));
+    }
+    serde_json::from_str(&body)
+        .map_err(|e| MarketDataError::parse(exchange, format!("响应不是有效的JSON: {}", e)))
+}
+
+fn status_error(exchange: &'static str, status: StatusCode, mut body: String) -> MarketDataError {
+    if let Some((index, _)) = body.char_indices().nth(ERROR_BODY_MAX_CHARS) {
+        body.truncate(index);
+    }
+    MarketDataError::Status {
+        exchange,
+        status,
+        body,
+    }
+}
+
+/// 解析交易所以字符串形式返回的小数字段，保留原始精度
+pub fn str_decimal(
+    value: &Value,
+    exchange: &'static str,
+    field: &str,
+) -> Result<Decimal, MarketDataError> {
+    value
+        .as_str()
+        .and_then(decimal::parse)
+        .ok_or_else(|| MarketDataError::parse(exchange, format!("无效{}", field)))
+}
+
+/// 解析可选的字符串小数字段，空字符串视为缺失
+pub fn opt_str_decimal(value: &Value) -> Option<Decimal> {
+    value.as_str().and_then(decimal::parse)
+}
+
+/// 解析以 JSON 数字（或字符串）返回的小数字段
+pub fn num_decimal(
+    value: &Value,
+    exchange: &'static str,
+    field: &str,
+) -> Result<Decimal, MarketDataError> {
+    decimal::from_json(value)
+        .ok_or_else(|| MarketDataError::parse(exchange, format!("无效{}", field)))
+}
+
+/// 解析字符串形式的整数字段（如毫秒时间戳）
+pub fn str_i64(value: &Value, exchange: &'static str, field: &str) -> Result<i64, MarketDataError> {
+    value
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| MarketDataError::parse(exchange, format!("无效{}", field)))
+}
+
+/// 解析 `[[price, amount, ...], ...]` 形式的订单簿档位
+pub fn parse_levels(
+    value: &Value,
+    exchange: &'static str,
+) -> Result<Vec<OrderBookLevel>, MarketDataError> {
+    value
+        .as_array()
+        .ok_or_else(|| MarketDataError::parse(exchange, "无效的订单簿格式"))?
+        .iter()
+        .map(|level| {
+            Ok(OrderBookLevel {
+                price: str_decimal(&level[0], exchange, "档位价格")?,
+                amount: str_decimal(&level[1], exchange, "档位数量")?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+/// 单个指标允许的最大周期
+const MAX_PERIOD: usize = 1000;
+
+/// 技术指标类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndicatorKind {
+    /// 简单移动平均
+    Sma,
+    /// 指数移动平均
+    Ema,
+    /// 相对强弱指数（Wilder 平滑）
+    Rsi,
+}
+
+/// 指标及其周期，文本形式为 `sma_20`、`ema_50`、`rsi_14`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndicatorSpec {
+    pub kind: IndicatorKind,
+    pub period: usize,
+}
+
+impl IndicatorSpec {
+    pub fn parse(input: &str) -> Option<Self> {
+        let (kind, period) =
+            input
+                .trim()
+                .to_ascii_lowercase()
+                .split_once('_')
+                .and_then(|(kind, period)| {
+                    let kind = match kind {
+                        "sma" => IndicatorKind::Sma,
+                        "ema" => IndicatorKind::Ema,
+                        "rsi" => IndicatorKind::Rsi,
+                        _ => return None,
+                    };
+                    Some((kind, period.parse::<usize>().ok()?))
+                })?;
+        (1..=MAX_PERIOD)
+            .contains(&period)
+            .then_some(Self { kind, period })
+    }
+
+    /// 解析逗号分隔的指标列表，返回无法识别的项
+    pub fn parse_list(input: &str) -> Result<Vec<Self>, String> {
+        input
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| Self::parse(s).ok_or_else(|| s.to_string()))
+            .collect()
+    }
+}
+
+impl fmt::Display for IndicatorSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            IndicatorKind::Sma => "sma",
+            IndicatorKind::Ema => "ema",
+            IndicatorKind::Rsi => "rsi",
+        };
+        write!(f, "{}_{}", kind, self.period)
+    }
+}
+
+/// 按收盘价逐根计算的指标，只保留计算所需的窗口，适合流式处理
+///
+/// 数据不足一个周期时返回 `None`。
+pub struct Indicator {
+    spec: IndicatorSpec,
+    window: VecDeque<f64>,
+    sum: f64,
+    /// EMA 的当前值，或 RSI 的平均涨幅
+    average: Option<f64>,
+    /// RSI 的平均跌幅
+    average_loss: f64,
+    previous_close: Option<f64>,
+}
+
+impl Indicator {
+    pub fn new(spec: IndicatorSpec) -> Self {
+        Self {
+            spec,
+            window: VecDeque::with_capacity(spec.period),
+            sum: 0.0,
+            average: None,
+            average_loss: 0.0,
+            previous_close: None,
+        }
+    }
+
+    pub fn spec(&self) -> IndicatorSpec {
+        self.spec
+    }
+
+    pub fn next(&mut self, close: f64) -> Option<f64> {
+        match self.spec.kind {
+            IndicatorKind::Sma => self.next_sma(close),
+            IndicatorKind::Ema => self.next_ema(close),
+            IndicatorKind::Rsi => self.next_rsi(close),
+        }
+    }
+
+    fn next_sma(&mut self, close: f64) -> Option<f64> {
+        self.window.push_back(close);
+        self.sum += close;
+        if self.window.len() > self.spec.period {
+            self.sum -= self.window.pop_front().unwrap_or_default();
+        }
+        (self.window.len() == self.spec.period).then(|| self.sum / self.spec.period as f64)
+    }
+
+    /// 以前 `period` 根的简单平均作为初值
+    fn next_ema(&mut self, close: f64) -> Option<f64> {
+        if let Some(previous) = self.average {
+            let alpha = 2.0 / (self.spec.period as f64 + 1.0);
+            let ema = previous + alpha * (close - previous);
+            self.average = Some(ema);
+            return Some(ema);
+        }
+        self.average = self.next_sma(close);
+        self.average
+    }
+
+    /// 前 `period` 个涨跌幅取简单平均，之后按 Wilder 方法平滑
+    fn next_rsi(&mut self, close: f64) -> Option<f64> {
+        let previous = self.previous_close.replace(close)?;
+        let change = close - previous;
+        let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+        let period = self.spec.period as f64;
+
+        let (average_gain, average_loss) = match self.average {
+            Some(average_gain) => (
+                (average_gain * (period - 1.0) + gain) / period,
+                (self.average_loss * (period - 1.0) + loss) / period,
+            ),
+            None => {
+                self.window.push_back(gain);
+                self.sum += gain;
+                self.average_loss += loss;
+                if self.window.len() < self.spec.period {
+                    return None;
+                }
+                (self.sum / period, self.average_loss / period)
+            }
+        };
+        self.average = Some(average_gain);
+        self.average_loss = average_loss;
+
+        Some(if average_loss == 0.0 {
+            // 没有下跌时为100，完全横盘时取中性值
+            if average_gain == 0.0 {
+                50.0
+            } else {
+                100.0
+            }
+        } else {
+            100.0 - 100.0 / (1.0 + average_gain / average_loss)
+        })
+    }
+}
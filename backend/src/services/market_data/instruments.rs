@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::{
+    Instrument, InstrumentStatus, MarketDataError, MarketDataRegistry, MarketDataSource, Symbol,
+};
+
+/// 默认快照目录
+const DEFAULT_SNAPSHOT_DIR: &str = "./data/instruments";
+/// 默认刷新周期（秒）
+const DEFAULT_REFRESH_SECS: u64 = 3600;
+
+/// 磁盘上的交易对快照，每个交易所一个文件（`<exchange>.json`）
+#[derive(Debug, Serialize, Deserialize)]
+struct InstrumentSnapshot {
+    exchange: String,
+    /// 快照时间（毫秒时间戳）
+    updated_at: i64,
+    instruments: Vec<Instrument>,
+}
+
+struct ExchangeInstruments {
+    updated_at: i64,
+    instruments: Vec<Instrument>,
+}
+
+/// 同一交易对在各交易所的汇总，用于 `/symbols` 搜索结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolSummary {
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    /// 任一交易所可交易即为 `trading`
+    pub status: InstrumentStatus,
+    /// 上架该交易对的交易所
+    pub sources: Vec<String>,
+    pub instruments: Vec<Instrument>,
+}
+
+/// 交易对注册表，从交易所元数据接口加载，并缓存为磁盘快照
+///
+/// 接口不可用时使用上次的快照；不提供交易对接口的交易所可直接放置快照文件。
+pub struct InstrumentRegistry {
+    snapshot_dir: PathBuf,
+    exchanges: RwLock<BTreeMap<String, ExchangeInstruments>>,
+}
+
+impl InstrumentRegistry {
+    pub fn new(snapshot_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            snapshot_dir: snapshot_dir.into(),
+            exchanges: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// 快照目录由 `INSTRUMENT_SNAPSHOT_DIR` 指定
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("INSTRUMENT_SNAPSHOT_DIR")
+                .unwrap_or_else(|_| DEFAULT_SNAPSHOT_DIR.to_string()),
+        )
+    }
+
+    /// 加载快照目录中的全部快照，返回加载的交易对数量
+    pub async fn load_snapshots(&self) -> usize {
+        let mut entries = match tokio::fs::read_dir(&self.snapshot_dir).await {
+            Ok(entries) => entries,
+            Err(_) => return 0,
+        };
+
+        let mut loaded = 0;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let snapshot = tokio::fs::read(&path)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| {
+                    serde_json::from_slice::<InstrumentSnapshot>(&bytes).map_err(|e| e.to_string())
+                });
+            match snapshot {
+                Ok(snapshot) => {
+                    loaded += snapshot.instruments.len();
+                    self.replace(snapshot.exchange, snapshot.updated_at, snapshot.instruments);
+                }
+                Err(e) => log::warn!("交易对快照 {} 读取失败: {}", path.display(), e),
+            }
+        }
+        loaded
+    }
+
+    /// 从所有数据源重新拉取交易对，成功的写入快照；失败时保留现有数据
+    pub async fn refresh(&self, sources: &MarketDataRegistry) {
+        for source in sources.sources() {
+            match source.fetch_instruments().await {
+                Ok(instruments) if !instruments.is_empty() => {
+                    let updated_at = chrono::Utc::now().timestamp_millis();
+                    if let Err(e) = self
+                        .save_snapshot(source.name(), updated_at, &instruments)
+                        .await
+                    {
+                        log::warn!("交易对快照 {} 写入失败: {}", source.name(), e);
+                    }
+                    self.replace(source.name().to_string(), updated_at, instruments);
+                }
+                Ok(_) | Err(MarketDataError::Unsupported { .. }) => {}
+                Err(e) => println!("⚠️ {} 交易对列表刷新失败，沿用缓存: {}", source.name(), e),
+            }
+        }
+    }
+
+    /// 启动后台任务：先加载磁盘快照，再按 `INSTRUMENT_REFRESH_SECS` 周期刷新
+    pub fn spawn_refresh(self: Arc<Self>, sources: Arc<MarketDataRegistry>) {
+        let period = std::env::var("INSTRUMENT_REFRESH_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_SECS);
+
+        tokio::spawn(async move {
+            let loaded = self.load_snapshots().await;
+            log::info!("已从快照加载 {} 个交易对", loaded);
+
+            let mut ticker = tokio::time::interval(Duration::from_secs(period.max(60)));
+            loop {
+                ticker.tick().await;
+                self.refresh(&sources).await;
+                log::info!("交易对注册表已刷新，共 {} 个交易对", self.len());
+            }
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.exchanges
+            .read()
+            .unwrap()
+            .values()
+            .map(|e| e.instruments.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 各交易所最近一次更新时间（毫秒时间戳）
+    pub fn updated_at(&self) -> BTreeMap<String, i64> {
+        self.exchanges
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(exchange, e)| (exchange.clone(), e.updated_at))
+            .collect()
+    }
+
+    /// 查找交易对在指定交易所的元数据
+    pub fn find(&self, exchange: &str, symbol: &Symbol) -> Option<Instrument> {
+        self.exchanges
+            .read()
+            .unwrap()
+            .get(exchange)?
+            .instruments
+            .iter()
+            .find(|i| {
+                i.base.eq_ignore_ascii_case(&symbol.base)
+                    && i.quote.eq_ignore_ascii_case(&symbol.quote)
+            })
+            .cloned()
+    }
+
+    /// 解析用户输入的交易对，只写基础币或不带分隔符时参考已加载的交易对（见 [`Symbol::parse_with`]）
+    pub fn resolve_symbol(&self, input: &str) -> Result<Symbol, MarketDataError> {
+        let exchanges = self.exchanges.read().unwrap();
+        let instruments = || exchanges.values().flat_map(|e| &e.instruments);
+        Symbol::parse_with(
+            input,
+            |base| instruments().any(|i| i.base.eq_ignore_ascii_case(base)),
+            |base, quote| {
+                instruments().any(|i| {
+                    i.base.eq_ignore_ascii_case(base) && i.quote.eq_ignore_ascii_case(quote)
+                })
+            },
+        )
+    }
+
+    /// 按关键字搜索交易对并按统一符号汇总
+    ///
+    /// 关键字忽略大小写和分隔符（`BTC`、`btcusdt`、`BTC-USDT` 均可），
+    /// 基础币完全匹配的排在前面，其次是上架交易所多的交易对。
+    pub fn search(
+        &self,
+        q: Option<&str>,
+        exchange: Option<&str>,
+        limit: usize,
+    ) -> Vec<SymbolSummary> {
+        let needle = q.map(normalize_keyword).unwrap_or_default();
+        let exchanges = self.exchanges.read().unwrap();
+
+        let mut grouped: BTreeMap<String, SymbolSummary> = BTreeMap::new();
+        let matching = exchanges
+            .iter()
+            .filter(|(name, _)| exchange.is_none_or(|e| e.eq_ignore_ascii_case(name)))
+            .flat_map(|(_, e)| &e.instruments)
+            .filter(|i| {
+                needle.is_empty()
+                    || normalize_keyword(&i.symbol).contains(&needle)
+                    || normalize_keyword(&i.exchange_symbol).contains(&needle)
+            });
+        for instrument in matching {
+            let summary =
+                grouped
+                    .entry(instrument.symbol.clone())
+                    .or_insert_with(|| SymbolSummary {
+                        symbol: instrument.symbol.clone(),
+                        base: instrument.base.clone(),
+                        quote: instrument.quote.clone(),
+                        status: instrument.status,
+                        sources: Vec::new(),
+                        instruments: Vec::new(),
+                    });
+            if instrument.is_trading() {
+                summary.status = InstrumentStatus::Trading;
+            }
+            summary.sources.push(instrument.exchange.clone());
+            summary.instruments.push(instrument.clone());
+        }
+
+        let mut results: Vec<SymbolSummary> = grouped.into_values().collect();
+        results.sort_by_key(|s| {
+            (
+                s.base != needle,
+                s.status != InstrumentStatus::Trading,
+                std::cmp::Reverse(s.sources.len()),
+            )
+        });
+        results.truncate(limit);
+        results
+    }
+
+    fn replace(&self, exchange: String, updated_at: i64, instruments: Vec<Instrument>) {
+        self.exchanges.write().unwrap().insert(
+            exchange,
+            ExchangeInstruments {
+                updated_at,
+                instruments,
+            },
+        );
+    }
+
+    async fn save_snapshot(
+        &self,
+        exchange: &str,
+        updated_at: i64,
+        instruments: &[Instrument],
+    ) -> std::io::Result<()> {
+        let snapshot = InstrumentSnapshot {
+            exchange: exchange.to_string(),
+            updated_at,
+            instruments: instruments.to_vec(),
+        };
+        let bytes = serde_json::to_vec_pretty(&snapshot)?;
+        tokio::fs::create_dir_all(&self.snapshot_dir).await?;
+        // 先写临时文件再重命名，避免进程中断留下不完整的快照
+        let path = self.snapshot_dir.join(format!("{}.json", exchange));
+        let tmp_path = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, bytes).await?;
+        tokio::fs::rename(&tmp_path, &path).await
+    }
+}
+
+/// 去掉分隔符并转为大写
+fn normalize_keyword(keyword: &str) -> String {
+    keyword
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
@@ -0,0 +1,243 @@
+use chrono::{DateTime, Datelike, NaiveDate};
+use std::fmt;
+
+use super::MarketDataError;
+
+const MINUTE_MS: i64 = 60_000;
+const HOUR_MS: i64 = 60 * MINUTE_MS;
+const DAY_MS: i64 = 24 * HOUR_MS;
+const WEEK_MS: i64 = 7 * DAY_MS;
+/// 1970-01-05（周一）相对纪元的偏移，周线按周一 00:00 UTC 对齐
+const FIRST_MONDAY_MS: i64 = 4 * DAY_MS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalUnit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+/// K线周期，如 `15m`、`4h`、`1d`、`1w`、`1M`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub count: u32,
+    pub unit: IntervalUnit,
+}
+
+impl Interval {
+    /// 解析时间间隔，兼容 `hourly`、`1min`、`1H` 等写法；`M` 表示月，`m` 表示分钟
+    pub fn parse(interval: &str) -> Option<Self> {
+        let interval = match interval {
+            "hourly" => "1h",
+            "daily" => "1d",
+            "weekly" => "1w",
+            "monthly" => "1M",
+            other => other,
+        };
+        let interval = interval.strip_suffix("in").unwrap_or(interval);
+
+        let unit_char = interval.chars().last()?;
+        let count: u32 = interval[..interval.len() - unit_char.len_utf8()]
+            .parse()
+            .ok()?;
+        if count == 0 {
+            return None;
+        }
+
+        let unit = match unit_char {
+            'm' => IntervalUnit::Minute,
+            'h' | 'H' => IntervalUnit::Hour,
+            'd' | 'D' => IntervalUnit::Day,
+            'w' | 'W' => IntervalUnit::Week,
+            'M' => IntervalUnit::Month,
+            _ => return None,
+        };
+
+        // 统一写法，如 60m -> 1h、24h -> 1d
+        let (count, unit) = match unit {
+            IntervalUnit::Minute if count.is_multiple_of(60) => (count / 60, IntervalUnit::Hour),
+            _ => (count, unit),
+        };
+        let (count, unit) = match unit {
+            IntervalUnit::Hour if count.is_multiple_of(24) => (count / 24, IntervalUnit::Day),
+            _ => (count, unit),
+        };
+
+        Some(Self { count, unit })
+    }
+
+    /// 固定长度周期的毫秒数，按月的周期返回 `None`
+    pub fn millis(&self) -> Option<i64> {
+        let unit_ms = match self.unit {
+            IntervalUnit::Minute => MINUTE_MS,
+            IntervalUnit::Hour => HOUR_MS,
+            IntervalUnit::Day => DAY_MS,
+            IntervalUnit::Week => WEEK_MS,
+            IntervalUnit::Month => return None,
+        };
+        Some(unit_ms * i64::from(self.count))
+    }
+
+    /// 近似毫秒数（月按31天计），用于估算需要的K线数量
+    pub fn approx_millis(&self) -> i64 {
+        self.millis().unwrap_or(31 * DAY_MS * i64::from(self.count))
+    }
+
+    /// 时间戳所在周期的开始时间（UTC 对齐：周线从周一开始，月线从每月1日开始）
+    pub fn bucket_start(&self, timestamp: i64) -> i64 {
+        match self.unit {
+            IntervalUnit::Month => {
+                let index = month_index(timestamp);
+                let index = index - index.rem_euclid(i64::from(self.count));
+                month_start(index)
+            }
+            IntervalUnit::Week => {
+                let width = WEEK_MS * i64::from(self.count);
+                timestamp - (timestamp - FIRST_MONDAY_MS).rem_euclid(width)
+            }
+            _ => {
+                let width = self.millis().unwrap_or(MINUTE_MS);
+                timestamp - timestamp.rem_euclid(width)
+            }
+        }
+    }
+
+    /// 下一个周期的开始时间
+    pub fn next_bucket(&self, timestamp: i64) -> i64 {
+        let start = self.bucket_start(timestamp);
+        match self.millis() {
+            Some(width) => start + width,
+            None => month_start(month_index(start) + i64::from(self.count)),
+        }
+    }
+
+    /// 能否用当前周期的K线无缝拼接出 `target` 周期（边界对齐且整除）
+    pub fn divides(&self, target: &Interval) -> bool {
+        match (self.unit, target.unit) {
+            (IntervalUnit::Month, IntervalUnit::Month) => target.count.is_multiple_of(self.count),
+            (IntervalUnit::Month, _) => false,
+            (IntervalUnit::Week, IntervalUnit::Week) => target.count.is_multiple_of(self.count),
+            (IntervalUnit::Week, _) => false,
+            // 周线和月线的边界都落在自然日上，需要能整除一天的基础周期
+            (_, IntervalUnit::Week) | (_, IntervalUnit::Month) => {
+                self.millis().is_some_and(|ms| DAY_MS % ms == 0)
+            }
+            _ => match (self.millis(), target.millis()) {
+                (Some(base), Some(target)) => target % base == 0,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            IntervalUnit::Minute => "m",
+            IntervalUnit::Hour => "h",
+            IntervalUnit::Day => "d",
+            IntervalUnit::Week => "w",
+            IntervalUnit::Month => "M",
+        };
+        write!(f, "{}{}", self.count, unit)
+    }
+}
+
+/// 按交易所的周期映射表转换时间间隔，不在表中的周期返回错误
+pub fn map_interval(
+    exchange: &'static str,
+    table: &[(&'static str, &'static str)],
+    interval: &str,
+) -> Result<&'static str, MarketDataError> {
+    let canonical = Interval::parse(interval).map(|i| i.to_string());
+    table
+        .iter()
+        .find(|(standard, _)| Some(*standard) == canonical.as_deref())
+        .map(|(_, native)| *native)
+        .ok_or_else(|| MarketDataError::UnsupportedInterval {
+            exchange,
+            interval: interval.to_string(),
+        })
+}
+
+/// 自1970年1月起的月份序号
+fn month_index(timestamp: i64) -> i64 {
+    let date = DateTime::from_timestamp_millis(timestamp)
+        .unwrap_or_default()
+        .date_naive();
+    (i64::from(date.year()) - 1970) * 12 + i64::from(date.month0())
+}
+
+fn month_start(index: i64) -> i64 {
+    let year = 1970 + index.div_euclid(12);
+    let month = index.rem_euclid(12) + 1;
+    NaiveDate::from_ymd_opt(year as i32, month as u32, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc().timestamp_millis())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interval(s: &str) -> Interval {
+        Interval::parse(s).unwrap()
+    }
+
+    /// 2024-05-29（周三）13:45 UTC
+    const WEDNESDAY_MS: i64 = 1_716_990_300_000;
+
+    #[test]
+    fn normalizes_interval_spellings() {
+        assert_eq!(interval("60m"), interval("1h"));
+        assert_eq!(interval("24h"), interval("1d"));
+        assert_eq!(interval("1H").to_string(), "1h");
+        assert_eq!(interval("1min").to_string(), "1m");
+        assert_eq!(interval("monthly").to_string(), "1M");
+        assert_eq!(Interval::parse("0h"), None);
+    }
+
+    #[test]
+    fn week_buckets_start_on_monday() {
+        // 2024-05-27（周一）00:00 UTC
+        assert_eq!(interval("1w").bucket_start(WEDNESDAY_MS), 1_716_768_000_000);
+        assert_eq!(
+            interval("1w").next_bucket(WEDNESDAY_MS),
+            1_716_768_000_000 + WEEK_MS
+        );
+        // 纪元当天是周四，归入 1969-12-29 周一开始的周
+        assert_eq!(interval("1w").bucket_start(0), FIRST_MONDAY_MS - WEEK_MS);
+    }
+
+    #[test]
+    fn month_buckets_follow_calendar_months() {
+        // 2024-05-01 00:00 UTC
+        assert_eq!(interval("1M").bucket_start(WEDNESDAY_MS), 1_714_521_600_000);
+        // 2024-06-01 00:00 UTC
+        assert_eq!(interval("1M").next_bucket(WEDNESDAY_MS), 1_717_200_000_000);
+        // 季度从 1、4、7、10 月开始：2024-04-01
+        assert_eq!(interval("3M").bucket_start(WEDNESDAY_MS), 1_711_929_600_000);
+        // 跨年：2024-01-01
+        assert_eq!(
+            interval("3M").next_bucket(1_703_980_800_000),
+            1_704_067_200_000
+        );
+    }
+
+    #[test]
+    fn divides_requires_aligned_boundaries() {
+        assert!(interval("1m").divides(&interval("7m")));
+        assert!(interval("1h").divides(&interval("4h")));
+        assert!(!interval("1h").divides(&interval("90m")));
+        assert!(interval("1d").divides(&interval("1w")));
+        assert!(interval("1d").divides(&interval("1M")));
+        assert!(interval("1M").divides(&interval("3M")));
+        assert!(!interval("1w").divides(&interval("1M")));
+        assert!(!interval("1M").divides(&interval("1w")));
+        // 7小时不能整除一天，拼不出周线
+        assert!(!interval("7h").divides(&interval("1w")));
+    }
+}
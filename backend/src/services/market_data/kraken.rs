@@ -0,0 +1,458 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::http::{build_client, get_json, opt_str_decimal, parse_levels, str_decimal, HttpClient};
+use super::interval::map_interval;
+use super::rate_limit::{BucketSpec, RateLimitRules, RateLimiter, RequestCost};
+use super::replay::HttpMode;
+use super::{
+    Instrument, InstrumentStatus, KlineData, KlineQuery, MarketDataError, MarketDataSource,
+    OrderBook, PublicTrade, SourceCapabilities, Symbol, Ticker, TradeQuery, TradeSide,
+};
+
+const NAME: &str = "kraken";
+const BASE_URL: &str = "https://api.kraken.com";
+/// 标准周期 -> Kraken interval（分钟）
+///
+/// Kraken 的周线按纪元起的整周（周四）对齐，与统一的周一对齐不一致，由日线重采样得到。
+const INTERVALS: &[(&str, &str)] = &[
+    ("1m", "1"),
+    ("5m", "5"),
+    ("15m", "15"),
+    ("30m", "30"),
+    ("1h", "60"),
+    ("4h", "240"),
+    ("1d", "1440"),
+];
+/// `/0/public/OHLC` 最多返回最近的720根，不支持更早的区间
+const MAX_LIMIT: u32 = 720;
+/// `/0/public/Depth` 每侧最多返回500档
+const MAX_DEPTH: u32 = 500;
+/// `/0/public/Trades` 单次最多返回1000条
+const MAX_TRADES: u32 = 1000;
+/// 交易对不存在的错误信息
+const UNKNOWN_PAIR_ERROR: &str = "Unknown asset pair";
+
+/// 公共接口建议每秒不超过1次
+static RATE_LIMITS: RateLimitRules = RateLimitRules {
+    buckets: &[BucketSpec::new("public", 1, Duration::from_secs(1))],
+    cost: |_| RequestCost::new("public", 1),
+    used_weight_headers: &[],
+};
+
+pub struct KrakenSource {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl KrakenSource {
+    pub fn new(mode: &HttpMode) -> Self {
+        Self {
+            client: build_client(NAME, mode),
+            limiter: RateLimiter::new(NAME, &RATE_LIMITS),
+        }
+    }
+
+    /// 检查 Kraken 响应的 error 数组并取出 result 字段
+    fn unwrap_result(data: &Value) -> Result<&Value, MarketDataError> {
+        let errors: Vec<&str> = data["error"]
+            .as_array()
+            .map(|errors| errors.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if !errors.is_empty() {
+            return Err(MarketDataError::api(NAME, errors.join("; ")));
+        }
+        Ok(&data["result"])
+    }
+
+    /// 请求公共接口并取出交易对对应的数据
+    async fn get_pair(&self, url: &str, symbol: &Symbol) -> Result<Value, MarketDataError> {
+        let data = get_json(&self.client, &self.limiter, url).await?;
+        let result = Self::unwrap_result(&data).map_err(|e| match &e {
+            MarketDataError::Api { message, .. } if message.contains(UNKNOWN_PAIR_ERROR) => {
+                MarketDataError::unknown_symbol(NAME, symbol)
+            }
+            _ => e,
+        })?;
+        pair_entry(result).cloned()
+    }
+}
+
+impl Default for KrakenSource {
+    fn default() -> Self {
+        Self::new(&HttpMode::Live)
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for KrakenSource {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            klines: true,
+            ticker: true,
+            order_book: true,
+            trades: true,
+            native_ohlc: true,
+            base_volume: true,
+            max_klines_per_request: MAX_LIMIT,
+            history_page_size: None,
+            perpetuals: false,
+        }
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        Some(&self.limiter)
+    }
+
+    fn supported_intervals(&self) -> Vec<&'static str> {
+        INTERVALS.iter().map(|(standard, _)| *standard).collect()
+    }
+
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError> {
+        let interval = map_interval(NAME, INTERVALS, &query.interval)?;
+        let limit = query.limit.clamp(1, MAX_LIMIT) as usize;
+
+        let mut url = format!(
+            "{}/0/public/OHLC?pair={}&interval={}",
+            BASE_URL,
+            query.symbol.kraken(),
+            interval
+        );
+        // since 只返回晚于该时间（秒）的K线
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&since={}", start_time / 1000 - 1));
+        }
+
+        println!("🟤 Kraken请求: {}", url);
+
+        let rows = self.get_pair(&url, &query.symbol).await?;
+        let mut klines = parse_ohlc(&rows)?;
+        klines.retain(|k| {
+            query.start_time.is_none_or(|start| k.timestamp >= start)
+                && query.end_time.is_none_or(|end| k.timestamp <= end)
+        });
+        if query.start_time.is_some() {
+            klines.truncate(limit);
+        } else {
+            let skip = klines.len().saturating_sub(limit);
+            klines.drain(..skip);
+        }
+
+        println!("✅ Kraken成功获取 {} 个K线数据点", klines.len());
+        Ok(klines)
+    }
+
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        let url = format!("{}/0/public/Ticker?pair={}", BASE_URL, symbol.kraken());
+
+        let ticker = self.get_pair(&url, symbol).await?;
+        parse_ticker(&ticker, symbol)
+    }
+
+    async fn fetch_order_book(
+        &self,
+        symbol: &Symbol,
+        depth: u32,
+    ) -> Result<OrderBook, MarketDataError> {
+        let url = format!(
+            "{}/0/public/Depth?pair={}&count={}",
+            BASE_URL,
+            symbol.kraken(),
+            depth.clamp(1, MAX_DEPTH)
+        );
+
+        let book = self.get_pair(&url, symbol).await?;
+        parse_order_book(&book, symbol)
+    }
+
+    /// 只能按时间向后翻页，不支持按游标获取更早的成交
+    async fn fetch_trades(&self, query: &TradeQuery) -> Result<Vec<PublicTrade>, MarketDataError> {
+        if query.before.is_some() {
+            return Err(MarketDataError::Unsupported {
+                exchange: NAME,
+                operation: "成交记录翻页",
+            });
+        }
+
+        let url = format!(
+            "{}/0/public/Trades?pair={}&count={}",
+            BASE_URL,
+            query.symbol.kraken(),
+            query.limit.clamp(1, MAX_TRADES)
+        );
+
+        let rows = self.get_pair(&url, &query.symbol).await?;
+        let mut trades = parse_trades(&rows, &query.symbol)?;
+        trades.truncate(query.limit as usize);
+        Ok(trades)
+    }
+
+    async fn fetch_instruments(&self) -> Result<Vec<Instrument>, MarketDataError> {
+        let url = format!("{}/0/public/AssetPairs", BASE_URL);
+
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        let instruments = parse_instruments(Self::unwrap_result(&data)?)?;
+
+        println!("✅ Kraken获取 {} 个现货交易对", instruments.len());
+        Ok(instruments)
+    }
+}
+
+/// result 以交易所内部的交易对名称（如 `XXBTZUSD`）为键，取第一个非 `last` 的条目
+fn pair_entry(result: &Value) -> Result<&Value, MarketDataError> {
+    result
+        .as_object()
+        .and_then(|entries| {
+            entries
+                .iter()
+                .find(|(key, _)| key.as_str() != "last")
+                .map(|(_, value)| value)
+        })
+        .ok_or_else(|| MarketDataError::parse(NAME, "无交易对数据"))
+}
+
+/// Kraken K线格式: [time(秒), open, high, low, close, vwap, volume, count]，按时间升序
+fn parse_ohlc(rows: &Value) -> Result<Vec<KlineData>, MarketDataError> {
+    rows.as_array()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?
+        .iter()
+        .map(|row| {
+            let candle = row
+                .as_array()
+                .filter(|c| c.len() >= 7)
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?;
+            Ok(KlineData {
+                timestamp: candle[0]
+                    .as_i64()
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效时间戳"))?
+                    * 1000,
+                open: str_decimal(&candle[1], NAME, "开盘价")?,
+                high: str_decimal(&candle[2], NAME, "最高价")?,
+                low: str_decimal(&candle[3], NAME, "最低价")?,
+                close: str_decimal(&candle[4], NAME, "收盘价")?,
+                volume: str_decimal(&candle[6], NAME, "成交量")?,
+                source: NAME.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 数组字段的第二项为最近24小时的统计；`o` 是当日（UTC）开盘价而非24小时前的价格，不用于计算涨跌
+fn parse_ticker(ticker: &Value, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+    let volume_24h = opt_str_decimal(&ticker["v"][1]);
+    let vwap_24h = opt_str_decimal(&ticker["p"][1]);
+
+    Ok(Ticker {
+        symbol: symbol.to_string(),
+        price: str_decimal(&ticker["c"][0], NAME, "最新价")?,
+        price_change_24h: None,
+        price_change_percent_24h: None,
+        high_24h: opt_str_decimal(&ticker["h"][1]),
+        low_24h: opt_str_decimal(&ticker["l"][1]),
+        volume_24h,
+        quote_volume_24h: volume_24h.zip(vwap_24h).map(|(volume, vwap)| volume * vwap),
+        market_cap: None,
+        // 行情接口不带时间，使用接收时间
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        source: NAME.to_string(),
+    })
+}
+
+/// 档位格式: [price, volume, timestamp]
+fn parse_order_book(book: &Value, symbol: &Symbol) -> Result<OrderBook, MarketDataError> {
+    Ok(OrderBook {
+        symbol: symbol.to_string(),
+        bids: parse_levels(&book["bids"], NAME)?,
+        asks: parse_levels(&book["asks"], NAME)?,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        source: NAME.to_string(),
+    })
+}
+
+/// 成交格式: [price, volume, time(秒，小数), side(b/s), 订单类型, misc, trade_id]，
+/// 按时间升序返回，转为从新到旧
+fn parse_trades(rows: &Value, symbol: &Symbol) -> Result<Vec<PublicTrade>, MarketDataError> {
+    rows.as_array()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无效的成交格式"))?
+        .iter()
+        .rev()
+        .map(|row| {
+            let trade = row
+                .as_array()
+                .filter(|t| t.len() >= 7)
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效的成交格式"))?;
+            Ok(PublicTrade {
+                id: trade[6]
+                    .as_u64()
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效成交ID"))?
+                    .to_string(),
+                symbol: symbol.to_string(),
+                side: match trade[3].as_str() {
+                    Some("s") => TradeSide::Sell,
+                    _ => TradeSide::Buy,
+                },
+                price: str_decimal(&trade[0], NAME, "成交价")?,
+                amount: str_decimal(&trade[1], NAME, "成交量")?,
+                timestamp: trade[2]
+                    .as_f64()
+                    .map(|secs| (secs * 1000.0).round() as i64)
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效时间戳"))?,
+                source: NAME.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 交易对名称以 `wsname`（如 `XBT/USD`）为准，跳过暗池交易对（`.d` 后缀）
+fn parse_instruments(result: &Value) -> Result<Vec<Instrument>, MarketDataError> {
+    let pairs = result
+        .as_object()
+        .ok_or_else(|| MarketDataError::parse(NAME, "无交易对数据"))?;
+
+    Ok(pairs
+        .values()
+        .filter_map(|info| {
+            let altname = info["altname"].as_str()?;
+            if altname.ends_with(".d") {
+                return None;
+            }
+            let (base, quote) = info["wsname"].as_str()?.split_once('/')?;
+            let symbol = Symbol::from_kraken(base, quote);
+            Some(Instrument {
+                symbol: symbol.to_string(),
+                base: symbol.base,
+                quote: symbol.quote,
+                exchange: NAME.to_string(),
+                exchange_symbol: altname.to_string(),
+                tick_size: opt_str_decimal(&info["tick_size"]),
+                lot_size: info["lot_decimals"]
+                    .as_u64()
+                    .and_then(|decimals| u32::try_from(decimals).ok())
+                    .filter(|decimals| *decimals <= 28)
+                    .map(|decimals| Decimal::new(1, decimals)),
+                min_size: opt_str_decimal(&info["ordermin"]),
+                status: match info["status"].as_str() {
+                    Some("online") => InstrumentStatus::Trading,
+                    _ => InstrumentStatus::Halted,
+                },
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::{dec, fixture};
+
+    fn pair(name: &str) -> Value {
+        let data = fixture("kraken", name);
+        pair_entry(KrakenSource::unwrap_result(&data).unwrap())
+            .unwrap()
+            .clone()
+    }
+
+    fn btc_usd() -> Symbol {
+        Symbol::new("BTC", "USD")
+    }
+
+    #[test]
+    fn parses_ohlc_skipping_last_cursor() {
+        let klines = parse_ohlc(&pair("ohlc")).unwrap();
+
+        assert_eq!(klines.len(), 3);
+        assert!(klines.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        let first = &klines[0];
+        assert_eq!(first.timestamp, 1_717_020_000_000);
+        assert_eq!(first.open, dec("67825.1"));
+        assert_eq!(first.high, dec("67905.0"));
+        assert_eq!(first.low, dec("67710.2"));
+        assert_eq!(first.close, dec("67858.0"));
+        // 第6列为 vwap，成交量在第7列
+        assert_eq!(first.volume, dec("45.12345678"));
+        assert_eq!(first.source, "kraken");
+    }
+
+    #[test]
+    fn parses_ticker_with_rolling_24h_stats() {
+        let ticker = parse_ticker(&pair("ticker"), &btc_usd()).unwrap();
+
+        assert_eq!(ticker.symbol, "BTC/USD");
+        assert_eq!(ticker.price, dec("68010.0"));
+        assert_eq!(ticker.price_change_24h, None);
+        assert_eq!(ticker.high_24h, Some(dec("68450.0")));
+        assert_eq!(ticker.low_24h, Some(dec("66880.0")));
+        assert_eq!(ticker.volume_24h, Some(dec("2500.0")));
+        assert_eq!(ticker.quote_volume_24h, Some(dec("169000000")));
+    }
+
+    #[test]
+    fn parses_order_book_levels() {
+        let book = parse_order_book(&pair("depth"), &btc_usd()).unwrap();
+
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(book.asks.len(), 2);
+        assert_eq!(book.bids[0].price, dec("68009.9"));
+        assert_eq!(book.bids[0].amount, dec("1.5"));
+        assert_eq!(book.asks[0].price, dec("68010.0"));
+        assert_eq!(book.asks[0].amount, dec("0.02"));
+    }
+
+    #[test]
+    fn parses_trades_newest_first() {
+        let trades = parse_trades(&pair("trades"), &btc_usd()).unwrap();
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].id, "70123457");
+        assert_eq!(trades[0].side, TradeSide::Sell);
+        assert_eq!(trades[0].timestamp, 1_717_023_600_512);
+        assert_eq!(trades[1].id, "70123456");
+        assert_eq!(trades[1].side, TradeSide::Buy);
+        assert_eq!(trades[1].price, dec("68009.9"));
+        assert_eq!(trades[1].amount, dec("0.125"));
+    }
+
+    #[test]
+    fn parses_instruments_with_standard_asset_codes() {
+        let mut instruments = parse_instruments(
+            KrakenSource::unwrap_result(&fixture("kraken", "asset_pairs")).unwrap(),
+        )
+        .unwrap();
+        instruments.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+        assert_eq!(instruments.len(), 2);
+        let btc = &instruments[0];
+        assert_eq!(btc.symbol, "BTC/USD");
+        assert_eq!(btc.exchange_symbol, "XBTUSD");
+        assert_eq!(btc.tick_size, Some(dec("0.1")));
+        assert_eq!(btc.lot_size, Some(dec("0.00000001")));
+        assert_eq!(btc.min_size, Some(dec("0.0001")));
+        assert!(btc.is_trading());
+        assert_eq!(instruments[1].symbol, "DOGE/USDT");
+        assert_eq!(instruments[1].status, InstrumentStatus::Halted);
+    }
+
+    #[test]
+    fn reports_api_errors() {
+        let error = KrakenSource::unwrap_result(&fixture("kraken", "error")).unwrap_err();
+        assert!(error.to_string().contains(UNKNOWN_PAIR_ERROR));
+    }
+
+    #[test]
+    fn maps_asset_codes() {
+        assert_eq!(Symbol::new("BTC", "USDT").kraken(), "XBTUSDT");
+        assert_eq!(Symbol::new("ETH", "BTC").kraken(), "ETHXBT");
+        assert_eq!(
+            Symbol::from_kraken("XDG", "USD"),
+            Symbol::new("DOGE", "USD")
+        );
+        assert_eq!(map_interval(NAME, INTERVALS, "4h").unwrap(), "240");
+        assert!(map_interval(NAME, INTERVALS, "1w").is_err());
+    }
+}
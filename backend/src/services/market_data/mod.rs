@@ -0,0 +1,429 @@
+pub mod basis;
+pub mod binance;
+pub mod bybit;
+pub mod chart;
+pub mod clock;
+pub mod coinbase;
+pub mod coingecko;
+pub mod compare;
+pub mod consensus;
+pub mod decimal;
+pub mod error;
+pub mod export;
+pub mod fear_greed;
+pub mod health;
+pub mod http;
+pub mod indicators;
+pub mod instruments;
+pub mod interval;
+pub mod kraken;
+pub mod okx;
+pub mod orderbook;
+pub mod pagination;
+pub mod quality;
+pub mod rate_limit;
+pub mod registry;
+pub mod replay;
+pub mod resample;
+pub mod stream;
+pub mod symbol;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod ticker;
+pub mod yahoo;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub use binance::BinanceSource;
+pub use bybit::BybitSource;
+pub use clock::Clock;
+pub use coinbase::CoinbaseSource;
+pub use coingecko::CoinGeckoSource;
+pub use error::MarketDataError;
+pub use instruments::InstrumentRegistry;
+pub use kraken::KrakenSource;
+pub use okx::OkxSource;
+pub use rate_limit::RateLimiter;
+pub use registry::MarketDataRegistry;
+pub use replay::HttpMode;
+pub use stream::MarketStream;
+pub use symbol::Symbol;
+pub use ticker::TickerService;
+pub use yahoo::YahooSource;
+
+/// K线，价格和成交量保留交易所返回的精确小数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KlineData {
+    pub timestamp: i64,
+    #[serde(with = "decimal")]
+    pub open: Decimal,
+    #[serde(with = "decimal")]
+    pub high: Decimal,
+    #[serde(with = "decimal")]
+    pub low: Decimal,
+    #[serde(with = "decimal")]
+    pub close: Decimal,
+    #[serde(with = "decimal")]
+    pub volume: Decimal,
+    pub source: String,
+}
+
+/// 24小时行情快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ticker {
+    pub symbol: String,
+    #[serde(with = "decimal")]
+    pub price: Decimal,
+    #[serde(with = "decimal::option", default)]
+    pub price_change_24h: Option<Decimal>,
+    #[serde(with = "decimal::option", default)]
+    pub price_change_percent_24h: Option<Decimal>,
+    #[serde(with = "decimal::option", default)]
+    pub high_24h: Option<Decimal>,
+    #[serde(with = "decimal::option", default)]
+    pub low_24h: Option<Decimal>,
+    #[serde(with = "decimal::option", default)]
+    pub volume_24h: Option<Decimal>,
+    #[serde(with = "decimal::option", default)]
+    pub quote_volume_24h: Option<Decimal>,
+    #[serde(with = "decimal::option", default)]
+    pub market_cap: Option<Decimal>,
+    pub timestamp: i64,
+    pub source: String,
+}
+
+/// 订单簿价位
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OrderBookLevel {
+    #[serde(with = "decimal")]
+    pub price: Decimal,
+    #[serde(with = "decimal")]
+    pub amount: Decimal,
+}
+
+/// 单个交易所的订单簿快照，买盘按价格降序、卖盘按价格升序
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBook {
+    pub symbol: String,
+    pub bids: Vec<OrderBookLevel>,
+    pub asks: Vec<OrderBookLevel>,
+    pub timestamp: i64,
+    pub source: String,
+}
+
+/// 主动成交方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// 公开成交记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicTrade {
+    /// 交易所的成交ID，同时作为翻页游标
+    pub id: String,
+    pub symbol: String,
+    /// 主动成交（taker）方向
+    pub side: TradeSide,
+    #[serde(with = "decimal")]
+    pub price: Decimal,
+    #[serde(with = "decimal")]
+    pub amount: Decimal,
+    pub timestamp: i64,
+    pub source: String,
+}
+
+/// 成交记录查询参数
+#[derive(Debug, Clone)]
+pub struct TradeQuery {
+    pub symbol: Symbol,
+    pub limit: u32,
+    /// 只返回早于该成交ID的记录，为空时返回最新成交
+    pub before: Option<String>,
+}
+
+/// 交易对状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstrumentStatus {
+    Trading,
+    /// 暂停交易
+    Halted,
+    /// 即将上线
+    PreTrading,
+    /// 已下架
+    Delisted,
+}
+
+/// 交易所上架的交易对元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instrument {
+    /// 统一格式的交易对，如 `BTC/USDT`
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    pub exchange: String,
+    /// 交易所原生代码，如 `BTC-USDT`、`BTCUSDT`
+    pub exchange_symbol: String,
+    /// 最小价格变动单位
+    #[serde(with = "decimal::option", default)]
+    pub tick_size: Option<Decimal>,
+    /// 最小数量变动单位
+    #[serde(with = "decimal::option", default)]
+    pub lot_size: Option<Decimal>,
+    /// 最小下单数量
+    #[serde(with = "decimal::option", default)]
+    pub min_size: Option<Decimal>,
+    pub status: InstrumentStatus,
+}
+
+impl Instrument {
+    pub fn is_trading(&self) -> bool {
+        self.status == InstrumentStatus::Trading
+    }
+}
+
+/// K线查询参数
+#[derive(Debug, Clone)]
+pub struct KlineQuery {
+    pub symbol: Symbol,
+    pub interval: String,
+    pub limit: u32,
+    /// 区间起点（毫秒，含），为空时返回最近的K线
+    pub start_time: Option<i64>,
+    /// 区间终点（毫秒，含）
+    pub end_time: Option<i64>,
+}
+
+/// 永续合约资金费率
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRate {
+    pub symbol: String,
+    #[serde(with = "decimal")]
+    pub funding_rate: Decimal,
+    /// 该费率的结算时间（毫秒），当前费率为下一次结算时间
+    pub funding_time: i64,
+    /// 再下一次结算时间，交易所不提供时为空
+    pub next_funding_time: Option<i64>,
+    pub source: String,
+}
+
+/// 资金费率历史查询参数
+#[derive(Debug, Clone)]
+pub struct FundingQuery {
+    pub symbol: Symbol,
+    pub limit: u32,
+    /// 结算时间区间起点（毫秒，含），为空时返回最近的记录
+    pub start_time: Option<i64>,
+    /// 结算时间区间终点（毫秒，含）
+    pub end_time: Option<i64>,
+}
+
+/// 永续合约持仓量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenInterest {
+    pub symbol: String,
+    pub timestamp: i64,
+    /// 以基础币计的持仓量
+    #[serde(with = "decimal")]
+    pub open_interest: Decimal,
+    /// 以计价币计的持仓价值
+    #[serde(with = "decimal::option", default)]
+    pub open_interest_value: Option<Decimal>,
+    pub source: String,
+}
+
+/// 永续合约标记价格与指数价格
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkPrice {
+    pub symbol: String,
+    #[serde(with = "decimal")]
+    pub mark_price: Decimal,
+    #[serde(with = "decimal::option", default)]
+    pub index_price: Option<Decimal>,
+    pub timestamp: i64,
+    pub source: String,
+}
+
+/// 全市场统计（美元计价）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalMarketMetrics {
+    pub total_market_cap: f64,
+    pub total_volume_24h: f64,
+    /// BTC 市值占总市值的百分比
+    pub btc_dominance: f64,
+    /// ETH 市值占总市值的百分比
+    pub eth_dominance: f64,
+    pub timestamp: i64,
+    pub source: String,
+}
+
+/// 衍生价格K线类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceKind {
+    /// 永续合约标记价格
+    Mark,
+    /// 现货指数价格
+    Index,
+}
+
+/// 数据源能力描述
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceCapabilities {
+    pub klines: bool,
+    pub ticker: bool,
+    pub order_book: bool,
+    pub trades: bool,
+    /// 是否提供真实的OHLC（而非由单一价格点构造）
+    pub native_ohlc: bool,
+    /// 成交量是否以基础币计价（可跨数据源比较和加权）
+    pub base_volume: bool,
+    /// 单次请求可获取的最大K线数量
+    pub max_klines_per_request: u32,
+    /// 按时间区间查询时每页的最大K线数量，`None` 表示不支持区间查询
+    pub history_page_size: Option<u32>,
+    /// 是否提供永续合约数据（资金费率、持仓量、标记/指数价格）
+    pub perpetuals: bool,
+}
+
+/// 市场数据源，每个交易所/数据提供方实现一次
+#[async_trait]
+pub trait MarketDataSource: Send + Sync {
+    /// 数据源标识，如 `okx`、`binance`
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> SourceCapabilities;
+
+    /// 上游请求的限频器，用于健康检查展示
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        None
+    }
+
+    /// 原生支持的标准时间间隔（如 `1m`、`1h`、`1d`），其余周期由这些周期重采样得到
+    fn supported_intervals(&self) -> Vec<&'static str>;
+
+    /// 当前时间为 `now` 时，从 `start_time` 开始按区间查询每页的最大K线数量（`step` 为周期毫秒数），
+    /// 默认为 `history_page_size`
+    ///
+    /// 近期K线与更早的历史K线走不同接口、每页数量不同的数据源（如 OKX）需覆盖。
+    fn history_page_size_from(&self, _start_time: i64, _step: i64, _now: i64) -> Option<u32> {
+        self.capabilities().history_page_size
+    }
+
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError>;
+
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError>;
+
+    /// 订单簿快照，`depth` 为每侧的最大档位数
+    async fn fetch_order_book(
+        &self,
+        _symbol: &Symbol,
+        _depth: u32,
+    ) -> Result<OrderBook, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "订单簿",
+        })
+    }
+
+    /// 公开成交记录，按时间从新到旧排列
+    async fn fetch_trades(&self, _query: &TradeQuery) -> Result<Vec<PublicTrade>, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "成交记录",
+        })
+    }
+
+    /// 现货交易对列表，不提供交易对元数据的数据源返回 `Unsupported`
+    async fn fetch_instruments(&self) -> Result<Vec<Instrument>, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "交易对列表",
+        })
+    }
+
+    // 以下为永续合约接口，`symbol` 指 USDT 本位永续合约的标的交易对（如 `BTC/USDT`）
+
+    /// 当前（预测）资金费率
+    async fn fetch_funding_rate(&self, _symbol: &Symbol) -> Result<FundingRate, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "资金费率",
+        })
+    }
+
+    /// 已结算的资金费率历史，按结算时间升序
+    async fn fetch_funding_history(
+        &self,
+        _query: &FundingQuery,
+    ) -> Result<Vec<FundingRate>, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "资金费率历史",
+        })
+    }
+
+    /// 当前持仓量
+    async fn fetch_open_interest(&self, _symbol: &Symbol) -> Result<OpenInterest, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "持仓量",
+        })
+    }
+
+    /// 持仓量历史，`query.interval` 为统计周期，按时间升序
+    async fn fetch_open_interest_history(
+        &self,
+        _query: &KlineQuery,
+    ) -> Result<Vec<OpenInterest>, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "持仓量历史",
+        })
+    }
+
+    /// 当前标记价格和指数价格
+    async fn fetch_mark_price(&self, _symbol: &Symbol) -> Result<MarkPrice, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "标记价格",
+        })
+    }
+
+    /// 标记价格或指数价格K线（成交量为0）
+    async fn fetch_price_klines(
+        &self,
+        _kind: PriceKind,
+        _query: &KlineQuery,
+    ) -> Result<Vec<KlineData>, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "标记/指数价格K线",
+        })
+    }
+
+    /// 全市场总市值、成交额和市值占比，只有聚合类数据源提供
+    async fn fetch_global_metrics(&self) -> Result<GlobalMarketMetrics, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "全市场统计",
+        })
+    }
+
+    /// 一次请求批量获取多个交易对的市值，未收录的交易对不在结果中，只有聚合类数据源提供
+    async fn fetch_market_caps(
+        &self,
+        _symbols: &[Symbol],
+    ) -> Result<HashMap<Symbol, Decimal>, MarketDataError> {
+        Err(MarketDataError::Unsupported {
+            exchange: self.name(),
+            operation: "市值",
+        })
+    }
+}
@@ -0,0 +1,905 @@
+use async_trait::async_trait;
+use reqwest::Url;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::decimal::percent_change;
+use super::http::{
+    build_client, get_json, opt_str_decimal, parse_levels, str_decimal, str_i64, HttpClient,
+};
+use super::interval::map_interval;
+use super::rate_limit::{BucketSpec, RateLimitRules, RateLimiter, RequestCost};
+use super::replay::HttpMode;
+use super::stream::{MarketEvent, StreamChannel, StreamKline, StreamProtocol, StreamTopic};
+use super::{
+    FundingQuery, FundingRate, Instrument, InstrumentStatus, KlineData, KlineQuery, MarkPrice,
+    MarketDataError, MarketDataSource, OpenInterest, OrderBook, PriceKind, PublicTrade,
+    SourceCapabilities, Symbol, Ticker, TradeQuery, TradeSide,
+};
+
+const NAME: &str = "okx";
+const BASE_URL: &str = "https://www.okx.com";
+/// 标准周期 -> OKX bar 参数（6小时及以上使用UTC对齐的K线）
+const INTERVALS: &[(&str, &str)] = &[
+    ("1m", "1m"),
+    ("3m", "3m"),
+    ("5m", "5m"),
+    ("15m", "15m"),
+    ("30m", "30m"),
+    ("1h", "1H"),
+    ("2h", "2H"),
+    ("4h", "4H"),
+    ("6h", "6Hutc"),
+    ("12h", "12Hutc"),
+    ("1d", "1Dutc"),
+    ("1w", "1Wutc"),
+    ("1M", "1Mutc"),
+];
+/// `/market/candles` 单次最多返回300根
+const MAX_LIMIT: u32 = 300;
+/// `/market/history-candles` 单次最多返回100根
+const HISTORY_PAGE_SIZE: u32 = 100;
+/// `/market/candles` 等近期接口只提供最近1440根K线，更早的需要走 `history-` 接口
+const RECENT_CANDLES: i64 = 1440;
+/// `/market/books` 每侧最多返回400档
+const MAX_DEPTH: u32 = 400;
+/// `/market/trades` 单次最多返回500条
+const MAX_TRADES: u32 = 500;
+/// `/market/history-trades` 单次最多返回100条
+const HISTORY_TRADES_PAGE_SIZE: u32 = 100;
+/// `/market/mark-price-candles` 单次最多返回100根
+const MAX_PRICE_CANDLES: u32 = 100;
+/// `/public/funding-rate-history` 单次最多返回100条
+const FUNDING_PAGE_SIZE: u32 = 100;
+/// 资金费率历史最多翻页次数（OKX 只保留约3个月的记录）
+const MAX_FUNDING_PAGES: usize = 10;
+/// `/rubik/stat/contracts/open-interest-history` 单次最多返回100条
+const MAX_OPEN_INTEREST_HISTORY: u32 = 100;
+/// 标准周期 -> 持仓量统计周期
+const OPEN_INTEREST_PERIODS: &[(&str, &str)] = &[
+    ("5m", "5m"),
+    ("15m", "15m"),
+    ("30m", "30m"),
+    ("1h", "1H"),
+    ("2h", "2H"),
+    ("4h", "4H"),
+    ("6h", "6H"),
+    ("12h", "12H"),
+    ("1d", "1D"),
+];
+/// instId 不存在的错误码
+const UNKNOWN_INSTRUMENT_CODE: &str = "51001";
+
+/// 按接口限频（IP维度），每个接口一个令牌桶，桶名即接口路径
+static RATE_LIMITS: RateLimitRules = RateLimitRules {
+    buckets: &[
+        BucketSpec::new("default", 20, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/market/candles", 40, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/market/history-candles", 20, Duration::from_secs(2)),
+        BucketSpec::new(
+            "/api/v5/market/mark-price-candles",
+            20,
+            Duration::from_secs(2),
+        ),
+        BucketSpec::new(
+            "/api/v5/market/history-mark-price-candles",
+            10,
+            Duration::from_secs(2),
+        ),
+        BucketSpec::new("/api/v5/market/index-candles", 20, Duration::from_secs(2)),
+        BucketSpec::new(
+            "/api/v5/market/history-index-candles",
+            10,
+            Duration::from_secs(2),
+        ),
+        BucketSpec::new("/api/v5/market/ticker", 20, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/market/index-tickers", 20, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/market/books", 40, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/market/trades", 100, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/market/history-trades", 20, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/public/instruments", 20, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/public/funding-rate", 20, Duration::from_secs(2)),
+        BucketSpec::new(
+            "/api/v5/public/funding-rate-history",
+            10,
+            Duration::from_secs(2),
+        ),
+        BucketSpec::new("/api/v5/public/open-interest", 20, Duration::from_secs(2)),
+        BucketSpec::new("/api/v5/public/mark-price", 10, Duration::from_secs(2)),
+        BucketSpec::new(
+            "/api/v5/rubik/stat/contracts/open-interest-history",
+            5,
+            Duration::from_secs(2),
+        ),
+    ],
+    cost: request_cost,
+    used_weight_headers: &[],
+};
+
+/// 按接口路径匹配令牌桶，未列出的接口使用默认桶
+fn request_cost(url: &Url) -> RequestCost {
+    let bucket = RATE_LIMITS
+        .buckets
+        .iter()
+        .find(|b| b.name == url.path())
+        .map_or("default", |b| b.name);
+    RequestCost::new(bucket, 1)
+}
+
+pub struct OkxSource {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl OkxSource {
+    pub fn new(mode: &HttpMode) -> Self {
+        Self {
+            client: build_client(NAME, mode),
+            limiter: RateLimiter::new(NAME, &RATE_LIMITS),
+        }
+    }
+
+    /// 检查OKX响应格式并取出 data 字段
+    fn unwrap_data(data: &Value) -> Result<&Vec<Value>, MarketDataError> {
+        if data["code"].as_str() != Some("0") {
+            return Err(MarketDataError::api(
+                NAME,
+                format!(
+                    "{} {}",
+                    data["code"].as_str().unwrap_or_default(),
+                    data["msg"].as_str().unwrap_or("Unknown error")
+                ),
+            ));
+        }
+
+        data["data"]
+            .as_array()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无效的数据格式"))
+    }
+
+    /// 错误码 51001 表示 instId 不存在
+    fn symbol_error(error: MarketDataError, symbol: &Symbol) -> MarketDataError {
+        match &error {
+            MarketDataError::Api { message, .. }
+            | MarketDataError::Status { body: message, .. }
+                if message.contains(UNKNOWN_INSTRUMENT_CODE) =>
+            {
+                MarketDataError::unknown_symbol(NAME, symbol)
+            }
+            _ => error,
+        }
+    }
+}
+
+impl Default for OkxSource {
+    fn default() -> Self {
+        Self::new(&HttpMode::Live)
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for OkxSource {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            klines: true,
+            ticker: true,
+            order_book: true,
+            trades: true,
+            native_ohlc: true,
+            base_volume: true,
+            max_klines_per_request: MAX_LIMIT,
+            history_page_size: Some(HISTORY_PAGE_SIZE),
+            perpetuals: true,
+        }
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        Some(&self.limiter)
+    }
+
+    fn supported_intervals(&self) -> Vec<&'static str> {
+        INTERVALS.iter().map(|(standard, _)| *standard).collect()
+    }
+
+    /// 近期区间走 `/market/candles`，每页300根；留出一页余量，保证整页都在近期范围内
+    fn history_page_size_from(&self, start_time: i64, step: i64, now: i64) -> Option<u32> {
+        let recent_from = now - (RECENT_CANDLES - i64::from(MAX_LIMIT)) * step;
+        Some(if start_time >= recent_from {
+            MAX_LIMIT
+        } else {
+            HISTORY_PAGE_SIZE
+        })
+    }
+
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError> {
+        let bar = map_interval(NAME, INTERVALS, &query.interval)?;
+
+        let inst_id = query.symbol.okx();
+
+        let url = candles_url("candles", &inst_id, bar, query, MAX_LIMIT);
+
+        println!("🟡 OKX请求: {}", url);
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let candles = Self::unwrap_data(&data).map_err(|e| Self::symbol_error(e, &query.symbol))?;
+
+        // OKX按时间倒序返回，统一转为升序
+        let mut klines = Vec::new();
+        for candle in candles.iter().rev() {
+            let candle_array = candle
+                .as_array()
+                .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?;
+
+            if candle_array.len() >= 6 {
+                klines.push(parse_candle(candle_array)?);
+            }
+        }
+
+        println!("✅ OKX成功获取 {} 个K线数据点", klines.len());
+        Ok(klines)
+    }
+
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        let url = format!("{}/api/v5/market/ticker?instId={}", BASE_URL, symbol.okx());
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+        let ticker = Self::unwrap_data(&data)
+            .map_err(|e| Self::symbol_error(e, symbol))?
+            .first()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无行情数据"))?;
+
+        parse_ticker(ticker, symbol)
+    }
+
+    async fn fetch_order_book(
+        &self,
+        symbol: &Symbol,
+        depth: u32,
+    ) -> Result<OrderBook, MarketDataError> {
+        let url = format!(
+            "{}/api/v5/market/books?instId={}&sz={}",
+            BASE_URL,
+            symbol.okx(),
+            depth.clamp(1, MAX_DEPTH)
+        );
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+        let book = Self::unwrap_data(&data)
+            .map_err(|e| Self::symbol_error(e, symbol))?
+            .first()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无订单簿数据"))?;
+
+        // 档位格式: [price, size, 已废弃字段, 订单数]
+        Ok(OrderBook {
+            symbol: symbol.to_string(),
+            bids: parse_levels(&book["bids"], NAME)?,
+            asks: parse_levels(&book["asks"], NAME)?,
+            timestamp: str_i64(&book["ts"], NAME, "时间戳")?,
+            source: NAME.to_string(),
+        })
+    }
+
+    async fn fetch_trades(&self, query: &TradeQuery) -> Result<Vec<PublicTrade>, MarketDataError> {
+        let inst_id = query.symbol.okx();
+
+        let url = match &query.before {
+            // type=1 按成交ID翻页，after 返回早于该ID的成交
+            Some(before) => {
+                before
+                    .parse::<u64>()
+                    .map_err(|_| MarketDataError::InvalidCursor(before.clone()))?;
+                format!(
+                    "{}/api/v5/market/history-trades?instId={}&type=1&after={}&limit={}",
+                    BASE_URL,
+                    inst_id,
+                    before,
+                    query.limit.clamp(1, HISTORY_TRADES_PAGE_SIZE)
+                )
+            }
+            None => format!(
+                "{}/api/v5/market/trades?instId={}&limit={}",
+                BASE_URL,
+                inst_id,
+                query.limit.clamp(1, MAX_TRADES)
+            ),
+        };
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let trades = Self::unwrap_data(&data).map_err(|e| Self::symbol_error(e, &query.symbol))?;
+
+        trades
+            .iter()
+            .map(|trade| parse_trade(trade, &query.symbol))
+            .collect()
+    }
+
+    async fn fetch_instruments(&self) -> Result<Vec<Instrument>, MarketDataError> {
+        let url = format!("{}/api/v5/public/instruments?instType=SPOT", BASE_URL);
+
+        let data = get_json(&self.client, &self.limiter, &url).await?;
+        let instruments = Self::unwrap_data(&data)?
+            .iter()
+            .filter_map(|inst| {
+                let base = inst["baseCcy"].as_str()?;
+                let quote = inst["quoteCcy"].as_str()?;
+                Some(Instrument {
+                    symbol: Symbol::new(base, quote).to_string(),
+                    base: base.to_string(),
+                    quote: quote.to_string(),
+                    exchange: NAME.to_string(),
+                    exchange_symbol: inst["instId"].as_str()?.to_string(),
+                    tick_size: opt_str_decimal(&inst["tickSz"]),
+                    lot_size: opt_str_decimal(&inst["lotSz"]),
+                    min_size: opt_str_decimal(&inst["minSz"]),
+                    status: match inst["state"].as_str() {
+                        Some("live") => InstrumentStatus::Trading,
+                        Some("preopen") => InstrumentStatus::PreTrading,
+                        _ => InstrumentStatus::Halted,
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        println!("✅ OKX获取 {} 个现货交易对", instruments.len());
+        Ok(instruments)
+    }
+
+    async fn fetch_funding_rate(&self, symbol: &Symbol) -> Result<FundingRate, MarketDataError> {
+        let url = format!(
+            "{}/api/v5/public/funding-rate?instId={}",
+            BASE_URL,
+            symbol.okx_swap()
+        );
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+        let rate = Self::unwrap_data(&data)
+            .map_err(|e| Self::symbol_error(e, symbol))?
+            .first()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无资金费率数据"))?;
+
+        Ok(FundingRate {
+            symbol: symbol.to_string(),
+            funding_rate: str_decimal(&rate["fundingRate"], NAME, "资金费率")?,
+            funding_time: str_i64(&rate["fundingTime"], NAME, "结算时间")?,
+            next_funding_time: str_i64(&rate["nextFundingTime"], NAME, "结算时间").ok(),
+            source: NAME.to_string(),
+        })
+    }
+
+    /// 按结算时间从新到旧翻页（after 返回早于该时间的记录），直到覆盖区间起点或数量足够
+    async fn fetch_funding_history(
+        &self,
+        query: &FundingQuery,
+    ) -> Result<Vec<FundingRate>, MarketDataError> {
+        let limit = query.limit as usize;
+
+        let mut rates: Vec<FundingRate> = Vec::new();
+        let mut after = query.end_time.map(|end_time| end_time + 1);
+        for _ in 0..MAX_FUNDING_PAGES {
+            let mut url = format!(
+                "{}/api/v5/public/funding-rate-history?instId={}&limit={}",
+                BASE_URL,
+                query.symbol.okx_swap(),
+                FUNDING_PAGE_SIZE
+            );
+            if let Some(after) = after {
+                url.push_str(&format!("&after={}", after));
+            }
+
+            let data = get_json(&self.client, &self.limiter, &url)
+                .await
+                .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+            let page = Self::unwrap_data(&data)
+                .map_err(|e| Self::symbol_error(e, &query.symbol))?
+                .iter()
+                .map(|rate| {
+                    Ok(FundingRate {
+                        symbol: query.symbol.to_string(),
+                        funding_rate: str_decimal(&rate["fundingRate"], NAME, "资金费率")?,
+                        funding_time: str_i64(&rate["fundingTime"], NAME, "结算时间")?,
+                        next_funding_time: None,
+                        source: NAME.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<_>, MarketDataError>>()?;
+
+            let Some(oldest) = page.last().map(|r| r.funding_time) else {
+                break;
+            };
+            let done = page.len() < FUNDING_PAGE_SIZE as usize
+                || match query.start_time {
+                    Some(start_time) => oldest <= start_time,
+                    None => rates.len() + page.len() >= limit,
+                };
+            rates.extend(page);
+            if done {
+                break;
+            }
+            after = Some(oldest);
+        }
+
+        rates.retain(|r| {
+            query
+                .start_time
+                .is_none_or(|start_time| r.funding_time >= start_time)
+        });
+        rates.sort_by_key(|r| r.funding_time);
+        rates.dedup_by_key(|r| r.funding_time);
+        // 指定起点时保留最早的部分，否则保留最新的部分
+        if query.start_time.is_some() {
+            rates.truncate(limit);
+        } else {
+            rates.drain(..rates.len().saturating_sub(limit));
+        }
+        Ok(rates)
+    }
+
+    async fn fetch_open_interest(&self, symbol: &Symbol) -> Result<OpenInterest, MarketDataError> {
+        let url = format!(
+            "{}/api/v5/public/open-interest?instType=SWAP&instId={}",
+            BASE_URL,
+            symbol.okx_swap()
+        );
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+        let oi = Self::unwrap_data(&data)
+            .map_err(|e| Self::symbol_error(e, symbol))?
+            .first()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无持仓量数据"))?;
+
+        // oi 为合约张数，oiCcy 为币数
+        Ok(OpenInterest {
+            symbol: symbol.to_string(),
+            timestamp: str_i64(&oi["ts"], NAME, "时间戳")?,
+            open_interest: str_decimal(&oi["oiCcy"], NAME, "持仓量")?,
+            open_interest_value: opt_str_decimal(&oi["oiUsd"]),
+            source: NAME.to_string(),
+        })
+    }
+
+    async fn fetch_open_interest_history(
+        &self,
+        query: &KlineQuery,
+    ) -> Result<Vec<OpenInterest>, MarketDataError> {
+        let period = map_interval(NAME, OPEN_INTEREST_PERIODS, &query.interval)?;
+
+        let mut url = format!(
+            "{}/api/v5/rubik/stat/contracts/open-interest-history?instId={}&period={}&limit={}",
+            BASE_URL,
+            query.symbol.okx_swap(),
+            period,
+            query.limit.clamp(1, MAX_OPEN_INTEREST_HISTORY)
+        );
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&begin={}", start_time));
+        }
+        if let Some(end_time) = query.end_time {
+            url.push_str(&format!("&end={}", end_time));
+        }
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let rows = Self::unwrap_data(&data).map_err(|e| Self::symbol_error(e, &query.symbol))?;
+
+        // 格式: [ts, oi, oiCcy, oiUsd]，按时间倒序
+        rows.iter()
+            .rev()
+            .map(|row| {
+                let row = row
+                    .as_array()
+                    .filter(|r| r.len() >= 3)
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效的持仓量格式"))?;
+                Ok(OpenInterest {
+                    symbol: query.symbol.to_string(),
+                    timestamp: str_i64(&row[0], NAME, "时间戳")?,
+                    open_interest: str_decimal(&row[2], NAME, "持仓量")?,
+                    open_interest_value: row.get(3).and_then(opt_str_decimal),
+                    source: NAME.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    async fn fetch_mark_price(&self, symbol: &Symbol) -> Result<MarkPrice, MarketDataError> {
+        let url = format!(
+            "{}/api/v5/public/mark-price?instType=SWAP&instId={}",
+            BASE_URL,
+            symbol.okx_swap()
+        );
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+        let mark = Self::unwrap_data(&data)
+            .map_err(|e| Self::symbol_error(e, symbol))?
+            .first()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无标记价格数据"))?;
+
+        // 指数价格在单独的接口，获取失败时留空
+        let index_url = format!(
+            "{}/api/v5/market/index-tickers?instId={}",
+            BASE_URL,
+            symbol.okx()
+        );
+        let index_price = get_json(&self.client, &self.limiter, &index_url)
+            .await
+            .ok()
+            .and_then(|data| {
+                Self::unwrap_data(&data)
+                    .ok()
+                    .and_then(|rows| rows.first().and_then(|row| opt_str_decimal(&row["idxPx"])))
+            });
+
+        Ok(MarkPrice {
+            symbol: symbol.to_string(),
+            mark_price: str_decimal(&mark["markPx"], NAME, "标记价格")?,
+            index_price,
+            timestamp: str_i64(&mark["ts"], NAME, "时间戳")?,
+            source: NAME.to_string(),
+        })
+    }
+
+    async fn fetch_price_klines(
+        &self,
+        kind: PriceKind,
+        query: &KlineQuery,
+    ) -> Result<Vec<KlineData>, MarketDataError> {
+        let bar = map_interval(NAME, INTERVALS, &query.interval)?;
+
+        // 标记价格K线按合约查询，指数价格K线按现货指数查询
+        let (path, inst_id) = match kind {
+            PriceKind::Mark => ("mark-price-candles", query.symbol.okx_swap()),
+            PriceKind::Index => ("index-candles", query.symbol.okx()),
+        };
+        let url = candles_url(path, &inst_id, bar, query, MAX_PRICE_CANDLES);
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let candles = Self::unwrap_data(&data).map_err(|e| Self::symbol_error(e, &query.symbol))?;
+
+        // 格式: [ts, open, high, low, close, confirm]，按时间倒序
+        candles
+            .iter()
+            .rev()
+            .map(|candle| {
+                let candle = candle
+                    .as_array()
+                    .filter(|c| c.len() >= 5)
+                    .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?;
+                Ok(KlineData {
+                    timestamp: str_i64(&candle[0], NAME, "时间戳")?,
+                    open: str_decimal(&candle[1], NAME, "开盘价")?,
+                    high: str_decimal(&candle[2], NAME, "最高价")?,
+                    low: str_decimal(&candle[3], NAME, "最低价")?,
+                    close: str_decimal(&candle[4], NAME, "收盘价")?,
+                    volume: Decimal::ZERO,
+                    source: NAME.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// K线类接口的请求地址
+///
+/// 区间查询每页超过历史接口上限时使用近期接口（分页器只对最近的区间请求更大的页，见
+/// `history_page_size_from`），否则使用对应的历史接口（`history-` 前缀）；
+/// after 返回早于该时间的数据，before 返回晚于该时间的数据。
+fn candles_url(path: &str, inst_id: &str, bar: &str, query: &KlineQuery, max_limit: u32) -> String {
+    let ranged = query.start_time.is_some() || query.end_time.is_some();
+    if ranged && query.limit > HISTORY_PAGE_SIZE {
+        let mut url = format!(
+            "{}/api/v5/market/{}?instId={}&bar={}&limit={}",
+            BASE_URL,
+            path,
+            inst_id,
+            bar,
+            query.limit.min(max_limit)
+        );
+        if let Some(end_time) = query.end_time {
+            url.push_str(&format!("&after={}", end_time + 1));
+        }
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&before={}", start_time - 1));
+        }
+        url
+    } else if ranged {
+        let mut url = format!(
+            "{}/api/v5/market/history-{}?instId={}&bar={}&limit={}",
+            BASE_URL,
+            path,
+            inst_id,
+            bar,
+            query.limit.min(HISTORY_PAGE_SIZE)
+        );
+        if let Some(end_time) = query.end_time {
+            url.push_str(&format!("&after={}", end_time + 1));
+        }
+        if let Some(start_time) = query.start_time {
+            url.push_str(&format!("&before={}", start_time - 1));
+        }
+        url
+    } else {
+        format!(
+            "{}/api/v5/market/{}?instId={}&bar={}&limit={}",
+            BASE_URL,
+            path,
+            inst_id,
+            bar,
+            query.limit.min(max_limit)
+        )
+    }
+}
+
+/// OKX K线格式: [timestamp, open, high, low, close, volume, volumeCcy, volCcyQuote, confirm]
+fn parse_candle(candle: &[Value]) -> Result<KlineData, MarketDataError> {
+    Ok(KlineData {
+        timestamp: str_i64(&candle[0], NAME, "时间戳")?,
+        open: str_decimal(&candle[1], NAME, "开盘价")?,
+        high: str_decimal(&candle[2], NAME, "最高价")?,
+        low: str_decimal(&candle[3], NAME, "最低价")?,
+        close: str_decimal(&candle[4], NAME, "收盘价")?,
+        volume: str_decimal(&candle[5], NAME, "成交量")?,
+        source: NAME.to_string(),
+    })
+}
+
+fn parse_ticker(ticker: &Value, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+    let price = str_decimal(&ticker["last"], NAME, "最新价")?;
+    let open_24h = opt_str_decimal(&ticker["open24h"]);
+    let price_change_24h = open_24h.map(|open| price - open);
+
+    Ok(Ticker {
+        symbol: symbol.to_string(),
+        price,
+        price_change_24h,
+        price_change_percent_24h: open_24h.and_then(|open| percent_change(price, open)),
+        high_24h: opt_str_decimal(&ticker["high24h"]),
+        low_24h: opt_str_decimal(&ticker["low24h"]),
+        volume_24h: opt_str_decimal(&ticker["vol24h"]),
+        quote_volume_24h: opt_str_decimal(&ticker["volCcy24h"]),
+        market_cap: None,
+        timestamp: str_i64(&ticker["ts"], NAME, "时间戳")?,
+        source: NAME.to_string(),
+    })
+}
+
+fn parse_trade(trade: &Value, symbol: &Symbol) -> Result<PublicTrade, MarketDataError> {
+    Ok(PublicTrade {
+        id: trade["tradeId"]
+            .as_str()
+            .ok_or_else(|| MarketDataError::parse(NAME, "无效成交ID"))?
+            .to_string(),
+        symbol: symbol.to_string(),
+        side: match trade["side"].as_str() {
+            Some("sell") => TradeSide::Sell,
+            _ => TradeSide::Buy,
+        },
+        price: str_decimal(&trade["px"], NAME, "成交价")?,
+        amount: str_decimal(&trade["sz"], NAME, "成交量")?,
+        timestamp: str_i64(&trade["ts"], NAME, "时间戳")?,
+        source: NAME.to_string(),
+    })
+}
+
+/// OKX WebSocket 推送
+///
+/// 行情和成交在 public 地址，K线在 business 地址，分别建立连接。
+pub struct OkxStream {
+    url: String,
+    /// 是否为 business 连接（只提供K线）
+    business: bool,
+}
+
+impl OkxStream {
+    pub const PUBLIC_URL: &'static str = "wss://ws.okx.com:8443/ws/v5/public";
+    pub const BUSINESS_URL: &'static str = "wss://ws.okx.com:8443/ws/v5/business";
+
+    pub fn public(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            business: false,
+        }
+    }
+
+    pub fn business(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            business: true,
+        }
+    }
+
+    fn channel_name(channel: &StreamChannel) -> Option<String> {
+        match channel {
+            StreamChannel::Ticker => Some("tickers".to_string()),
+            StreamChannel::Trade => Some("trades".to_string()),
+            StreamChannel::Kline(interval) => map_interval(NAME, INTERVALS, interval)
+                .ok()
+                .map(|bar| format!("candle{}", bar)),
+        }
+    }
+
+    /// 订阅/取消订阅消息，`op` 为 `subscribe` 或 `unsubscribe`
+    fn operation(&self, op: &str, topics: &[StreamTopic]) -> Option<String> {
+        let args: Vec<Value> = topics
+            .iter()
+            .filter(|topic| self.supports(&topic.channel))
+            .filter_map(|topic| {
+                Some(json!({
+                    "channel": Self::channel_name(&topic.channel)?,
+                    "instId": topic.symbol.okx(),
+                }))
+            })
+            .collect();
+        if args.is_empty() {
+            return None;
+        }
+        Some(json!({ "op": op, "args": args }).to_string())
+    }
+}
+
+impl StreamProtocol for OkxStream {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn supports(&self, channel: &StreamChannel) -> bool {
+        match channel {
+            StreamChannel::Kline(_) => self.business && Self::channel_name(channel).is_some(),
+            _ => !self.business,
+        }
+    }
+
+    fn subscribe_message(&self, topics: &[StreamTopic]) -> Option<String> {
+        self.operation("subscribe", topics)
+    }
+
+    fn unsubscribe_message(&self, topics: &[StreamTopic]) -> Option<String> {
+        self.operation("unsubscribe", topics)
+    }
+
+    fn parse_message(&self, text: &str) -> Result<Vec<MarketEvent>, MarketDataError> {
+        // 心跳响应
+        if text == "pong" {
+            return Ok(Vec::new());
+        }
+
+        let message: Value = serde_json::from_str(text)
+            .map_err(|e| MarketDataError::parse(NAME, format!("无效的推送消息: {}", e)))?;
+        match message["event"].as_str() {
+            Some("error") => {
+                return Err(MarketDataError::stream(
+                    NAME,
+                    format!(
+                        "{} {}",
+                        message["code"].as_str().unwrap_or_default(),
+                        message["msg"].as_str().unwrap_or("Unknown error")
+                    ),
+                ))
+            }
+            Some(_) => return Ok(Vec::new()),
+            None => {}
+        }
+
+        let channel = message["arg"]["channel"].as_str().unwrap_or_default();
+        let symbol = Symbol::parse(message["arg"]["instId"].as_str().unwrap_or_default())?;
+        let Some(data) = message["data"].as_array() else {
+            return Ok(Vec::new());
+        };
+
+        match channel {
+            "tickers" => data
+                .iter()
+                .map(|ticker| parse_ticker(ticker, &symbol).map(MarketEvent::Ticker))
+                .collect(),
+            "trades" => data
+                .iter()
+                .map(|trade| parse_trade(trade, &symbol).map(MarketEvent::Trade))
+                .collect(),
+            _ => {
+                let Some((interval, _)) = channel
+                    .strip_prefix("candle")
+                    .and_then(|bar| INTERVALS.iter().find(|(_, native)| *native == bar))
+                else {
+                    return Ok(Vec::new());
+                };
+                data.iter()
+                    .map(|candle| {
+                        let candle = candle
+                            .as_array()
+                            .filter(|c| c.len() >= 6)
+                            .ok_or_else(|| MarketDataError::parse(NAME, "无效的K线格式"))?;
+                        Ok(MarketEvent::Kline(StreamKline {
+                            symbol: symbol.to_string(),
+                            interval: interval.to_string(),
+                            // 第9列 confirm 为 "1" 表示已收盘
+                            closed: candle.get(8).and_then(Value::as_str) == Some("1"),
+                            kline: parse_candle(candle)?,
+                        }))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn ping_message(&self) -> Option<&'static str> {
+        Some("ping")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600_000;
+    const NOW: i64 = 1_717_000_000_000;
+
+    fn range_query(start_time: i64, end_time: i64, limit: u32) -> KlineQuery {
+        KlineQuery {
+            symbol: Symbol::parse("BTC/USDT").unwrap(),
+            interval: "1h".to_string(),
+            limit,
+            start_time: Some(start_time),
+            end_time: Some(end_time),
+        }
+    }
+
+    #[test]
+    fn recent_ranges_use_candles_endpoint_with_larger_pages() {
+        let okx = OkxSource::new(&HttpMode::Live);
+        let recent_start = NOW - 300 * HOUR;
+        let old_start = NOW - 2000 * HOUR;
+        assert_eq!(
+            okx.history_page_size_from(recent_start, HOUR, NOW),
+            Some(MAX_LIMIT)
+        );
+        assert_eq!(
+            okx.history_page_size_from(old_start, HOUR, NOW),
+            Some(HISTORY_PAGE_SIZE)
+        );
+
+        let url = candles_url(
+            "candles",
+            "BTC-USDT",
+            "1H",
+            &range_query(recent_start, NOW, MAX_LIMIT),
+            MAX_LIMIT,
+        );
+        assert!(url.contains("/api/v5/market/candles?"), "{}", url);
+        assert!(url.contains("limit=300"), "{}", url);
+        assert!(url.contains(&format!("&after={}", NOW + 1)), "{}", url);
+
+        let url = candles_url(
+            "candles",
+            "BTC-USDT",
+            "1H",
+            &range_query(old_start, old_start + 99 * HOUR, HISTORY_PAGE_SIZE),
+            MAX_LIMIT,
+        );
+        assert!(url.contains("/api/v5/market/history-candles?"), "{}", url);
+        assert!(url.contains("limit=100"), "{}", url);
+    }
+}
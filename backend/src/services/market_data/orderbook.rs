@@ -0,0 +1,235 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::{decimal, OrderBook, OrderBookLevel};
+
+/// 分组步长相对最优报价的最小比例，过小的步长分组没有意义且可能溢出
+const MIN_STEP_FRACTION: Decimal = Decimal::from_parts(1, 0, 0, false, 12);
+
+/// 单个交易所在合并档位中的数量
+#[derive(Debug, Clone, Serialize)]
+pub struct VenueAmount {
+    pub exchange: String,
+    #[serde(with = "decimal")]
+    pub amount: Decimal,
+}
+
+/// 合并后的价位，附带各交易所的数量构成
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedLevel {
+    #[serde(with = "decimal")]
+    pub price: Decimal,
+    #[serde(with = "decimal")]
+    pub amount: Decimal,
+    pub venues: Vec<VenueAmount>,
+}
+
+/// 跨交易所合并的订单簿
+#[derive(Debug, Clone, Serialize)]
+pub struct MergedOrderBook {
+    pub symbol: String,
+    pub bids: Vec<MergedLevel>,
+    pub asks: Vec<MergedLevel>,
+    /// 参与合并的订单簿中最早的时间戳
+    pub timestamp: i64,
+}
+
+#[derive(Clone, Copy)]
+enum Side {
+    Bid,
+    Ask,
+}
+
+/// 按价格合并多个交易所的订单簿，每侧保留 `depth` 档
+///
+/// 指定 `step` 时按价格步长分组：买盘向下取整、卖盘向上取整，
+/// 保证分组后的价格不会比原始报价更优。
+pub fn merge_order_books(
+    books: &[OrderBook],
+    depth: usize,
+    step: Option<Decimal>,
+) -> MergedOrderBook {
+    let step = step.filter(|s| *s > Decimal::ZERO);
+    MergedOrderBook {
+        symbol: books.first().map(|b| b.symbol.clone()).unwrap_or_default(),
+        bids: merge_side(books, Side::Bid, depth, step),
+        asks: merge_side(books, Side::Ask, depth, step),
+        timestamp: books.iter().map(|b| b.timestamp).min().unwrap_or_default(),
+    }
+}
+
+/// 检查分组步长：不能小于各订单簿最高报价的 [`MIN_STEP_FRACTION`]
+pub fn check_step(books: &[OrderBook], step: Decimal) -> Result<(), String> {
+    if step <= Decimal::ZERO {
+        return Err("step 必须大于0".to_string());
+    }
+    let best_price = books
+        .iter()
+        .flat_map(|b| b.bids.first().into_iter().chain(b.asks.first()))
+        .map(|level| level.price)
+        .max();
+    if let Some(min) = best_price.map(|price| price * MIN_STEP_FRACTION) {
+        if step < min {
+            return Err(format!("step 过小，不能小于 {}", min.normalize()));
+        }
+    }
+    Ok(())
+}
+
+fn merge_side(
+    books: &[OrderBook],
+    side: Side,
+    depth: usize,
+    step: Option<Decimal>,
+) -> Vec<MergedLevel> {
+    // 价格是精确小数，`1.10` 和 `1.1` 归为同一档
+    let mut levels: BTreeMap<Decimal, MergedLevel> = BTreeMap::new();
+    for book in books {
+        let book_levels = match side {
+            Side::Bid => &book.bids,
+            Side::Ask => &book.asks,
+        };
+        for level in book_levels.iter().filter(|l| l.amount > Decimal::ZERO) {
+            let price = level_price(level, side, step);
+            let merged = levels.entry(price).or_insert_with(|| MergedLevel {
+                price,
+                amount: Decimal::ZERO,
+                venues: Vec::new(),
+            });
+            merged.amount += level.amount;
+            match merged.venues.iter_mut().find(|v| v.exchange == book.source) {
+                Some(venue) => venue.amount += level.amount,
+                None => merged.venues.push(VenueAmount {
+                    exchange: book.source.clone(),
+                    amount: level.amount,
+                }),
+            }
+        }
+    }
+
+    let levels = levels.into_values();
+    match side {
+        Side::Bid => levels.rev().take(depth).collect(),
+        Side::Ask => levels.take(depth).collect(),
+    }
+}
+
+/// 分组后的价位，步长相对价格过小导致溢出时保留原始价格
+fn level_price(level: &OrderBookLevel, side: Side, step: Option<Decimal>) -> Decimal {
+    step.and_then(|step| {
+        let ticks = level.price.checked_div(step)?;
+        let ticks = match side {
+            Side::Bid => ticks.floor(),
+            Side::Ask => ticks.ceil(),
+        };
+        ticks.checked_mul(step)
+    })
+    .unwrap_or(level.price)
+    .normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::dec;
+
+    fn levels(levels: &[(&str, &str)]) -> Vec<OrderBookLevel> {
+        levels
+            .iter()
+            .map(|(price, amount)| OrderBookLevel {
+                price: dec(price),
+                amount: dec(amount),
+            })
+            .collect()
+    }
+
+    fn book(source: &str, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> OrderBook {
+        OrderBook {
+            symbol: "BTC/USDT".to_string(),
+            bids: levels(bids),
+            asks: levels(asks),
+            timestamp: 0,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn rejects_steps_too_small_for_the_price() {
+        let books = [book("okx", &[("67850.1", "1")], &[("67850.2", "1")])];
+
+        assert!(check_step(&books, dec("0.01")).is_ok());
+        assert!(check_step(&books, dec("0.0000001")).is_ok());
+        assert!(check_step(&books, dec("0.00000001")).is_err());
+        assert!(check_step(&books, dec("0.0000000000000000000000000001")).is_err());
+        assert!(check_step(&books, Decimal::ZERO).is_err());
+    }
+
+    #[test]
+    fn keeps_raw_price_when_grouping_overflows() {
+        let books = [book("okx", &[("67850.1", "1")], &[("67850.2", "1")])];
+
+        let merged = merge_order_books(&books, 10, Some(dec("0.0000000000000000000000000001")));
+
+        assert_eq!(merged.bids[0].price, dec("67850.1"));
+        assert_eq!(merged.asks[0].price, dec("67850.2"));
+    }
+
+    #[test]
+    fn merges_equal_prices_across_venues_with_attribution() {
+        let books = [
+            book(
+                "okx",
+                &[("1.10", "2"), ("1.09", "1")],
+                &[("1.12", "3"), ("1.13", "1")],
+            ),
+            book(
+                "binance",
+                &[("1.1", "5"), ("1.08", "4")],
+                &[("1.120", "1"), ("1.14", "2")],
+            ),
+        ];
+
+        let merged = merge_order_books(&books, 2, None);
+
+        // 1.10 与 1.1 为同一档
+        assert_eq!(merged.bids.len(), 2);
+        assert_eq!(merged.bids[0].price, dec("1.1"));
+        assert_eq!(merged.bids[0].amount, dec("7"));
+        let venues: Vec<_> = merged.bids[0]
+            .venues
+            .iter()
+            .map(|v| (v.exchange.as_str(), v.amount))
+            .collect();
+        assert_eq!(venues, vec![("okx", dec("2")), ("binance", dec("5"))]);
+        assert_eq!(merged.bids[1].price, dec("1.09"));
+
+        assert_eq!(merged.asks[0].price, dec("1.12"));
+        assert_eq!(merged.asks[0].amount, dec("4"));
+        assert_eq!(merged.asks[0].venues.len(), 2);
+        assert_eq!(merged.asks[1].price, dec("1.13"));
+        assert_eq!(merged.symbol, "BTC/USDT");
+    }
+
+    #[test]
+    fn groups_by_step_without_improving_prices() {
+        let books = [
+            book(
+                "okx",
+                &[("100.7", "1"), ("100.2", "2"), ("99.9", "0")],
+                &[("100.8", "1"), ("101.0", "2")],
+            ),
+            book("binance", &[("100.5", "3")], &[("101.3", "4")]),
+        ];
+
+        let merged = merge_order_books(&books, 10, Some(dec("0.5")));
+
+        // 买盘向下取整：100.7 -> 100.5，100.2 -> 100；数量为0的价位忽略
+        let bids: Vec<_> = merged.bids.iter().map(|l| (l.price, l.amount)).collect();
+        assert_eq!(bids, vec![(dec("100.5"), dec("4")), (dec("100"), dec("2"))]);
+        assert_eq!(merged.bids[0].venues.len(), 2);
+        // 卖盘向上取整：100.8 -> 101，101.3 -> 101.5
+        let asks: Vec<_> = merged.asks.iter().map(|l| (l.price, l.amount)).collect();
+        assert_eq!(asks, vec![(dec("101"), dec("3")), (dec("101.5"), dec("4"))]);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use super::{KlineData, KlineQuery, MarketDataError, MarketDataSource};
+
+/// 单次区间查询最多请求的页数，防止超大区间无限翻页
+const MAX_PAGES: usize = 5000;
+
+/// 区间查询的一页K线
+pub struct KlinePage {
+    /// 区间内的K线，按时间升序
+    pub klines: Vec<KlineData>,
+    /// 本页覆盖的区间（闭区间，毫秒），下一页从 `to + 1` 开始
+    pub from: i64,
+    pub to: i64,
+}
+
+/// 按时间区间逐页获取K线
+///
+/// 每页覆盖 `history_page_size` 根K线的时间跨度，下一页从上一页最后一根K线之后开始；
+/// 不支持区间查询的数据源只请求一次，并按区间过滤结果。
+pub struct RangePager<'a> {
+    source: &'a dyn MarketDataSource,
+    query: &'a KlineQuery,
+    start_time: i64,
+    end_time: i64,
+    step: i64,
+    now: i64,
+    cursor: i64,
+    pages: usize,
+}
+
+impl<'a> RangePager<'a> {
+    pub fn new(
+        source: &'a dyn MarketDataSource,
+        query: &'a KlineQuery,
+        start_time: i64,
+        end_time: i64,
+        step: i64,
+        now: i64,
+    ) -> Self {
+        Self {
+            source,
+            query,
+            start_time,
+            end_time,
+            step,
+            now,
+            cursor: start_time,
+            pages: 0,
+        }
+    }
+
+    /// 获取下一页，区间已取完时返回 `None`
+    pub async fn next_page(&mut self) -> Result<Option<KlinePage>, MarketDataError> {
+        if self.cursor > self.end_time {
+            return Ok(None);
+        }
+        if self.pages >= MAX_PAGES {
+            return Err(MarketDataError::RangeTooLarge {
+                exchange: self.source.name(),
+                pages: MAX_PAGES,
+            });
+        }
+        self.pages += 1;
+
+        let capabilities = self.source.capabilities();
+        let from = self.cursor;
+        let (limit, page_end) = match self
+            .source
+            .history_page_size_from(from, self.step, self.now)
+        {
+            Some(page_size) => {
+                let page_size = page_size.max(1);
+                let page_span = self.step * i64::from(page_size);
+                (page_size, (from + page_span - 1).min(self.end_time))
+            }
+            None => (capabilities.max_klines_per_request, self.end_time),
+        };
+        let page = self
+            .source
+            .fetch_klines(&KlineQuery {
+                limit,
+                start_time: Some(from),
+                end_time: Some(page_end),
+                ..self.query.clone()
+            })
+            .await?;
+
+        let last_open_time = page.iter().map(|k| k.timestamp).max();
+        // 空页（如交易对尚未上线）直接跳过整页区间
+        self.cursor = match last_open_time {
+            Some(ts) if ts >= from && capabilities.history_page_size.is_some() => ts + self.step,
+            _ => page_end + 1,
+        };
+
+        let klines: BTreeMap<i64, KlineData> = page
+            .into_iter()
+            .filter(|k| k.timestamp >= self.start_time && k.timestamp <= self.end_time)
+            .map(|k| (k.timestamp, k))
+            .collect();
+        Ok(Some(KlinePage {
+            klines: klines.into_values().collect(),
+            from,
+            to: self.cursor - 1,
+        }))
+    }
+}
+
+/// 按时间区间分页获取K线，合并去重后按时间升序返回
+pub async fn fetch_range(
+    source: &dyn MarketDataSource,
+    query: &KlineQuery,
+    start_time: i64,
+    end_time: i64,
+    step: i64,
+    now: i64,
+) -> Result<Vec<KlineData>, MarketDataError> {
+    let mut pager = RangePager::new(source, query, start_time, end_time, step, now);
+    let mut merged = BTreeMap::new();
+    while let Some(page) = pager.next_page().await? {
+        merged.extend(page.klines.into_iter().map(|k| (k.timestamp, k)));
+    }
+    Ok(merged.into_values().collect())
+}
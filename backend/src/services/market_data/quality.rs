@@ -0,0 +1,494 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::consensus::{deviation_bps, median};
+use super::interval::Interval;
+use super::KlineData;
+
+/// 单个响应中最多列出的问题数量，超出部分只计入统计
+const MAX_REPORTED_ISSUES: usize = 100;
+
+/// 交叉校验比较的价格字段
+type PriceField = (&'static str, fn(&KlineData) -> Decimal);
+const PRICE_FIELDS: [PriceField; 4] = [
+    ("开盘价", |k| k.open),
+    ("最高价", |k| k.high),
+    ("最低价", |k| k.low),
+    ("收盘价", |k| k.close),
+];
+
+/// K线数据问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityIssueKind {
+    /// 价格不为正或成交量为负
+    InvalidValue,
+    /// 最高价/最低价没有包住开盘价和收盘价
+    InconsistentRange,
+    /// 同一周期出现多根K线
+    Duplicate,
+    /// 时间戳不是升序
+    OutOfOrder,
+    /// 相邻K线之间缺少周期
+    Missing,
+    /// 成交量为0（交易所停机或无成交时的补齐K线）
+    ZeroVolume,
+    /// 价格偏离其他数据源超过阈值
+    Outlier,
+}
+
+/// 对问题K线的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityAction {
+    /// 已修正后返回
+    Repaired,
+    /// 已从结果中剔除
+    Dropped,
+    /// 保留原值，仅标记
+    Flagged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityIssue {
+    /// 问题K线的开盘时间（缺失时为第一根缺失K线的开盘时间）
+    pub timestamp: i64,
+    pub kind: QualityIssueKind,
+    pub action: QualityAction,
+    pub message: String,
+}
+
+impl QualityIssue {
+    fn new(
+        timestamp: i64,
+        kind: QualityIssueKind,
+        action: QualityAction,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            timestamp,
+            kind,
+            action,
+            message: message.into(),
+        }
+    }
+}
+
+/// 数据质量指标
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataQualityMetrics {
+    /// 获取数据的耗时（毫秒）
+    pub latency_ms: u64,
+    /// 与其他数据源一致的K线占比（0-1），未做交叉校验时为空
+    pub accuracy: Option<f64>,
+    /// 首尾K线之间实际K线数与应有K线数之比（0-1）
+    pub completeness: f64,
+    /// 最新一根K线收盘至查询终点的时间（毫秒），未收盘时为0，无数据时为空
+    pub freshness_ms: Option<i64>,
+}
+
+/// K线质量报告：指标、问题统计和问题明细
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityReport {
+    #[serde(flatten)]
+    pub metrics: DataQualityMetrics,
+    /// 返回的K线数量
+    pub bars: usize,
+    pub missing_bars: usize,
+    pub zero_volume_bars: usize,
+    pub outliers: usize,
+    pub repaired: usize,
+    pub dropped: usize,
+    /// 问题明细，最多列出前100条
+    pub issues: Vec<QualityIssue>,
+}
+
+impl QualityReport {
+    fn push(&mut self, issue: QualityIssue) {
+        match issue.action {
+            QualityAction::Repaired => self.repaired += 1,
+            QualityAction::Dropped => self.dropped += 1,
+            QualityAction::Flagged => {}
+        }
+        match issue.kind {
+            QualityIssueKind::ZeroVolume => self.zero_volume_bars += 1,
+            QualityIssueKind::Outlier => self.outliers += 1,
+            _ => {}
+        }
+        if self.issues.len() < MAX_REPORTED_ISSUES {
+            self.issues.push(issue);
+        }
+    }
+
+    /// 标记 `[from, to)` 内缺少的周期
+    fn flag_missing(&mut self, interval: &Interval, from: i64, to: i64) {
+        let missing = count_buckets(interval, from, to);
+        if missing > 0 {
+            self.missing_bars += missing;
+            self.push(QualityIssue::new(
+                from,
+                QualityIssueKind::Missing,
+                QualityAction::Flagged,
+                format!("缺少 {} 根K线", missing),
+            ));
+        }
+    }
+
+    /// 与其他数据源同一周期的中位数比较，标记开高低收偏离超过 `threshold_bps` 的K线
+    ///
+    /// 只统计有参照数据的周期，没有任何可比较的周期时准确率为空。
+    pub fn cross_check(
+        &mut self,
+        klines: &[KlineData],
+        interval: &Interval,
+        references: &[&[KlineData]],
+        threshold_bps: f64,
+    ) {
+        let mut buckets: BTreeMap<i64, Vec<&KlineData>> = BTreeMap::new();
+        for reference in references {
+            let mut last_bucket = None;
+            for kline in reference.iter() {
+                // 每个参照源在一个周期内只取第一根
+                let bucket = interval.bucket_start(kline.timestamp);
+                if last_bucket != Some(bucket) {
+                    buckets.entry(bucket).or_default().push(kline);
+                    last_bucket = Some(bucket);
+                }
+            }
+        }
+
+        let mut compared = 0usize;
+        let mut outliers = 0usize;
+        for kline in klines {
+            let Some(members) = buckets.get(&interval.bucket_start(kline.timestamp)) else {
+                continue;
+            };
+            compared += 1;
+
+            let worst = PRICE_FIELDS
+                .iter()
+                .filter_map(|(label, field)| {
+                    let reference = median(members.iter().map(|k| field(k)).collect())?;
+                    Some((*label, deviation_bps(field(kline), reference)))
+                })
+                .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()));
+
+            if let Some((label, bps)) = worst.filter(|(_, bps)| bps.abs() > threshold_bps) {
+                outliers += 1;
+                self.push(QualityIssue::new(
+                    kline.timestamp,
+                    QualityIssueKind::Outlier,
+                    QualityAction::Flagged,
+                    format!("{}偏离其他数据源 {:.1} 个基点", label, bps),
+                ));
+            }
+        }
+
+        self.metrics.accuracy = (compared > 0).then(|| 1.0 - outliers as f64 / compared as f64);
+    }
+}
+
+/// 通过校验的K线及其质量报告
+#[derive(Debug, Clone)]
+pub struct CheckedKlines {
+    pub klines: Vec<KlineData>,
+    pub quality: QualityReport,
+}
+
+/// 修正或剔除不可能存在的K线，结果按时间升序且每个周期只有一根
+///
+/// - 价格不为正、成交量为负的K线剔除；
+/// - 最高价/最低价没有包住开收盘价时，以开高低收中的极值修正；
+/// - 时间戳乱序时重新排序；
+/// - 同一周期的多根K线保留最后一根（较新的数据）。
+pub fn sanitize(
+    mut klines: Vec<KlineData>,
+    interval: &Interval,
+) -> (Vec<KlineData>, Vec<QualityIssue>) {
+    let mut issues = Vec::new();
+
+    if let Some(w) = klines.windows(2).find(|w| w[0].timestamp > w[1].timestamp) {
+        issues.push(QualityIssue::new(
+            w[1].timestamp,
+            QualityIssueKind::OutOfOrder,
+            QualityAction::Repaired,
+            "K线时间戳乱序，已重新排序",
+        ));
+        klines.sort_by_key(|k| k.timestamp);
+    }
+
+    let mut result: Vec<KlineData> = Vec::with_capacity(klines.len());
+    for mut kline in klines {
+        let prices = [kline.open, kline.high, kline.low, kline.close];
+        if prices.iter().any(|p| *p <= Decimal::ZERO) || kline.volume < Decimal::ZERO {
+            issues.push(QualityIssue::new(
+                kline.timestamp,
+                QualityIssueKind::InvalidValue,
+                QualityAction::Dropped,
+                format!(
+                    "无效数值: 开{} 高{} 低{} 收{} 量{}",
+                    kline.open, kline.high, kline.low, kline.close, kline.volume
+                ),
+            ));
+            continue;
+        }
+
+        let high = prices.iter().copied().max().unwrap_or(kline.high);
+        let low = prices.iter().copied().min().unwrap_or(kline.low);
+        if high != kline.high || low != kline.low {
+            issues.push(QualityIssue::new(
+                kline.timestamp,
+                QualityIssueKind::InconsistentRange,
+                QualityAction::Repaired,
+                format!(
+                    "最高价/最低价 {}/{} 未包住开收盘价，已修正为 {}/{}",
+                    kline.high, kline.low, high, low
+                ),
+            ));
+            kline.high = high;
+            kline.low = low;
+        }
+
+        match result.last_mut() {
+            Some(last)
+                if interval.bucket_start(last.timestamp)
+                    == interval.bucket_start(kline.timestamp) =>
+            {
+                issues.push(QualityIssue::new(
+                    last.timestamp,
+                    QualityIssueKind::Duplicate,
+                    QualityAction::Dropped,
+                    "同一周期出现多根K线，保留最后一根",
+                ));
+                *last = kline;
+            }
+            _ => result.push(kline),
+        }
+    }
+
+    (result, issues)
+}
+
+/// 统计缺失周期和零成交量K线，计算完整度与新鲜度
+///
+/// `klines` 须已经过 [`sanitize`]；`issues` 为之前阶段（如写入存储前的修正）发现的问题。
+/// 完整度按请求区间 `window`（`[start, end]`，毫秒）内开盘的周期计算，
+/// 包括区间开头和末尾缺少的K线；`now` 时尚未收盘的周期不计入缺失。
+/// 新鲜度以 `end` 和 `now` 中较早者为准。
+pub fn inspect(
+    klines: &[KlineData],
+    interval: &Interval,
+    issues: Vec<QualityIssue>,
+    window: (i64, i64),
+    now: i64,
+    latency: Duration,
+) -> QualityReport {
+    let (start, end) = window;
+    let mut report = QualityReport {
+        bars: klines.len(),
+        ..Default::default()
+    };
+    for issue in issues {
+        report.push(issue);
+    }
+
+    let first_expected = if interval.bucket_start(start) == start {
+        start
+    } else {
+        interval.next_bucket(start)
+    };
+    let expected_end = interval.next_bucket(end).min(interval.bucket_start(now));
+    match (klines.first(), klines.last()) {
+        (Some(first), Some(last)) => {
+            report.flag_missing(
+                interval,
+                first_expected,
+                interval.bucket_start(first.timestamp),
+            );
+            for w in klines.windows(2) {
+                report.flag_missing(
+                    interval,
+                    interval.next_bucket(w[0].timestamp),
+                    interval.bucket_start(w[1].timestamp),
+                );
+            }
+            report.flag_missing(interval, interval.next_bucket(last.timestamp), expected_end);
+        }
+        _ => report.flag_missing(interval, first_expected, expected_end),
+    }
+
+    for kline in klines.iter().filter(|k| k.volume.is_zero()) {
+        report.push(QualityIssue::new(
+            kline.timestamp,
+            QualityIssueKind::ZeroVolume,
+            QualityAction::Flagged,
+            "成交量为0",
+        ));
+    }
+
+    let expected = report.bars + report.missing_bars;
+    report.metrics = DataQualityMetrics {
+        latency_ms: u64::try_from(latency.as_millis()).unwrap_or(u64::MAX),
+        accuracy: None,
+        completeness: if expected == 0 {
+            0.0
+        } else {
+            report.bars as f64 / expected as f64
+        },
+        freshness_ms: klines
+            .last()
+            .map(|k| (end.min(now) - interval.next_bucket(k.timestamp)).max(0)),
+    };
+    report
+}
+
+/// `[from, to)` 内的周期数
+fn count_buckets(interval: &Interval, from: i64, to: i64) -> usize {
+    if from >= to {
+        return 0;
+    }
+    match interval.millis() {
+        Some(width) => usize::try_from((to - from) / width).unwrap_or(0),
+        None => {
+            let mut count = 0;
+            let mut cursor = from;
+            while cursor < to {
+                count += 1;
+                cursor = interval.next_bucket(cursor);
+            }
+            count
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::kline;
+
+    const HOUR_MS: i64 = 3_600_000;
+
+    #[test]
+    fn repairs_ranges_and_drops_invalid_and_duplicate_bars() {
+        let interval = Interval::parse("1h").unwrap();
+        let klines = vec![
+            kline(HOUR_MS, ["100", "99", "98", "101", "5"]),
+            kline(0, ["100", "101", "99", "100", "1"]),
+            kline(2 * HOUR_MS, ["0", "1", "0", "1", "1"]),
+            kline(HOUR_MS, ["100", "102", "98", "101", "6"]),
+        ];
+
+        let (klines, issues) = sanitize(klines, &interval);
+
+        assert_eq!(
+            klines.iter().map(|k| k.timestamp).collect::<Vec<_>>(),
+            vec![0, HOUR_MS]
+        );
+        // 重复周期保留最后一根
+        assert_eq!(klines[1].high, "102".parse::<Decimal>().unwrap());
+        let kinds: Vec<_> = issues.iter().map(|i| i.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                QualityIssueKind::OutOfOrder,
+                QualityIssueKind::InconsistentRange,
+                QualityIssueKind::Duplicate,
+                QualityIssueKind::InvalidValue,
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_gaps_zero_volume_and_freshness() {
+        let interval = Interval::parse("1h").unwrap();
+        let klines = vec![
+            kline(0, ["100", "101", "99", "100", "1"]),
+            kline(3 * HOUR_MS, ["100", "100", "100", "100", "0"]),
+        ];
+
+        let now = 4 * HOUR_MS + HOUR_MS / 2;
+
+        let report = inspect(
+            &klines,
+            &interval,
+            Vec::new(),
+            (0, now),
+            now,
+            Duration::ZERO,
+        );
+
+        // 4h 的K线尚未收盘，不计入缺失
+        assert_eq!(report.missing_bars, 2);
+        assert_eq!(report.zero_volume_bars, 1);
+        assert_eq!(report.metrics.completeness, 0.5);
+        assert_eq!(report.metrics.freshness_ms, Some(HOUR_MS / 2));
+        assert_eq!(report.issues[0].timestamp, HOUR_MS);
+    }
+
+    #[test]
+    fn counts_missing_bars_at_both_ends_of_window() {
+        let interval = Interval::parse("1h").unwrap();
+        let klines = vec![
+            kline(2 * HOUR_MS, ["100", "101", "99", "100", "1"]),
+            kline(3 * HOUR_MS, ["100", "101", "99", "100", "1"]),
+        ];
+        let window = (HOUR_MS / 2, 6 * HOUR_MS);
+
+        let report = inspect(
+            &klines,
+            &interval,
+            Vec::new(),
+            window,
+            10 * HOUR_MS,
+            Duration::ZERO,
+        );
+
+        // 区间内开盘的周期为 1h-6h，缺少开头 1 根和末尾 3 根
+        assert_eq!(report.missing_bars, 4);
+        assert_eq!(report.metrics.completeness, 2.0 / 6.0);
+        assert_eq!(report.issues[0].timestamp, HOUR_MS);
+        assert_eq!(report.issues[1].timestamp, 4 * HOUR_MS);
+
+        let empty = inspect(
+            &[],
+            &interval,
+            Vec::new(),
+            window,
+            10 * HOUR_MS,
+            Duration::ZERO,
+        );
+
+        assert_eq!(empty.missing_bars, 6);
+        assert_eq!(empty.metrics.completeness, 0.0);
+    }
+
+    #[test]
+    fn flags_spikes_against_other_sources() {
+        let interval = Interval::parse("1h").unwrap();
+        let klines = vec![
+            kline(0, ["100", "101", "99", "100", "1"]),
+            kline(HOUR_MS, ["100", "150", "99", "100", "1"]),
+        ];
+        let reference = vec![
+            kline(0, ["100", "101", "99", "100.1", "1"]),
+            kline(HOUR_MS, ["100", "101", "99", "100", "1"]),
+        ];
+        let mut report = inspect(
+            &klines,
+            &interval,
+            Vec::new(),
+            (0, HOUR_MS),
+            0,
+            Duration::ZERO,
+        );
+
+        report.cross_check(&klines, &interval, &[&reference], 50.0);
+
+        assert_eq!(report.outliers, 1);
+        assert_eq!(report.metrics.accuracy, Some(0.5));
+        assert_eq!(report.issues[0].timestamp, HOUR_MS);
+        assert!(report.issues[0].message.starts_with("最高价"));
+    }
+}
@@ -0,0 +1,403 @@
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Url};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::MarketDataError;
+
+/// 默认最长排队时间（毫秒），需要等待更久的请求直接失败
+const DEFAULT_MAX_WAIT_MS: u64 = 2000;
+/// 上游返回 429/418 但没有 `Retry-After` 时暂停请求的时间
+const DEFAULT_PAUSE: Duration = Duration::from_secs(10);
+
+/// 令牌桶规格：`window` 内最多消耗 `capacity` 权重，令牌匀速补充
+pub struct BucketSpec {
+    pub name: &'static str,
+    pub capacity: u32,
+    pub window: Duration,
+}
+
+impl BucketSpec {
+    pub const fn new(name: &'static str, capacity: u32, window: Duration) -> Self {
+        Self {
+            name,
+            capacity,
+            window,
+        }
+    }
+}
+
+/// 单个请求消耗的令牌桶和权重
+pub struct RequestCost {
+    pub bucket: &'static str,
+    pub weight: u32,
+}
+
+impl RequestCost {
+    pub const fn new(bucket: &'static str, weight: u32) -> Self {
+        Self { bucket, weight }
+    }
+}
+
+/// 交易所公布的限频规则
+pub struct RateLimitRules {
+    /// 第一个桶为未匹配到的请求使用的默认桶
+    pub buckets: &'static [BucketSpec],
+    /// 根据请求地址计算消耗的桶和权重
+    pub cost: fn(&Url) -> RequestCost,
+    /// 交易所返回已用权重的响应头 `(桶, 响应头)`，如 Binance 的 `x-mbx-used-weight-1m`
+    pub used_weight_headers: &'static [(&'static str, &'static str)],
+}
+
+struct Bucket {
+    spec: &'static BucketSpec,
+    /// 可用令牌，排队中的请求会预占令牌，此时为负数
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn rate_per_sec(&self) -> f64 {
+        f64::from(self.spec.capacity) / self.spec.window.as_secs_f64()
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.rate_per_sec()).min(f64::from(self.spec.capacity));
+        self.updated = now;
+    }
+}
+
+struct LimiterState {
+    buckets: HashMap<&'static str, Bucket>,
+    /// 上游返回限频错误后暂停到该时间
+    paused_until: Option<Instant>,
+    queued_requests: u64,
+    rejected_requests: u64,
+}
+
+/// 单个令牌桶的状态
+#[derive(Debug, Clone, Serialize)]
+pub struct BucketSnapshot {
+    pub name: &'static str,
+    pub capacity: u32,
+    pub window_ms: u64,
+    /// 可用权重，为负数时表示有请求在排队
+    pub available: f64,
+}
+
+/// 限频器状态快照
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitSnapshot {
+    pub buckets: Vec<BucketSnapshot>,
+    pub max_wait_ms: u64,
+    /// 因令牌不足排队等待过的请求数
+    pub queued_requests: u64,
+    /// 需要等待超过 `max_wait_ms` 而直接拒绝的请求数
+    pub rejected_requests: u64,
+    /// 上游限频导致的剩余暂停时间（毫秒）
+    pub paused_for_ms: Option<u64>,
+}
+
+/// 按交易所公布的请求权重限制上游请求频率的令牌桶
+///
+/// 令牌不足时请求排队等待，预计等待超过 `max_wait` 时快速失败并返回 `RateLimited`；
+/// 上游返回的已用权重会同步到令牌桶，429/418 响应会按 `Retry-After` 暂停全部请求。
+pub struct RateLimiter {
+    exchange: &'static str,
+    rules: &'static RateLimitRules,
+    max_wait: Duration,
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    /// 最长排队时间由 `MARKET_RATE_LIMIT_MAX_WAIT_MS` 指定
+    pub fn new(exchange: &'static str, rules: &'static RateLimitRules) -> Self {
+        let max_wait_ms = std::env::var("MARKET_RATE_LIMIT_MAX_WAIT_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_MAX_WAIT_MS);
+        Self::with_max_wait(exchange, rules, Duration::from_millis(max_wait_ms))
+    }
+
+    pub fn with_max_wait(
+        exchange: &'static str,
+        rules: &'static RateLimitRules,
+        max_wait: Duration,
+    ) -> Self {
+        let now = Instant::now();
+        let buckets = rules
+            .buckets
+            .iter()
+            .map(|spec| {
+                (
+                    spec.name,
+                    Bucket {
+                        spec,
+                        tokens: f64::from(spec.capacity),
+                        updated: now,
+                    },
+                )
+            })
+            .collect();
+        Self {
+            exchange,
+            rules,
+            max_wait,
+            state: Mutex::new(LimiterState {
+                buckets,
+                paused_until: None,
+                queued_requests: 0,
+                rejected_requests: 0,
+            }),
+        }
+    }
+
+    pub fn exchange(&self) -> &'static str {
+        self.exchange
+    }
+
+    fn cost(&self, url: &str) -> RequestCost {
+        match Url::parse(url) {
+            Ok(url) => (self.rules.cost)(&url),
+            Err(_) => RequestCost::new(self.default_bucket(), 1),
+        }
+    }
+
+    fn default_bucket(&self) -> &'static str {
+        self.rules.buckets.first().map_or("default", |b| b.name)
+    }
+
+    /// 请求前获取令牌：令牌不足时排队，预计等待超过上限时返回 `RateLimited`
+    pub async fn acquire(&self, url: &str) -> Result<(), MarketDataError> {
+        let cost = self.cost(url);
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            let paused = state
+                .paused_until
+                .filter(|until| *until > now)
+                .map(|until| until - now)
+                .unwrap_or_default();
+
+            let bucket_name = if state.buckets.contains_key(cost.bucket) {
+                cost.bucket
+            } else {
+                self.default_bucket()
+            };
+            let Some(bucket) = state.buckets.get_mut(bucket_name) else {
+                return Ok(());
+            };
+            bucket.refill(now);
+
+            let weight = f64::from(cost.weight.min(bucket.spec.capacity));
+            let deficit = weight - bucket.tokens;
+            let wait = if deficit > 0.0 {
+                paused.max(Duration::from_secs_f64(deficit / bucket.rate_per_sec()))
+            } else {
+                paused
+            };
+
+            if wait > self.max_wait {
+                state.rejected_requests += 1;
+                return Err(MarketDataError::RateLimited {
+                    exchange: self.exchange,
+                    retry_after_ms: wait.as_millis() as u64,
+                });
+            }
+            // 排队的请求预占令牌，后到的请求按顺序等待更久
+            bucket.tokens -= weight;
+            if !wait.is_zero() {
+                state.queued_requests += 1;
+            }
+            wait
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    /// 根据响应状态码和响应头同步已用权重；上游限频时暂停请求并返回暂停时间
+    pub fn observe(&self, url: &str, status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+        let cost = self.cost(url);
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+
+        for (bucket_name, header) in self.rules.used_weight_headers {
+            if *bucket_name != cost.bucket {
+                continue;
+            }
+            let used = headers
+                .get(*header)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u32>().ok());
+            if let (Some(used), Some(bucket)) = (used, state.buckets.get_mut(bucket_name)) {
+                bucket.refill(now);
+                let remaining = f64::from(bucket.spec.capacity.saturating_sub(used));
+                bucket.tokens = bucket.tokens.min(remaining);
+            }
+        }
+
+        if status != StatusCode::TOO_MANY_REQUESTS && status.as_u16() != 418 {
+            return None;
+        }
+        let pause = headers
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_PAUSE);
+        let until = now + pause;
+        if state.paused_until.is_none_or(|current| current < until) {
+            state.paused_until = Some(until);
+        }
+        println!(
+            "⏳ {} 返回限频响应 {}，暂停请求 {} 秒",
+            self.exchange,
+            status,
+            pause.as_secs()
+        );
+        Some(pause)
+    }
+
+    pub fn snapshot(&self) -> RateLimitSnapshot {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let buckets = self
+            .rules
+            .buckets
+            .iter()
+            .filter_map(|spec| {
+                let bucket = state.buckets.get_mut(spec.name)?;
+                bucket.refill(now);
+                Some(BucketSnapshot {
+                    name: spec.name,
+                    capacity: spec.capacity,
+                    window_ms: spec.window.as_millis() as u64,
+                    available: (bucket.tokens * 100.0).round() / 100.0,
+                })
+            })
+            .collect();
+
+        RateLimitSnapshot {
+            buckets,
+            max_wait_ms: self.max_wait.as_millis() as u64,
+            queued_requests: state.queued_requests,
+            rejected_requests: state.rejected_requests,
+            paused_for_ms: state
+                .paused_until
+                .filter(|until| *until > now)
+                .map(|until| (until - now).as_millis() as u64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, RETRY_AFTER};
+
+    /// 每秒补充 8 个令牌，等待时间都是精确的二进制小数
+    static RULES: RateLimitRules = RateLimitRules {
+        buckets: &[BucketSpec::new("default", 8, Duration::from_secs(1))],
+        cost: weight_param,
+        used_weight_headers: &[],
+    };
+
+    /// 权重取自查询参数 `weight`
+    fn weight_param(url: &Url) -> RequestCost {
+        let weight = url
+            .query_pairs()
+            .find(|(key, _)| key == "weight")
+            .and_then(|(_, value)| value.parse().ok())
+            .unwrap_or(1);
+        RequestCost::new("default", weight)
+    }
+
+    fn url(weight: u32) -> String {
+        format!("https://example.com/api?weight={}", weight)
+    }
+
+    /// 获取令牌并返回排队时间（暂停的时钟在等待时自动前进）
+    async fn timed_acquire(
+        limiter: &RateLimiter,
+        weight: u32,
+    ) -> Result<Duration, MarketDataError> {
+        let started = Instant::now();
+        limiter.acquire(&url(weight)).await?;
+        Ok(started.elapsed())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queues_within_max_wait_and_fails_fast_beyond_it() {
+        let limiter = RateLimiter::with_max_wait("test", &RULES, Duration::from_millis(500));
+
+        assert_eq!(timed_acquire(&limiter, 8).await.unwrap(), Duration::ZERO);
+        assert_eq!(
+            timed_acquire(&limiter, 4).await.unwrap(),
+            Duration::from_millis(500)
+        );
+
+        let error = timed_acquire(&limiter, 6).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            MarketDataError::RateLimited {
+                retry_after_ms: 750,
+                ..
+            }
+        ));
+        let snapshot = limiter.snapshot();
+        assert_eq!(snapshot.queued_requests, 1);
+        assert_eq!(snapshot.rejected_requests, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clamps_weight_to_bucket_capacity() {
+        let limiter = RateLimiter::with_max_wait("test", &RULES, Duration::from_secs(2));
+
+        // 权重超过桶容量的请求按整桶计算，而不是永远无法获取
+        assert_eq!(timed_acquire(&limiter, 100).await.unwrap(), Duration::ZERO);
+        assert_eq!(
+            timed_acquire(&limiter, 100).await.unwrap(),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pauses_requests_after_rate_limited_response() {
+        let limiter = RateLimiter::with_max_wait("test", &RULES, Duration::from_secs(5));
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+
+        assert_eq!(limiter.observe(&url(1), StatusCode::OK, &headers), None);
+        let pause = limiter.observe(&url(1), StatusCode::TOO_MANY_REQUESTS, &headers);
+
+        assert_eq!(pause, Some(Duration::from_secs(3)));
+        assert_eq!(limiter.snapshot().paused_for_ms, Some(3000));
+        // 令牌充足，仍要等到暂停结束
+        assert_eq!(
+            timed_acquire(&limiter, 1).await.unwrap(),
+            Duration::from_secs(3)
+        );
+        assert_eq!(timed_acquire(&limiter, 1).await.unwrap(), Duration::ZERO);
+
+        // 没有 Retry-After 时按默认时间暂停，超过最长排队时间的请求直接失败
+        let pause = limiter.observe(&url(1), StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new());
+
+        assert_eq!(pause, Some(DEFAULT_PAUSE));
+        assert!(matches!(
+            timed_acquire(&limiter, 1).await.unwrap_err(),
+            MarketDataError::RateLimited {
+                retry_after_ms: 10_000,
+                ..
+            }
+        ));
+    }
+}
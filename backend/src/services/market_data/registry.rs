@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use super::health::{HealthSnapshot, MonitoredSource};
+use super::replay::HttpMode;
+use super::{
+    BinanceSource, BybitSource, CoinGeckoSource, CoinbaseSource, KrakenSource, MarketDataError,
+    MarketDataSource, OkxSource, YahooSource,
+};
+
+/// 数据源注册表，按注册顺序作为回退优先级
+///
+/// 注册的数据源统一包装为 [`MonitoredSource`]，记录健康统计并在持续失败时熔断。
+pub struct MarketDataRegistry {
+    sources: Vec<Arc<MonitoredSource>>,
+    http_mode: HttpMode,
+}
+
+impl MarketDataRegistry {
+    pub fn new(http_mode: HttpMode) -> Self {
+        Self {
+            sources: Vec::new(),
+            http_mode,
+        }
+    }
+
+    /// 默认注册表：优先使用OKX，其次 Binance、Bybit、Coinbase、Kraken 等交易所，
+    /// 最后是 CoinGecko、Yahoo 等聚合数据；各数据源按 `http_mode` 请求上游
+    pub fn with_default_sources(http_mode: HttpMode) -> Self {
+        let mut registry = Self::new(http_mode);
+        let mode = registry.http_mode.clone();
+        registry.register(Arc::new(OkxSource::new(&mode)));
+        registry.register(Arc::new(BinanceSource::new(&mode)));
+        registry.register(Arc::new(BybitSource::new(&mode)));
+        registry.register(Arc::new(CoinbaseSource::new(&mode)));
+        registry.register(Arc::new(KrakenSource::new(&mode)));
+        registry.register(Arc::new(CoinGeckoSource::new(&mode)));
+        registry.register(Arc::new(YahooSource::new(&mode)));
+        registry
+    }
+
+    /// 数据源的上游请求模式，其他上游客户端（如恐惧贪婪指数）使用同一模式
+    pub fn http_mode(&self) -> &HttpMode {
+        &self.http_mode
+    }
+
+    /// 注册数据源，同名数据源会被替换并保留原有优先级
+    pub fn register(&mut self, source: Arc<dyn MarketDataSource>) {
+        let source = Arc::new(MonitoredSource::new(source));
+        match self.sources.iter().position(|s| s.name() == source.name()) {
+            Some(index) => self.sources[index] = source,
+            None => self.sources.push(source),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Arc<dyn MarketDataSource>, MarketDataError> {
+        self.sources
+            .iter()
+            .find(|s| s.name() == name)
+            .map(|s| s.clone() as Arc<dyn MarketDataSource>)
+            .ok_or_else(|| MarketDataError::UnknownSource(name.to_string()))
+    }
+
+    /// 按优先级顺序返回所有数据源
+    pub fn sources(&self) -> &[Arc<MonitoredSource>] {
+        &self.sources
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.sources.iter().map(|s| s.name()).collect()
+    }
+
+    /// 各数据源的健康状态与熔断器状态
+    pub fn health(&self) -> Vec<HealthSnapshot> {
+        self.sources.iter().map(|s| s.health()).collect()
+    }
+}
+
+impl Default for MarketDataRegistry {
+    fn default() -> Self {
+        Self::with_default_sources(HttpMode::Live)
+    }
+}
@@ -0,0 +1,208 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+use super::MarketDataError;
+
+/// 录制文件的默认目录
+const DEFAULT_FIXTURE_DIR: &str = "tests/fixtures/recorded";
+
+/// 上游HTTP请求模式，由 `MARKET_HTTP_MODE` 指定，创建数据源时传入
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpMode {
+    /// 直接请求交易所（默认）
+    Live,
+    /// 请求交易所并把响应保存到录制目录
+    Record(PathBuf),
+    /// 只从录制目录读取响应，不访问网络
+    Replay(PathBuf),
+}
+
+/// 录制的一次上游响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub url: String,
+    pub status: u16,
+    /// 可解析为JSON时保存解析后的内容，否则保存原始文本
+    pub body: Value,
+}
+
+impl RecordedResponse {
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// 响应体原文，用于错误信息
+    pub fn text(&self) -> String {
+        match &self.body {
+            Value::String(text) => text.clone(),
+            body => body.to_string(),
+        }
+    }
+}
+
+impl HttpMode {
+    /// 读取 `MARKET_HTTP_MODE`（`live`/`record`/`replay`）和录制目录 `MARKET_FIXTURE_DIR`
+    pub fn from_env() -> Self {
+        let dir = std::env::var("MARKET_FIXTURE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_FIXTURE_DIR));
+        let mode = match std::env::var("MARKET_HTTP_MODE").as_deref() {
+            Ok("record") => Self::Record(dir),
+            Ok("replay") => Self::Replay(dir),
+            Ok("live") | Err(_) => Self::Live,
+            Ok(other) => {
+                println!("⚠️ 未知的 MARKET_HTTP_MODE: {}，使用实时请求", other);
+                Self::Live
+            }
+        };
+        match &mode {
+            Self::Record(dir) => println!("📼 录制上游响应到 {}", dir.display()),
+            Self::Replay(dir) => println!("📼 从 {} 回放上游响应", dir.display()),
+            Self::Live => {}
+        }
+        mode
+    }
+
+    /// 回放模式下读取录制的响应，缺少录制文件时返回错误
+    pub async fn replay(
+        &self,
+        exchange: &'static str,
+        url: &str,
+    ) -> Option<Result<RecordedResponse, MarketDataError>> {
+        let Self::Replay(dir) = self else {
+            return None;
+        };
+        let path = fixture_path(dir, exchange, url);
+        let result = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                MarketDataError::replay(exchange, format!("{} 格式无效: {}", path.display(), e))
+            }),
+            Err(e) => Err(MarketDataError::replay(
+                exchange,
+                format!("缺少 {} 的录制文件 {}: {}", url, path.display(), e),
+            )),
+        };
+        Some(result)
+    }
+
+    /// 录制模式下保存响应，写入失败只记录日志，不影响本次请求
+    pub async fn record(&self, exchange: &'static str, url: &str, status: StatusCode, body: &str) {
+        let Self::Record(dir) = self else {
+            return;
+        };
+        let recorded = RecordedResponse {
+            url: url.to_string(),
+            status: status.as_u16(),
+            body: serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.to_string())),
+        };
+        let path = fixture_path(dir, exchange, url);
+        let result = async {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let bytes = serde_json::to_vec_pretty(&recorded)?;
+            tokio::fs::write(&path, bytes).await
+        }
+        .await;
+        match result {
+            Ok(()) => println!("📼 已录制 {} -> {}", url, path.display()),
+            Err(e) => println!("❌ 录制 {} 失败: {}", url, e),
+        }
+    }
+}
+
+/// 录制文件路径：`<目录>/<数据源>/<接口路径>-<完整URL哈希>.json`
+///
+/// 哈希覆盖查询参数，参数相同的请求总是对应同一个文件；
+/// 请求中带有当前时间的查询需要固定时间参数才能稳定回放。
+pub fn fixture_path(dir: &std::path::Path, exchange: &str, url: &str) -> PathBuf {
+    let endpoint = reqwest::Url::parse(url)
+        .map(|u| u.path().to_string())
+        .unwrap_or_default();
+    let slug: String = endpoint
+        .trim_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir.join(exchange)
+        .join(format!("{}-{:016x}.json", slug, fnv1a(url.as_bytes())))
+}
+
+/// FNV-1a 64位哈希，结果与 Rust 版本和运行平台无关，保证文件名稳定
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::dec;
+    use crate::services::market_data::{CoinGeckoSource, MarketDataSource, OkxSource, Symbol};
+
+    fn replay_mode() -> HttpMode {
+        HttpMode::Replay(PathBuf::from(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/recorded"
+        )))
+    }
+
+    #[test]
+    fn fixture_path_is_stable_per_url() {
+        let dir = std::path::Path::new("fixtures");
+        let url = "https://www.okx.com/api/v5/market/ticker?instId=BTC-USDT";
+
+        assert_eq!(
+            fixture_path(dir, "okx", url),
+            dir.join("okx/api_v5_market_ticker-40c2ea61a0001e02.json")
+        );
+        assert_ne!(
+            fixture_path(dir, "okx", url),
+            fixture_path(dir, "okx", &url.replace("BTC", "ETH"))
+        );
+    }
+
+    #[actix_rt::test]
+    async fn replays_recorded_ticker_through_adapter() {
+        let symbol = Symbol::parse("BTC/USDT").unwrap();
+
+        let ticker = OkxSource::new(&replay_mode())
+            .fetch_ticker(&symbol)
+            .await
+            .unwrap();
+
+        assert_eq!(ticker.symbol, "BTC/USDT");
+        assert_eq!(ticker.price, dec("67850.1"));
+        assert_eq!(ticker.high_24h, Some(dec("68420.0")));
+        assert_eq!(ticker.timestamp, 1_717_023_600_123);
+    }
+
+    #[actix_rt::test]
+    async fn replays_recorded_global_metrics() {
+        let metrics = CoinGeckoSource::new(&replay_mode())
+            .fetch_global_metrics()
+            .await
+            .unwrap();
+
+        assert_eq!(metrics.total_market_cap, 2_647_093_851_234.56);
+        assert_eq!(metrics.total_volume_24h, 81_512_345_678.9);
+        assert_eq!(metrics.btc_dominance, 50.1234);
+        assert_eq!(metrics.eth_dominance, 16.8765);
+        assert_eq!(metrics.timestamp, 1_717_023_600_000);
+    }
+
+    #[actix_rt::test]
+    async fn missing_recording_fails_without_network() {
+        let symbol = Symbol::parse("DOGE/USDT").unwrap();
+
+        let error = OkxSource::new(&replay_mode())
+            .fetch_ticker(&symbol)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, MarketDataError::Replay { .. }), "{}", error);
+    }
+}
@@ -0,0 +1,153 @@
+use super::interval::Interval;
+use super::KlineData;
+
+/// 从数据源支持的周期中选出可拼接为目标周期的最大基础周期
+pub fn choose_base_interval(target: &Interval, supported: &[&str]) -> Option<Interval> {
+    supported
+        .iter()
+        .filter_map(|interval| Interval::parse(interval))
+        .filter(|base| base.divides(target))
+        .max_by_key(|base| base.approx_millis())
+}
+
+/// 将升序排列的K线聚合为目标周期
+///
+/// 开盘价取周期内第一根、收盘价取最后一根，最高/最低取极值，成交量求和；
+/// 时间戳为目标周期的开始时间（UTC 对齐）。
+/// 基础K线不是从周期起点开始时，第一根聚合K线不完整，丢弃。
+pub fn resample(klines: &[KlineData], target: &Interval) -> Vec<KlineData> {
+    let mut buckets: Vec<KlineData> = Vec::new();
+
+    for kline in klines {
+        let bucket_start = target.bucket_start(kline.timestamp);
+        match buckets.last_mut() {
+            Some(bucket) if bucket.timestamp == bucket_start => {
+                bucket.high = bucket.high.max(kline.high);
+                bucket.low = bucket.low.min(kline.low);
+                bucket.close = kline.close;
+                bucket.volume += kline.volume;
+            }
+            _ => buckets.push(KlineData {
+                timestamp: bucket_start,
+                ..kline.clone()
+            }),
+        }
+    }
+
+    if let (Some(first_base), Some(first)) = (klines.first(), buckets.first()) {
+        if first_base.timestamp > first.timestamp {
+            buckets.remove(0);
+        }
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::market_data::test_support::{dec, kline};
+
+    const MINUTE_MS: i64 = 60_000;
+    const HOUR_MS: i64 = 60 * MINUTE_MS;
+    const DAY_MS: i64 = 24 * HOUR_MS;
+    /// 2024-05-27（周一）00:00 UTC
+    const MONDAY_MS: i64 = 1_716_768_000_000;
+
+    fn interval(s: &str) -> Interval {
+        Interval::parse(s).unwrap()
+    }
+
+    /// 从 `start` 起每隔 `step` 一根，收盘价依次为 1、2、3…，成交量均为 1
+    fn series(start: i64, step: i64, count: i64) -> Vec<KlineData> {
+        (0..count)
+            .map(|i| {
+                let close = (i + 1).to_string();
+                kline(start + i * step, ["1", &close, "0", &close, "1"])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chooses_largest_supported_interval_that_divides_target() {
+        let supported = ["1m", "5m", "15m", "1h", "1d", "1w"];
+        let base = |target: &str| choose_base_interval(&interval(target), &supported);
+
+        assert_eq!(base("7m"), Some(interval("1m")));
+        assert_eq!(base("45m"), Some(interval("15m")));
+        assert_eq!(base("4h"), Some(interval("1h")));
+        assert_eq!(base("2w"), Some(interval("1w")));
+        // 周线边界与月初不对齐，月线只能由日线拼接
+        assert_eq!(base("1M"), Some(interval("1d")));
+        assert_eq!(choose_base_interval(&interval("7m"), &["5m", "1h"]), None);
+    }
+
+    #[test]
+    fn resamples_minutes_into_seven_minute_bars() {
+        // 7分钟周期从纪元起对齐，周一 00:01 恰为边界
+        let start = MONDAY_MS + MINUTE_MS;
+        let klines = resample(&series(start, MINUTE_MS, 14), &interval("7m"));
+
+        assert_eq!(klines.len(), 2);
+        assert_eq!(klines[0].timestamp, start);
+        assert_eq!(klines[1].timestamp, start + 7 * MINUTE_MS);
+        assert_eq!(klines[0].open, dec("1"));
+        assert_eq!(klines[0].high, dec("7"));
+        assert_eq!(klines[0].close, dec("7"));
+        assert_eq!(klines[1].close, dec("14"));
+        assert_eq!(klines[1].volume, dec("7"));
+    }
+
+    #[test]
+    fn resamples_hours_into_four_hour_bars_and_drops_incomplete_first() {
+        // 从 02:00 开始，00:00 的4小时周期缺少前两根
+        let klines = resample(
+            &series(MONDAY_MS + 2 * HOUR_MS, HOUR_MS, 10),
+            &interval("4h"),
+        );
+
+        let timestamps: Vec<_> = klines.iter().map(|k| k.timestamp).collect();
+        assert_eq!(
+            timestamps,
+            vec![MONDAY_MS + 4 * HOUR_MS, MONDAY_MS + 8 * HOUR_MS]
+        );
+        assert_eq!(klines[0].close, dec("6"));
+        assert_eq!(klines[0].volume, dec("4"));
+        // 最后一个周期正在形成，保留
+        assert_eq!(klines[1].close, dec("10"));
+        assert_eq!(klines[1].volume, dec("4"));
+    }
+
+    #[test]
+    fn resamples_days_into_weeks_anchored_on_monday() {
+        let klines = resample(&series(MONDAY_MS, DAY_MS, 14), &interval("1w"));
+
+        assert_eq!(klines.len(), 2);
+        assert_eq!(klines[0].timestamp, MONDAY_MS);
+        assert_eq!(klines[1].timestamp, MONDAY_MS + 7 * DAY_MS);
+        assert_eq!(klines[0].close, dec("7"));
+        assert_eq!(klines[1].volume, dec("7"));
+
+        // 从周三开始时第一周不完整
+        let klines = resample(&series(MONDAY_MS + 2 * DAY_MS, DAY_MS, 12), &interval("1w"));
+        assert_eq!(klines.len(), 1);
+        assert_eq!(klines[0].timestamp, MONDAY_MS + 7 * DAY_MS);
+    }
+
+    #[test]
+    fn resamples_days_into_calendar_months() {
+        // 2024-05-01 起 62 天：5月31天，6月30天，7月1天
+        let may_first = 1_714_521_600_000;
+        let klines = resample(&series(may_first, DAY_MS, 62), &interval("1M"));
+
+        let timestamps: Vec<_> = klines.iter().map(|k| k.timestamp).collect();
+        assert_eq!(
+            timestamps,
+            vec![may_first, 1_717_200_000_000, 1_719_792_000_000]
+        );
+        assert_eq!(klines[0].close, dec("31"));
+        assert_eq!(klines[1].open, dec("1"));
+        assert_eq!(klines[1].close, dec("61"));
+        assert_eq!(klines[1].volume, dec("30"));
+    }
+}
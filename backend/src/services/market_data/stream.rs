@@ -0,0 +1,553 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::binance::BinanceStream;
+use super::okx::OkxStream;
+use super::replay::HttpMode;
+use super::{KlineData, MarketDataError, PublicTrade, Symbol, Ticker};
+
+/// 广播总线容量，接收方落后超过该数量的事件会丢失最旧的事件
+const BUS_CAPACITY: usize = 4096;
+/// 单条订阅消息最多包含的主题数
+const SUBSCRIBE_BATCH: usize = 50;
+/// 每个连接最多订阅的主题数
+const MAX_TOPICS_PER_CONNECTION: usize = 200;
+/// 心跳间隔
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+/// 超过该时间没有收到任何消息即视为连接失效并重连
+const STALE_TIMEOUT: Duration = Duration::from_secs(60);
+/// 默认重连退避：从1秒开始翻倍，最长60秒
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 推送频道，K线频道带标准周期（如 `1m`、`1h`）
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StreamChannel {
+    Ticker,
+    Kline(String),
+    Trade,
+}
+
+/// 推送主题：频道 + 交易对
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StreamTopic {
+    pub channel: StreamChannel,
+    pub symbol: Symbol,
+}
+
+impl StreamTopic {
+    pub fn new(channel: StreamChannel, symbol: Symbol) -> Self {
+        Self { channel, symbol }
+    }
+}
+
+/// 推送的K线，`closed` 为 `false` 时是尚未收盘的当前K线
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamKline {
+    pub symbol: String,
+    pub interval: String,
+    pub closed: bool,
+    #[serde(flatten)]
+    pub kline: KlineData,
+}
+
+/// 统一格式的实时行情事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+pub enum MarketEvent {
+    Ticker(Ticker),
+    Kline(StreamKline),
+    Trade(PublicTrade),
+}
+
+impl MarketEvent {
+    pub fn source(&self) -> &str {
+        match self {
+            MarketEvent::Ticker(ticker) => &ticker.source,
+            MarketEvent::Kline(kline) => &kline.kline.source,
+            MarketEvent::Trade(trade) => &trade.source,
+        }
+    }
+
+    /// 事件是否属于该主题
+    pub fn matches(&self, topic: &StreamTopic) -> bool {
+        let symbol = match (self, &topic.channel) {
+            (MarketEvent::Ticker(ticker), StreamChannel::Ticker) => &ticker.symbol,
+            (MarketEvent::Kline(kline), StreamChannel::Kline(interval))
+                if kline.interval == *interval =>
+            {
+                &kline.symbol
+            }
+            (MarketEvent::Trade(trade), StreamChannel::Trade) => &trade.symbol,
+            _ => return false,
+        };
+        symbol.split_once('/') == Some((topic.symbol.base.as_str(), topic.symbol.quote.as_str()))
+    }
+}
+
+/// 交易所 WebSocket 协议：订阅消息的格式和推送消息的解析
+pub trait StreamProtocol: Send + Sync {
+    /// 数据源名称，与 REST 数据源一致
+    fn name(&self) -> &'static str;
+
+    fn url(&self) -> &str;
+
+    /// 该连接是否提供此频道
+    fn supports(&self, channel: &StreamChannel) -> bool;
+
+    /// 订阅一批主题的消息，没有可订阅的主题时返回 `None`
+    fn subscribe_message(&self, topics: &[StreamTopic]) -> Option<String>;
+
+    /// 取消订阅一批主题的消息，没有可取消的主题时返回 `None`
+    fn unsubscribe_message(&self, topics: &[StreamTopic]) -> Option<String>;
+
+    /// 解析推送消息，订阅确认等控制消息返回空列表
+    fn parse_message(&self, text: &str) -> Result<Vec<MarketEvent>, MarketDataError>;
+
+    /// 应用层心跳消息（如 OKX 的 `ping`），为 `None` 时发送 WebSocket ping 帧
+    fn ping_message(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// 单个推送连接的状态
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamStatus {
+    pub source: String,
+    pub url: String,
+    pub connected: bool,
+    pub topics: usize,
+    pub reconnects: u64,
+}
+
+/// 发给连接任务的订阅变更
+#[derive(Debug)]
+enum StreamCommand {
+    Subscribe(StreamTopic),
+    Unsubscribe(StreamTopic),
+}
+
+struct StreamConnection {
+    protocol: Arc<dyn StreamProtocol>,
+    /// 已订阅的主题及其引用计数
+    topics: Mutex<BTreeMap<StreamTopic, usize>>,
+    commands: mpsc::UnboundedSender<StreamCommand>,
+    receiver: Mutex<Option<mpsc::UnboundedReceiver<StreamCommand>>>,
+    connected: AtomicBool,
+    reconnects: AtomicU64,
+}
+
+impl StreamConnection {
+    fn topics(&self) -> Vec<StreamTopic> {
+        self.topics.lock().unwrap().keys().cloned().collect()
+    }
+
+    fn is_idle(&self) -> bool {
+        self.topics.lock().unwrap().is_empty()
+    }
+}
+
+/// 实时行情接入：维护到交易所的 WebSocket 连接，把推送统一为 [`MarketEvent`] 并通过广播总线分发
+///
+/// 主题按需订阅（[`MarketStream::watch`]），按引用计数在最后一个使用方释放时取消订阅
+/// （[`MarketStream::unwatch`]）；连接断开后按指数退避重连并重新订阅仍在使用的主题。
+pub struct MarketStream {
+    bus: broadcast::Sender<MarketEvent>,
+    connections: Vec<Arc<StreamConnection>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl MarketStream {
+    pub fn new() -> Self {
+        let (bus, _) = broadcast::channel(BUS_CAPACITY);
+        Self {
+            bus,
+            connections: Vec::new(),
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+
+    /// 按环境变量创建：`MARKET_STREAM_ENABLED=false` 或处于HTTP回放模式时不建立任何连接，
+    /// 连接地址可用 `OKX_WS_URL`、`OKX_WS_BUSINESS_URL`、`BINANCE_WS_URL` 覆盖，
+    /// `MARKET_STREAM_SYMBOLS` 中的交易对启动即订阅行情和成交
+    pub fn from_env(http_mode: &HttpMode) -> Self {
+        let mut stream = Self::new();
+        let enabled = std::env::var("MARKET_STREAM_ENABLED")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true);
+        // 回放模式用于离线测试，实时推送无法回放，改为轮询录制的接口
+        if !enabled || matches!(http_mode, HttpMode::Replay(_)) {
+            return stream;
+        }
+
+        let env_url = |key: &str, default: &str| std::env::var(key).unwrap_or(default.to_string());
+        stream.add_protocol(Arc::new(OkxStream::public(env_url(
+            "OKX_WS_URL",
+            OkxStream::PUBLIC_URL,
+        ))));
+        stream.add_protocol(Arc::new(OkxStream::business(env_url(
+            "OKX_WS_BUSINESS_URL",
+            OkxStream::BUSINESS_URL,
+        ))));
+        stream.add_protocol(Arc::new(BinanceStream::new(env_url(
+            "BINANCE_WS_URL",
+            BinanceStream::URL,
+        ))));
+
+        let symbols = std::env::var("MARKET_STREAM_SYMBOLS").unwrap_or_default();
+        for symbol in symbols.split(',').filter(|s| !s.trim().is_empty()) {
+            match Symbol::parse(symbol) {
+                Ok(symbol) => {
+                    for source in stream.sources(&StreamChannel::Ticker) {
+                        for channel in [StreamChannel::Ticker, StreamChannel::Trade] {
+                            stream.watch(source, StreamTopic::new(channel, symbol.clone()));
+                        }
+                    }
+                }
+                Err(e) => log::warn!("MARKET_STREAM_SYMBOLS 配置错误: {}", e),
+            }
+        }
+        stream
+    }
+
+    /// 重连退避的初始和最大间隔
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    pub fn add_protocol(&mut self, protocol: Arc<dyn StreamProtocol>) {
+        let (commands, receiver) = mpsc::unbounded_channel();
+        self.connections.push(Arc::new(StreamConnection {
+            protocol,
+            topics: Mutex::new(BTreeMap::new()),
+            commands,
+            receiver: Mutex::new(Some(receiver)),
+            connected: AtomicBool::new(false),
+            reconnects: AtomicU64::new(0),
+        }));
+    }
+
+    /// 为每个连接启动后台任务，没有订阅主题的连接在第一次订阅时才建立
+    pub fn spawn(&self) {
+        for connection in &self.connections {
+            let Some(receiver) = connection.receiver.lock().unwrap().take() else {
+                continue;
+            };
+            tokio::spawn(run_connection(
+                connection.clone(),
+                self.bus.clone(),
+                receiver,
+                self.initial_backoff,
+                self.max_backoff,
+            ));
+        }
+    }
+
+    /// 订阅广播总线上的全部事件
+    pub fn subscribe(&self) -> broadcast::Receiver<MarketEvent> {
+        self.bus.subscribe()
+    }
+
+    /// 提供该频道推送的数据源
+    pub fn sources(&self, channel: &StreamChannel) -> Vec<&'static str> {
+        let mut sources: Vec<&'static str> = Vec::new();
+        for connection in &self.connections {
+            let name = connection.protocol.name();
+            if connection.protocol.supports(channel) && !sources.contains(&name) {
+                sources.push(name);
+            }
+        }
+        sources
+    }
+
+    fn connection(&self, source: &str, channel: &StreamChannel) -> Option<&Arc<StreamConnection>> {
+        self.connections
+            .iter()
+            .find(|c| c.protocol.name() == source && c.protocol.supports(channel))
+    }
+
+    /// 确保指定数据源推送该主题；数据源不支持或订阅数已满时返回 `false`
+    ///
+    /// 每次成功的调用都要有一次对应的 [`MarketStream::unwatch`]。
+    pub fn watch(&self, source: &str, topic: StreamTopic) -> bool {
+        let Some(connection) = self.connection(source, &topic.channel) else {
+            return false;
+        };
+
+        let mut topics = connection.topics.lock().unwrap();
+        if let Some(count) = topics.get_mut(&topic) {
+            *count += 1;
+            return true;
+        }
+        if topics.len() >= MAX_TOPICS_PER_CONNECTION {
+            return false;
+        }
+        topics.insert(topic.clone(), 1);
+        let _ = connection.commands.send(StreamCommand::Subscribe(topic));
+        true
+    }
+
+    /// 释放一次 [`MarketStream::watch`]，最后一个使用方释放后取消上游订阅
+    pub fn unwatch(&self, source: &str, topic: &StreamTopic) {
+        let Some(connection) = self.connection(source, &topic.channel) else {
+            return;
+        };
+
+        let mut topics = connection.topics.lock().unwrap();
+        let Some(count) = topics.get_mut(topic) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            topics.remove(topic);
+            let _ = connection
+                .commands
+                .send(StreamCommand::Unsubscribe(topic.clone()));
+        }
+    }
+
+    pub fn status(&self) -> Vec<StreamStatus> {
+        self.connections
+            .iter()
+            .map(|c| StreamStatus {
+                source: c.protocol.name().to_string(),
+                url: c.protocol.url().to_string(),
+                connected: c.connected.load(Ordering::Relaxed),
+                topics: c.topics.lock().unwrap().len(),
+                reconnects: c.reconnects.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+impl Default for MarketStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 连接主循环：断开后按指数退避重连，收到过数据的连接断开后退避重置
+async fn run_connection(
+    connection: Arc<StreamConnection>,
+    bus: broadcast::Sender<MarketEvent>,
+    mut commands: mpsc::UnboundedReceiver<StreamCommand>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+) {
+    let name = connection.protocol.name();
+    let mut backoff = initial_backoff;
+    loop {
+        // 没有主题时等待订阅
+        while connection.is_idle() {
+            if commands.recv().await.is_none() {
+                return;
+            }
+        }
+        // 建立连接时会订阅当前全部主题，之前排队的订阅变更不再需要
+        while commands.try_recv().is_ok() {}
+
+        let result = run_session(&connection, &bus, &mut commands).await;
+        connection.connected.store(false, Ordering::Relaxed);
+        match result {
+            Ok(true) => backoff = initial_backoff,
+            Ok(false) => println!("⚠️ {} 行情推送连接关闭", name),
+            Err(e) => println!("❌ {}", e),
+        }
+
+        connection.reconnects.fetch_add(1, Ordering::Relaxed);
+        println!("🔄 {} 行情推送 {:?} 后重连", name, backoff);
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(max_backoff);
+    }
+}
+
+/// 一次连接的生命周期，返回是否收到过行情数据
+async fn run_session(
+    connection: &StreamConnection,
+    bus: &broadcast::Sender<MarketEvent>,
+    commands: &mut mpsc::UnboundedReceiver<StreamCommand>,
+) -> Result<bool, MarketDataError> {
+    let protocol = connection.protocol.as_ref();
+    let name = protocol.name();
+    let stream_error = |e: tokio_tungstenite::tungstenite::Error| MarketDataError::stream(name, e);
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(protocol.url())
+        .await
+        .map_err(stream_error)?;
+
+    let topics = connection.topics();
+    for batch in topics.chunks(SUBSCRIBE_BATCH) {
+        if let Some(message) = protocol.subscribe_message(batch) {
+            ws.send(Message::Text(message))
+                .await
+                .map_err(stream_error)?;
+        }
+    }
+    connection.connected.store(true, Ordering::Relaxed);
+    println!("🔌 {} 行情推送已连接，订阅 {} 个主题", name, topics.len());
+
+    let mut received = false;
+    let mut last_message = Instant::now();
+    let mut ping =
+        tokio::time::interval_at(tokio::time::Instant::now() + PING_INTERVAL, PING_INTERVAL);
+    loop {
+        tokio::select! {
+            message = ws.next() => {
+                let message = match message {
+                    Some(message) => message.map_err(stream_error)?,
+                    None => return Ok(received),
+                };
+                last_message = Instant::now();
+                match message {
+                    Message::Text(text) => match protocol.parse_message(&text) {
+                        Ok(events) => {
+                            received |= !events.is_empty();
+                            for event in events {
+                                // 没有接收方时发送失败，直接丢弃
+                                let _ = bus.send(event);
+                            }
+                        }
+                        Err(e) => println!("⚠️ {}", e),
+                    },
+                    Message::Close(_) => return Ok(received),
+                    _ => {}
+                }
+            }
+            Some(command) = commands.recv() => {
+                let message = match command {
+                    StreamCommand::Subscribe(topic) => protocol.subscribe_message(&[topic]),
+                    StreamCommand::Unsubscribe(topic) => protocol.unsubscribe_message(&[topic]),
+                };
+                if let Some(message) = message {
+                    ws.send(Message::Text(message)).await.map_err(stream_error)?;
+                }
+            }
+            _ = ping.tick() => {
+                if last_message.elapsed() > STALE_TIMEOUT {
+                    return Err(MarketDataError::stream(name, "长时间未收到消息"));
+                }
+                let message = match protocol.ping_message() {
+                    Some(text) => Message::Text(text.to_string()),
+                    None => Message::Ping(Vec::new()),
+                };
+                ws.send(message).await.map_err(stream_error)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::WebSocketStream;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+    const BACKOFF: Duration = Duration::from_millis(100);
+
+    async fn accept(listener: &TcpListener) -> WebSocketStream<TcpStream> {
+        let (socket, _) = tokio::time::timeout(TIMEOUT, listener.accept())
+            .await
+            .expect("等待重连超时")
+            .unwrap();
+        tokio_tungstenite::accept_async(socket).await.unwrap()
+    }
+
+    /// 读取客户端发来的下一条订阅请求，返回 `(method, params)`
+    async fn request(ws: &mut WebSocketStream<TcpStream>) -> (String, Vec<String>) {
+        loop {
+            let message = tokio::time::timeout(TIMEOUT, ws.next())
+                .await
+                .expect("等待订阅消息超时")
+                .unwrap()
+                .unwrap();
+            if let Message::Text(text) = message {
+                let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+                let params = value["params"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p.as_str().unwrap().to_string())
+                    .collect();
+                return (value["method"].as_str().unwrap().to_string(), params);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn normalizes_pushes_and_resubscribes_live_topics_after_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let mut stream = MarketStream::new().with_backoff(BACKOFF, BACKOFF);
+        stream.add_protocol(Arc::new(BinanceStream::new(url)));
+        stream.spawn();
+        let mut bus = stream.subscribe();
+
+        let symbol = Symbol::parse("BTC/USDT").unwrap();
+        let ticker = StreamTopic::new(StreamChannel::Ticker, symbol.clone());
+        let trade = StreamTopic::new(StreamChannel::Trade, symbol);
+        assert!(stream.watch("binance", ticker.clone()));
+
+        let mut ws = accept(&listener).await;
+        assert_eq!(
+            request(&mut ws).await,
+            ("SUBSCRIBE".to_string(), vec!["btcusdt@ticker".to_string()])
+        );
+
+        let push = r#"{"e":"24hrTicker","E":1717023600123,"s":"BTCUSDT","c":"67850.10","p":"-120.5","P":"-0.18","h":"68420.00","l":"67001.00","v":"1234.5","q":"83765432.1"}"#;
+        ws.send(Message::Text(push.to_string())).await.unwrap();
+        let event = tokio::time::timeout(TIMEOUT, bus.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let MarketEvent::Ticker(pushed) = &event else {
+            panic!("应为行情事件: {:?}", event);
+        };
+        assert!(event.matches(&ticker));
+        assert_eq!(pushed.symbol, "BTC/USDT");
+        assert_eq!(pushed.price, "67850.10".parse().unwrap());
+        assert_eq!(pushed.timestamp, 1_717_023_600_123);
+        assert_eq!(pushed.source, "binance");
+
+        // 引用计数归零才取消上游订阅
+        assert!(stream.watch("binance", trade.clone()));
+        assert!(stream.watch("binance", trade.clone()));
+        assert_eq!(
+            request(&mut ws).await,
+            (
+                "SUBSCRIBE".to_string(),
+                vec!["btcusdt@aggTrade".to_string()]
+            )
+        );
+        stream.unwatch("binance", &trade);
+        stream.unwatch("binance", &trade);
+        assert_eq!(
+            request(&mut ws).await,
+            (
+                "UNSUBSCRIBE".to_string(),
+                vec!["btcusdt@aggTrade".to_string()]
+            )
+        );
+
+        drop(ws);
+        let dropped = Instant::now();
+        let mut ws = accept(&listener).await;
+        assert!(dropped.elapsed() >= BACKOFF, "重连前应等待退避间隔");
+        assert_eq!(
+            request(&mut ws).await,
+            ("SUBSCRIBE".to_string(), vec!["btcusdt@ticker".to_string()])
+        );
+        assert_eq!(stream.status()[0].reconnects, 1);
+        assert_eq!(stream.status()[0].topics, 1);
+    }
+}
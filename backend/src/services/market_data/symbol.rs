@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::MarketDataError;
+
+/// 未写计价币时（如 `BTC`）默认的计价币
+const DEFAULT_QUOTE: &str = "USDT";
+
+/// 不带分隔符的写法（如 `ETHBTC`）按这些计价币后缀拆分，较长的在前
+const KNOWN_QUOTES: &[&str] = &[
+    "FDUSD", "USDT", "USDC", "BUSD", "TUSD", "USD", "EUR", "GBP", "TRY", "BRL", "DAI", "BTC",
+    "ETH", "BNB",
+];
+
+/// 视为美元的计价币，在只有美元报价的数据源（Yahoo、CoinGecko）上映射为 USD
+const USD_QUOTES: &[&str] = &["USD", "USDT", "USDC", "BUSD", "FDUSD", "TUSD"];
+
+/// 基础币 -> CoinGecko coin id，同时作为已知基础币列表：
+/// 只写基础币的输入（如 `WBTC`、`STETH`）不会再按计价币后缀拆分
+const COINGECKO_IDS: &[(&str, &str)] = &[
+    ("BTC", "bitcoin"),
+    ("ETH", "ethereum"),
+    ("BNB", "binancecoin"),
+    ("SOL", "solana"),
+    ("XRP", "ripple"),
+    ("ADA", "cardano"),
+    ("DOGE", "dogecoin"),
+    ("TRX", "tron"),
+    ("TON", "the-open-network"),
+    ("DOT", "polkadot"),
+    ("AVAX", "avalanche-2"),
+    ("LINK", "chainlink"),
+    ("MATIC", "matic-network"),
+    ("POL", "polygon-ecosystem-token"),
+    ("LTC", "litecoin"),
+    ("BCH", "bitcoin-cash"),
+    ("SHIB", "shiba-inu"),
+    ("UNI", "uniswap"),
+    ("ATOM", "cosmos"),
+    ("XLM", "stellar"),
+    ("ETC", "ethereum-classic"),
+    ("FIL", "filecoin"),
+    ("APT", "aptos"),
+    ("ARB", "arbitrum"),
+    ("OP", "optimism"),
+    ("NEAR", "near"),
+    ("SUI", "sui"),
+    ("PEPE", "pepe"),
+    ("WBTC", "wrapped-bitcoin"),
+    ("WETH", "weth"),
+    ("STETH", "staked-ether"),
+    ("USDT", "tether"),
+    ("USDC", "usd-coin"),
+    ("FDUSD", "first-digital-usd"),
+    ("TUSD", "true-usd"),
+    ("DAI", "dai"),
+];
+
+/// 统一币种代码 -> Kraken 币种代码
+const KRAKEN_ASSETS: &[(&str, &str)] = &[("BTC", "XBT"), ("DOGE", "XDG")];
+
+/// CoinGecko 支持作为 vs_currency 的非美元计价币
+const COINGECKO_VS_CURRENCIES: &[&str] = &["BTC", "ETH", "BNB", "EUR", "GBP", "TRY", "BRL"];
+
+/// 统一格式的交易对 `BASE/QUOTE`，负责与各交易所的写法互相转换
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Symbol {
+    pub base: String,
+    pub quote: String,
+}
+
+impl Symbol {
+    pub fn new(base: &str, quote: &str) -> Self {
+        Self {
+            base: base.to_uppercase(),
+            quote: quote.to_uppercase(),
+        }
+    }
+
+    /// 解析交易对，兼容 `BTC/USDT`、`BTC-USDT`、`btc_usdt`、`BTCUSDT` 和只写基础币的 `BTC`
+    ///
+    /// 不带分隔符时，输入本身是已知基础币（如 `WBTC`）则视为 `BASE/USDT`，
+    /// 否则按计价币后缀拆分。能查询交易对注册表时使用 [`Symbol::parse_with`]。
+    pub fn parse(input: &str) -> Result<Self, MarketDataError> {
+        Self::parse_with(input, |_| false, |_, _| true)
+    }
+
+    /// 同 [`Symbol::parse`]，不带分隔符时参考交易所上架的交易对：
+    /// `is_base` 为真的输入视为 `BASE/USDT`，按后缀拆分时优先选择 `is_listed` 的交易对
+    pub fn parse_with(
+        input: &str,
+        is_base: impl Fn(&str) -> bool,
+        is_listed: impl Fn(&str, &str) -> bool,
+    ) -> Result<Self, MarketDataError> {
+        let upper = input.trim().to_uppercase();
+        let invalid = || MarketDataError::InvalidSymbol(input.to_string());
+
+        let (base, quote) = match upper.split_once(['/', '-', '_']) {
+            Some((base, quote)) => (base, quote),
+            None if is_known_base(&upper) || is_base(&upper) => (upper.as_str(), DEFAULT_QUOTE),
+            None => {
+                let mut splits = KNOWN_QUOTES.iter().filter_map(|quote| {
+                    upper
+                        .strip_suffix(quote)
+                        .filter(|base| !base.is_empty())
+                        .map(|base| (base, *quote))
+                });
+                let first = splits.clone().next();
+                splits
+                    .find(|(base, quote)| is_listed(base, quote))
+                    .or(first)
+                    .unwrap_or((upper.as_str(), DEFAULT_QUOTE))
+            }
+        };
+
+        let valid = |part: &str| {
+            !part.is_empty() && part.len() <= 20 && part.chars().all(|c| c.is_ascii_alphanumeric())
+        };
+        if !valid(base) || !valid(quote) {
+            return Err(invalid());
+        }
+        Ok(Self::new(base, quote))
+    }
+
+    /// OKX instId，如 `BTC-USDT`
+    pub fn okx(&self) -> String {
+        format!("{}-{}", self.base, self.quote)
+    }
+
+    /// OKX 永续合约 instId，如 `BTC-USDT-SWAP`
+    pub fn okx_swap(&self) -> String {
+        format!("{}-{}-SWAP", self.base, self.quote)
+    }
+
+    /// Binance symbol，如 `BTCUSDT`
+    pub fn binance(&self) -> String {
+        format!("{}{}", self.base, self.quote)
+    }
+
+    /// Bybit symbol，与 Binance 相同，如 `BTCUSDT`
+    pub fn bybit(&self) -> String {
+        self.binance()
+    }
+
+    /// Coinbase Exchange product id，美元稳定币计价映射为 `BTC-USD`
+    pub fn coinbase(&self) -> String {
+        if self.is_usd_quoted() {
+            format!("{}-USD", self.base)
+        } else {
+            format!("{}-{}", self.base, self.quote)
+        }
+    }
+
+    /// Kraken 交易对名称，如 `XBTUSDT`（BTC 写作 XBT）
+    pub fn kraken(&self) -> String {
+        format!("{}{}", kraken_asset(&self.base), kraken_asset(&self.quote))
+    }
+
+    /// 由 Kraken 的币种代码构造交易对，如 `XBT/USD` -> `BTC/USD`
+    pub fn from_kraken(base: &str, quote: &str) -> Self {
+        let standard = |asset: &str| {
+            KRAKEN_ASSETS
+                .iter()
+                .find(|(_, kraken)| *kraken == asset)
+                .map_or_else(|| asset.to_string(), |(standard, _)| standard.to_string())
+        };
+        Self::new(&standard(base), &standard(quote))
+    }
+
+    /// Yahoo Finance 代码，美元稳定币计价映射为 `BTC-USD`
+    pub fn yahoo(&self) -> String {
+        if self.is_usd_quoted() {
+            format!("{}-USD", self.base)
+        } else {
+            format!("{}-{}", self.base, self.quote)
+        }
+    }
+
+    /// CoinGecko coin id 和 vs_currency，未收录的币种返回 `None`
+    pub fn coingecko(&self) -> Option<(&'static str, String)> {
+        let coin_id = COINGECKO_IDS
+            .iter()
+            .find(|(base, _)| *base == self.base)
+            .map(|(_, id)| *id)?;
+        let vs_currency = if self.is_usd_quoted() {
+            "usd".to_string()
+        } else if COINGECKO_VS_CURRENCIES.contains(&self.quote.as_str()) {
+            self.quote.to_lowercase()
+        } else {
+            return None;
+        };
+        Some((coin_id, vs_currency))
+    }
+
+    pub fn is_usd_quoted(&self) -> bool {
+        USD_QUOTES.contains(&self.quote.as_str())
+    }
+}
+
+fn is_known_base(asset: &str) -> bool {
+    COINGECKO_IDS.iter().any(|(base, _)| *base == asset)
+}
+
+fn kraken_asset(asset: &str) -> &str {
+    KRAKEN_ASSETS
+        .iter()
+        .find(|(standard, _)| *standard == asset)
+        .map_or(asset, |(_, kraken)| kraken)
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.base, self.quote)
+    }
+}
+
+impl TryFrom<String> for Symbol {
+    type Error = MarketDataError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> String {
+        Symbol::parse(input).unwrap().to_string()
+    }
+
+    #[test]
+    fn parses_separated_and_compact_pairs() {
+        assert_eq!(parse("btc/usdt"), "BTC/USDT");
+        assert_eq!(parse("BTC-USDT"), "BTC/USDT");
+        assert_eq!(parse("eth_btc"), "ETH/BTC");
+        assert_eq!(parse("ETHBTC"), "ETH/BTC");
+        assert_eq!(parse("BTCFDUSD"), "BTC/FDUSD");
+        assert_eq!(parse("BTC"), "BTC/USDT");
+        assert!(Symbol::parse("BTC/US DT").is_err());
+    }
+
+    #[test]
+    fn known_bases_ending_in_a_quote_are_not_split() {
+        for base in ["WBTC", "STETH", "WETH", "TUSD", "FDUSD"] {
+            assert_eq!(parse(base), format!("{}/USDT", base));
+        }
+        assert_eq!(parse("WBTCUSDT"), "WBTC/USDT");
+        assert_eq!(parse("STETHETH"), "STETH/ETH");
+    }
+
+    #[test]
+    fn prefers_listed_instruments_for_compact_input() {
+        let resolve = |input: &str| {
+            let is_base = |base: &str| base == "XBTC";
+            let is_listed = |base: &str, quote: &str| (base, quote) == ("FOOT", "USD");
+            Symbol::parse_with(input, is_base, is_listed)
+                .unwrap()
+                .to_string()
+        };
+
+        // 注册表中的基础币不拆分
+        assert_eq!(resolve("XBTC"), "XBTC/USDT");
+        // 按后缀拆分时选择已上架的交易对（否则会拆成 FOO/TUSD）
+        assert_eq!(resolve("FOOTUSD"), "FOOT/USD");
+        // 没有上架的拆分方式时按最长后缀拆分
+        assert_eq!(resolve("BARUSDT"), "BAR/USDT");
+    }
+}
@@ -0,0 +1,36 @@
+//! 行情模块单元测试共用的构造函数和样本读取
+
+use rust_decimal::Decimal;
+use serde_json::Value;
+
+use super::KlineData;
+
+pub fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+/// 由 `[开, 高, 低, 收, 量]` 构建数据源为 `test` 的K线
+pub fn kline(timestamp: i64, ohlcv: [&str; 5]) -> KlineData {
+    let [open, high, low, close, volume] = ohlcv.map(dec);
+    KlineData {
+        timestamp,
+        open,
+        high,
+        low,
+        close,
+        volume,
+        source: "test".to_string(),
+    }
+}
+
+/// 读取 `tests/fixtures/<exchange>/<name>.json` 中录制的交易所响应
+pub fn fixture(exchange: &str, name: &str) -> Value {
+    let path = format!(
+        "{}/tests/fixtures/{}/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        exchange,
+        name
+    );
+    let text = std::fs::read_to_string(&path).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
@@ -0,0 +1,240 @@
+use futures_util::future::join_all;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use super::{MarketDataError, MarketDataRegistry, MarketDataSource, Symbol, Ticker};
+
+/// 默认缓存时间（秒）
+const DEFAULT_CACHE_SECS: u64 = 10;
+/// 市值默认缓存时间（秒），市值变化慢且 CoinGecko 限频严格
+const DEFAULT_MARKET_CAP_CACHE_SECS: u64 = 300;
+/// 市值数据来源，交易所行情不含市值
+const MARKET_CAP_SOURCE: &str = "coingecko";
+/// 缓存条目超过该数量时清理过期条目
+const MAX_CACHE_ENTRIES: usize = 1000;
+
+/// 缓存键：交易对 + 指定的数据源（`None` 表示按优先级回退）
+type CacheKey = (Symbol, Option<String>);
+
+struct CachedTicker {
+    ticker: Ticker,
+    fetched_at: Instant,
+}
+
+/// 缓存的市值，CoinGecko 未收录的交易对缓存为 `None`，避免重复请求
+struct CachedMarketCap {
+    market_cap: Option<Decimal>,
+    fetched_at: Instant,
+}
+
+/// 24小时行情服务：按数据源优先级回退获取行情，短时间缓存并支持批量查询
+///
+/// 交易所行情不包含市值时，用 CoinGecko 的市值补全；市值单独按更长的时间缓存，
+/// 批量查询中缺少的市值合并为一次请求。
+pub struct TickerService {
+    sources: Arc<MarketDataRegistry>,
+    ttl: Duration,
+    market_cap_ttl: Duration,
+    cache: RwLock<HashMap<CacheKey, CachedTicker>>,
+    market_caps: RwLock<HashMap<Symbol, CachedMarketCap>>,
+}
+
+impl TickerService {
+    pub fn new(sources: Arc<MarketDataRegistry>, ttl: Duration, market_cap_ttl: Duration) -> Self {
+        Self {
+            sources,
+            ttl,
+            market_cap_ttl,
+            cache: RwLock::new(HashMap::new()),
+            market_caps: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 行情和市值的缓存时间分别由 `TICKER_CACHE_SECS`、`MARKET_CAP_CACHE_SECS` 指定
+    pub fn from_env(sources: Arc<MarketDataRegistry>) -> Self {
+        let secs = |key: &str, default: u64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(default)
+        };
+        Self::new(
+            sources,
+            Duration::from_secs(secs("TICKER_CACHE_SECS", DEFAULT_CACHE_SECS)),
+            Duration::from_secs(secs("MARKET_CAP_CACHE_SECS", DEFAULT_MARKET_CAP_CACHE_SECS)),
+        )
+    }
+
+    /// 获取单个交易对的行情，`source` 为空时按数据源优先级回退
+    pub async fn get(
+        &self,
+        symbol: &Symbol,
+        source: Option<&str>,
+    ) -> Result<Ticker, MarketDataError> {
+        let mut ticker = self.get_exchange_ticker(symbol, source).await?;
+        if ticker.market_cap.is_none() {
+            ticker.market_cap = self
+                .market_caps(std::slice::from_ref(symbol))
+                .await
+                .remove(symbol);
+        }
+        Ok(ticker)
+    }
+
+    /// 并行获取多个交易对的行情，结果与请求顺序一致；缺少的市值合并为一次请求
+    pub async fn get_many(
+        &self,
+        requests: &[(Symbol, Option<String>)],
+    ) -> Vec<Result<Ticker, MarketDataError>> {
+        let mut results = join_all(
+            requests
+                .iter()
+                .map(|(symbol, source)| self.get_exchange_ticker(symbol, source.as_deref())),
+        )
+        .await;
+
+        let without_market_cap: Vec<Symbol> = requests
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| result.as_ref().is_ok_and(|t| t.market_cap.is_none()))
+            .map(|((symbol, _), _)| symbol.clone())
+            .collect();
+        if without_market_cap.is_empty() {
+            return results;
+        }
+
+        let market_caps = self.market_caps(&without_market_cap).await;
+        for ((symbol, _), result) in requests.iter().zip(results.iter_mut()) {
+            if let Ok(ticker) = result {
+                if ticker.market_cap.is_none() {
+                    ticker.market_cap = market_caps.get(symbol).copied();
+                }
+            }
+        }
+        results
+    }
+
+    /// 数据源返回的行情（走行情缓存），不补全市值
+    async fn get_exchange_ticker(
+        &self,
+        symbol: &Symbol,
+        source: Option<&str>,
+    ) -> Result<Ticker, MarketDataError> {
+        let key = (symbol.clone(), source.map(str::to_string));
+        if let Some(ticker) = self.cached(&key) {
+            return Ok(ticker);
+        }
+
+        let ticker = match source {
+            Some(name) => self.sources.get(name)?.fetch_ticker(symbol).await?,
+            None => self.fetch_with_fallback(symbol).await?,
+        };
+        self.store(key, ticker.clone());
+        Ok(ticker)
+    }
+
+    fn cached(&self, key: &CacheKey) -> Option<Ticker> {
+        self.cache
+            .read()
+            .unwrap()
+            .get(key)
+            .filter(|cached| cached.fetched_at.elapsed() < self.ttl)
+            .map(|cached| cached.ticker.clone())
+    }
+
+    fn store(&self, key: CacheKey, ticker: Ticker) {
+        let mut cache = self.cache.write().unwrap();
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.retain(|_, cached| cached.fetched_at.elapsed() < self.ttl);
+        }
+        cache.insert(
+            key,
+            CachedTicker {
+                ticker,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    /// 按优先级尝试各数据源；全部因参数问题失败时返回第一个参数错误
+    async fn fetch_with_fallback(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        let mut client_error = None;
+        let mut last_error = None;
+        for source in self.sources.sources() {
+            if !source.capabilities().ticker {
+                continue;
+            }
+            match source.fetch_ticker(symbol).await {
+                Ok(ticker) => return Ok(ticker),
+                Err(e) => {
+                    println!("行情: 源 {} 失败: {}", source.name(), e);
+                    if e.is_client_error() {
+                        client_error.get_or_insert(e);
+                    } else {
+                        last_error = Some(e);
+                    }
+                }
+            }
+        }
+        Err(last_error
+            .or(client_error)
+            .unwrap_or(MarketDataError::Unsupported {
+                exchange: "market",
+                operation: "24小时行情",
+            }))
+    }
+
+    /// 批量获取市值：先查市值缓存，未命中的交易对合并为一次 CoinGecko 请求
+    ///
+    /// 获取失败或未收录的交易对不在结果中。
+    async fn market_caps(&self, symbols: &[Symbol]) -> HashMap<Symbol, Decimal> {
+        let mut result = HashMap::new();
+        let mut misses: Vec<Symbol> = Vec::new();
+        {
+            let cache = self.market_caps.read().unwrap();
+            for symbol in symbols {
+                match cache
+                    .get(symbol)
+                    .filter(|cached| cached.fetched_at.elapsed() < self.market_cap_ttl)
+                {
+                    Some(cached) => result.extend(cached.market_cap.map(|m| (symbol.clone(), m))),
+                    None if !misses.contains(symbol) => misses.push(symbol.clone()),
+                    None => {}
+                }
+            }
+        }
+        if misses.is_empty() {
+            return result;
+        }
+
+        let Ok(source) = self.sources.get(MARKET_CAP_SOURCE) else {
+            return result;
+        };
+        let fetched = match source.fetch_market_caps(&misses).await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                println!("⚠️ 市值获取失败: {}", e);
+                return result;
+            }
+        };
+
+        let mut cache = self.market_caps.write().unwrap();
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.retain(|_, cached| cached.fetched_at.elapsed() < self.market_cap_ttl);
+        }
+        for symbol in misses {
+            let market_cap = fetched.get(&symbol).copied();
+            cache.insert(
+                symbol.clone(),
+                CachedMarketCap {
+                    market_cap,
+                    fetched_at: Instant::now(),
+                },
+            );
+            result.extend(market_cap.map(|m| (symbol, m)));
+        }
+        result
+    }
+}
@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::decimal::{self, percent_change};
+use super::http::{build_client, get_json, num_decimal, HttpClient};
+use super::interval::map_interval;
+use super::rate_limit::{BucketSpec, RateLimitRules, RateLimiter, RequestCost};
+use super::replay::HttpMode;
+use super::{
+    KlineData, KlineQuery, MarketDataError, MarketDataSource, SourceCapabilities, Symbol, Ticker,
+};
+
+const NAME: &str = "yahoo";
+const BASE_URL: &str = "https://query1.finance.yahoo.com";
+/// 标准周期 -> Yahoo interval 参数
+const INTERVALS: &[(&str, &str)] = &[
+    ("1m", "1m"),
+    ("5m", "5m"),
+    ("15m", "15m"),
+    ("30m", "30m"),
+    ("1h", "1h"),
+    ("1d", "1d"),
+    ("1w", "1wk"),
+    ("1M", "1mo"),
+];
+
+/// 未公开限频规则，按每分钟60次保守限制
+static RATE_LIMITS: RateLimitRules = RateLimitRules {
+    buckets: &[BucketSpec::new("public", 60, Duration::from_secs(60))],
+    cost: |_| RequestCost::new("public", 1),
+    used_weight_headers: &[],
+};
+
+pub struct YahooSource {
+    client: HttpClient,
+    limiter: RateLimiter,
+}
+
+impl YahooSource {
+    pub fn new(mode: &HttpMode) -> Self {
+        Self {
+            client: build_client(NAME, mode),
+            limiter: RateLimiter::new(NAME, &RATE_LIMITS),
+        }
+    }
+
+    /// 代码不存在时返回 404
+    fn symbol_error(error: MarketDataError, symbol: &Symbol) -> MarketDataError {
+        match &error {
+            MarketDataError::Status { status, .. } if *status == reqwest::StatusCode::NOT_FOUND => {
+                MarketDataError::unknown_symbol(NAME, symbol)
+            }
+            _ => error,
+        }
+    }
+
+    fn chart_result(data: &Value) -> Result<&Value, MarketDataError> {
+        let result = &data["chart"]["result"][0];
+        if result.is_object() {
+            Ok(result)
+        } else {
+            Err(MarketDataError::parse(NAME, "No result data"))
+        }
+    }
+}
+
+impl Default for YahooSource {
+    fn default() -> Self {
+        Self::new(&HttpMode::Live)
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for YahooSource {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn capabilities(&self) -> SourceCapabilities {
+        SourceCapabilities {
+            klines: true,
+            ticker: true,
+            order_book: false,
+            trades: false,
+            native_ohlc: true,
+            // 加密货币的成交量以美元计价
+            base_volume: false,
+            max_klines_per_request: 24,
+            history_page_size: Some(1000),
+            perpetuals: false,
+        }
+    }
+
+    fn rate_limiter(&self) -> Option<&RateLimiter> {
+        Some(&self.limiter)
+    }
+
+    fn supported_intervals(&self) -> Vec<&'static str> {
+        INTERVALS.iter().map(|(standard, _)| *standard).collect()
+    }
+
+    async fn fetch_klines(&self, query: &KlineQuery) -> Result<Vec<KlineData>, MarketDataError> {
+        let interval = map_interval(NAME, INTERVALS, &query.interval)?;
+
+        let end_time = query
+            .end_time
+            .map(|ts| ts / 1000)
+            .unwrap_or_else(|| chrono::Utc::now().timestamp());
+        let start_time = query
+            .start_time
+            .map(|ts| ts / 1000)
+            .unwrap_or(end_time - 86400); // 默认1天前
+
+        let url = format!(
+            "{}/v8/finance/chart/{}?period1={}&period2={}&interval={}",
+            BASE_URL,
+            query.symbol.yahoo(),
+            start_time,
+            end_time,
+            interval
+        );
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, &query.symbol))?;
+        let result = Self::chart_result(&data)?;
+
+        let missing = |field: &str| MarketDataError::parse(NAME, format!("No {} data", field));
+        let timestamps = result["timestamp"]
+            .as_array()
+            .ok_or_else(|| missing("timestamp"))?;
+        let indicators = &result["indicators"]["quote"][0];
+
+        let opens = indicators["open"]
+            .as_array()
+            .ok_or_else(|| missing("open"))?;
+        let highs = indicators["high"]
+            .as_array()
+            .ok_or_else(|| missing("high"))?;
+        let lows = indicators["low"].as_array().ok_or_else(|| missing("low"))?;
+        let closes = indicators["close"]
+            .as_array()
+            .ok_or_else(|| missing("close"))?;
+        let volumes = indicators["volume"]
+            .as_array()
+            .ok_or_else(|| missing("volume"))?;
+
+        let mut klines = Vec::new();
+        for (i, timestamp) in timestamps.iter().enumerate() {
+            let field = |values: &Vec<Value>| values.get(i).and_then(decimal::from_json);
+            if let (Some(ts), Some(o), Some(h), Some(l), Some(c), Some(v)) = (
+                timestamp.as_i64(),
+                field(opens),
+                field(highs),
+                field(lows),
+                field(closes),
+                field(volumes),
+            ) {
+                klines.push(KlineData {
+                    timestamp: ts * 1000, // 转换为毫秒
+                    open: o,
+                    high: h,
+                    low: l,
+                    close: c,
+                    volume: v,
+                    source: NAME.to_string(),
+                });
+            }
+        }
+
+        Ok(klines)
+    }
+
+    async fn fetch_ticker(&self, symbol: &Symbol) -> Result<Ticker, MarketDataError> {
+        let url = format!(
+            "{}/v8/finance/chart/{}?range=1d&interval=1d",
+            BASE_URL,
+            symbol.yahoo()
+        );
+
+        let data = get_json(&self.client, &self.limiter, &url)
+            .await
+            .map_err(|e| Self::symbol_error(e, symbol))?;
+        let meta = &Self::chart_result(&data)?["meta"];
+
+        let price = num_decimal(&meta["regularMarketPrice"], NAME, "regularMarketPrice")?;
+        let previous_close =
+            decimal::from_json(&meta["chartPreviousClose"]).filter(|prev| !prev.is_zero());
+
+        Ok(Ticker {
+            symbol: symbol.to_string(),
+            price,
+            price_change_24h: previous_close.map(|prev| price - prev),
+            price_change_percent_24h: previous_close.and_then(|prev| percent_change(price, prev)),
+            high_24h: decimal::from_json(&meta["regularMarketDayHigh"]),
+            low_24h: decimal::from_json(&meta["regularMarketDayLow"]),
+            volume_24h: decimal::from_json(&meta["regularMarketVolume"]),
+            quote_volume_24h: None,
+            market_cap: None,
+            timestamp: meta["regularMarketTime"]
+                .as_i64()
+                .map(|ts| ts * 1000)
+                .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+            source: NAME.to_string(),
+        })
+    }
+}
@@ -0,0 +1,245 @@
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::models::{market_overview_daily, MarketOverviewDaily};
+use crate::services::market_data::fear_greed::{FearGreedClient, FearGreedIndex};
+use crate::services::market_data::{
+    GlobalMarketMetrics, MarketDataError, MarketDataRegistry, MarketDataSource,
+};
+
+/// 默认缓存时间（秒），CoinGecko 的全市场数据约每几分钟更新一次
+const DEFAULT_CACHE_SECS: u64 = 300;
+/// 默认后台刷新间隔（秒），保证没有访问时也能记录每日历史
+const DEFAULT_REFRESH_SECS: u64 = 3600;
+
+/// 全市场概览，字段名与前端 `MarketStats` 一致
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketOverview {
+    /// 总市值（美元）
+    pub total_market_cap: f64,
+    /// 24小时总成交额（美元）
+    pub total_volume_24h: f64,
+    /// BTC 市值占比（百分比）
+    pub btc_dominance: f64,
+    /// ETH 市值占比（百分比）
+    pub eth_dominance: f64,
+    /// 恐惧贪婪指数（0-100），获取失败时为空
+    pub fear_greed_index: Option<u8>,
+    pub fear_greed_label: Option<String>,
+    pub timestamp: i64,
+    /// 全市场统计的数据源
+    pub source: String,
+}
+
+/// 每日概览历史（每个UTC日取当天最后一次快照）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketOverviewDay {
+    /// `YYYY-MM-DD`
+    pub date: NaiveDate,
+    pub total_market_cap: f64,
+    pub total_volume_24h: f64,
+    pub btc_dominance: f64,
+    pub eth_dominance: f64,
+    pub fear_greed_index: Option<u8>,
+    pub fear_greed_label: Option<String>,
+    pub source: String,
+}
+
+struct CachedOverview {
+    overview: MarketOverview,
+    fetched_at: Instant,
+}
+
+/// 全市场概览服务：从聚合数据源获取总市值、成交额和市值占比，
+/// 附加恐惧贪婪指数，短时间缓存并按日写入历史表
+pub struct MarketOverviewService {
+    db: DatabaseConnection,
+    sources: Arc<MarketDataRegistry>,
+    fear_greed: FearGreedClient,
+    ttl: Duration,
+    cache: RwLock<Option<CachedOverview>>,
+}
+
+impl MarketOverviewService {
+    pub fn new(db: DatabaseConnection, sources: Arc<MarketDataRegistry>, ttl: Duration) -> Self {
+        Self {
+            db,
+            fear_greed: FearGreedClient::new(sources.http_mode()),
+            sources,
+            ttl,
+            cache: RwLock::new(None),
+        }
+    }
+
+    /// 缓存时间由 `MARKET_OVERVIEW_CACHE_SECS` 指定
+    pub fn from_env(db: DatabaseConnection, sources: Arc<MarketDataRegistry>) -> Self {
+        let ttl = std::env::var("MARKET_OVERVIEW_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_SECS);
+        Self::new(db, sources, Duration::from_secs(ttl))
+    }
+
+    /// 按 `MARKET_OVERVIEW_REFRESH_SECS` 定期刷新并记录当日快照
+    pub fn spawn_refresh(self: Arc<Self>) {
+        let period = std::env::var("MARKET_OVERVIEW_REFRESH_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_REFRESH_SECS);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(period.max(60)));
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.refresh().await {
+                    log::warn!("全市场概览刷新失败: {}", e);
+                }
+            }
+        });
+    }
+
+    /// 当前概览，缓存未过期时直接返回
+    pub async fn get(&self) -> Result<MarketOverview, MarketDataError> {
+        if let Some(cached) = self.cache.read().unwrap().as_ref() {
+            if cached.fetched_at.elapsed() < self.ttl {
+                return Ok(cached.overview.clone());
+            }
+        }
+        self.refresh().await
+    }
+
+    /// 重新获取概览，更新缓存并写入当日历史
+    pub async fn refresh(&self) -> Result<MarketOverview, MarketDataError> {
+        let (metrics, fear_greed) =
+            tokio::join!(self.fetch_metrics_with_fallback(), self.fear_greed.latest());
+        let metrics = metrics?;
+        let fear_greed = match fear_greed {
+            Ok(index) => Some(index),
+            Err(e) => {
+                println!("恐惧贪婪指数获取失败: {}", e);
+                None
+            }
+        };
+        let overview = build_overview(metrics, fear_greed);
+
+        if let Err(e) = self.save_day(&overview).await {
+            log::warn!("全市场概览历史写入失败: {}", e);
+        }
+        *self.cache.write().unwrap() = Some(CachedOverview {
+            overview: overview.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(overview)
+    }
+
+    /// 最近 `days` 天的每日概览，按日期升序
+    pub async fn history(&self, days: u32) -> Result<Vec<MarketOverviewDay>, DbErr> {
+        let since = Utc::now().date_naive() - ChronoDuration::days(i64::from(days.max(1)) - 1);
+        let rows = MarketOverviewDaily::find()
+            .filter(market_overview_daily::Column::Date.gte(since))
+            .order_by_asc(market_overview_daily::Column::Date)
+            .all(&self.db)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| MarketOverviewDay {
+                date: r.date,
+                total_market_cap: r.total_market_cap.to_f64().unwrap_or_default(),
+                total_volume_24h: r.total_volume24h.to_f64().unwrap_or_default(),
+                btc_dominance: r.btc_dominance.to_f64().unwrap_or_default(),
+                eth_dominance: r.eth_dominance.to_f64().unwrap_or_default(),
+                fear_greed_index: r.fear_greed_index.and_then(|v| u8::try_from(v).ok()),
+                fear_greed_label: r.fear_greed_label,
+                source: r.source,
+            })
+            .collect())
+    }
+
+    async fn fetch_metrics_with_fallback(&self) -> Result<GlobalMarketMetrics, MarketDataError> {
+        let mut last_error = None;
+        for source in self.sources.sources() {
+            match source.fetch_global_metrics().await {
+                Ok(metrics) => return Ok(metrics),
+                Err(MarketDataError::Unsupported { .. }) => continue,
+                Err(e) => {
+                    println!("全市场统计: 源 {} 失败: {}", source.name(), e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or(MarketDataError::Unsupported {
+            exchange: "market",
+            operation: "全市场统计",
+        }))
+    }
+
+    /// 同一天多次写入时保留最后一次快照
+    async fn save_day(&self, overview: &MarketOverview) -> Result<(), DbErr> {
+        let date = DateTime::from_timestamp_millis(overview.timestamp)
+            .unwrap_or_else(Utc::now)
+            .date_naive();
+        let model = market_overview_daily::ActiveModel {
+            date: Set(date),
+            total_market_cap: Set(to_decimal(overview.total_market_cap)),
+            total_volume24h: Set(to_decimal(overview.total_volume_24h)),
+            btc_dominance: Set(to_decimal(overview.btc_dominance)),
+            eth_dominance: Set(to_decimal(overview.eth_dominance)),
+            fear_greed_index: Set(overview.fear_greed_index.map(i32::from)),
+            fear_greed_label: Set(overview.fear_greed_label.clone()),
+            source: Set(overview.source.clone()),
+            updated_at: Set(Utc::now().into()),
+            ..Default::default()
+        };
+
+        MarketOverviewDaily::insert(model)
+            .on_conflict(
+                OnConflict::column(market_overview_daily::Column::Date)
+                    .update_columns([
+                        market_overview_daily::Column::TotalMarketCap,
+                        market_overview_daily::Column::TotalVolume24h,
+                        market_overview_daily::Column::BtcDominance,
+                        market_overview_daily::Column::EthDominance,
+                        market_overview_daily::Column::FearGreedIndex,
+                        market_overview_daily::Column::FearGreedLabel,
+                        market_overview_daily::Column::Source,
+                        market_overview_daily::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+fn build_overview(
+    metrics: GlobalMarketMetrics,
+    fear_greed: Option<FearGreedIndex>,
+) -> MarketOverview {
+    MarketOverview {
+        total_market_cap: metrics.total_market_cap,
+        total_volume_24h: metrics.total_volume_24h,
+        btc_dominance: metrics.btc_dominance,
+        eth_dominance: metrics.eth_dominance,
+        fear_greed_index: fear_greed.as_ref().map(|f| f.value),
+        fear_greed_label: fear_greed.map(|f| f.classification),
+        timestamp: metrics.timestamp,
+        source: metrics.source,
+    }
+}
+
+fn to_decimal(value: f64) -> Decimal {
+    Decimal::from_str(&value.to_string()).unwrap_or_default()
+}
@@ -1,3 +1,16 @@
 pub mod auth;
+pub mod candle_import;
+pub mod candle_store;
+pub mod coverage_store;
+pub mod funding_store;
+pub mod market_data;
+pub mod market_overview;
 
 pub use auth::AuthService;
+pub use candle_import::CandleImporter;
+pub use candle_store::CandleStore;
+pub use funding_store::FundingStore;
+pub use market_data::{
+    Clock, HttpMode, InstrumentRegistry, MarketDataRegistry, MarketStream, TickerService,
+};
+pub use market_overview::MarketOverviewService;